@@ -0,0 +1,100 @@
+use battleship_rs::{
+    engine::Board,
+    intel,
+    types::{CellState, GRID_SIZE, Message},
+};
+use criterion::{Criterion, criterion_group, criterion_main};
+use rand::{SeedableRng, rngs::StdRng};
+use std::hint::black_box;
+
+/// A board halfway through a game: every other cell has been fired at.
+struct Midgame {
+    /// The defender's grid.
+    grid: Board,
+    /// The attacker's view of it.
+    view: Vec<Vec<CellState>>,
+    /// Where each sinking shot landed.
+    sinks: Vec<(usize, usize)>,
+}
+
+fn midgame() -> Midgame {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut grid = Board::random(&mut rng);
+    let mut view = vec![vec![CellState::Empty; GRID_SIZE]; GRID_SIZE];
+    let mut sinks = Vec::new();
+    for y in 0..GRID_SIZE {
+        for x in (y % 2..GRID_SIZE).step_by(2) {
+            let shot = grid.fire(x, y);
+            view[y][x] = grid[y][x];
+            if shot.sunk {
+                sinks.push((x, y));
+            }
+        }
+    }
+    Midgame { grid, view, sinks }
+}
+
+fn placement(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(1);
+    c.bench_function("Board::random", |b| b.iter(|| Board::random(&mut rng)));
+}
+
+fn sink_detection(c: &mut Criterion) {
+    let Midgame { grid, .. } = midgame();
+    let hits: Vec<(usize, usize)> = (0..GRID_SIZE)
+        .flat_map(|y| (0..GRID_SIZE).map(move |x| (x, y)))
+        .filter(|&(x, y)| grid[y][x] == CellState::Hit)
+        .collect();
+    c.bench_function("Board::ship_at (all hits)", |b| {
+        b.iter(|| {
+            hits.iter()
+                .filter(|&&(x, y)| {
+                    black_box(&grid)
+                        .ship_at(x, y)
+                        .is_some_and(|ship| ship.is_sunk())
+                })
+                .count()
+        })
+    });
+    c.bench_function("Board::all_sunk", |b| {
+        b.iter(|| black_box(&grid).all_sunk())
+    });
+}
+
+fn deduction(c: &mut Criterion) {
+    let Midgame { view, sinks, .. } = midgame();
+    c.bench_function("intel::deduce", |b| {
+        b.iter(|| intel::deduce(black_box(&view), black_box(&sinks)))
+    });
+}
+
+fn serialization(c: &mut Criterion) {
+    let Midgame { grid, .. } = midgame();
+    let attack = Message::AttackResult {
+        x: 3,
+        y: 7,
+        hit: true,
+        sunk: false,
+        ship: None,
+        revealed: Vec::new(),
+    };
+    let placement = Message::PlaceShips(grid);
+    let attack_json = serde_json::to_string(&attack).unwrap();
+    let placement_json = serde_json::to_string(&placement).unwrap();
+
+    c.bench_function("serialize AttackResult", |b| {
+        b.iter(|| serde_json::to_string(black_box(&attack)).unwrap())
+    });
+    c.bench_function("deserialize AttackResult", |b| {
+        b.iter(|| serde_json::from_str::<Message>(black_box(&attack_json)).unwrap())
+    });
+    c.bench_function("serialize PlaceShips", |b| {
+        b.iter(|| serde_json::to_string(black_box(&placement)).unwrap())
+    });
+    c.bench_function("deserialize PlaceShips", |b| {
+        b.iter(|| serde_json::from_str::<Message>(black_box(&placement_json)).unwrap())
+    });
+}
+
+criterion_group!(benches, placement, sink_detection, deduction, serialization);
+criterion_main!(benches);
@@ -0,0 +1,409 @@
+//! How the AI plays. Each [`AiStrategy`] places a fleet and picks shots from
+//! nothing but an [`AiView`] of the player's board, so strategies can be
+//! swapped or added without touching the servers that run them.
+
+use anyhow::{Result, bail};
+use rand::{Rng, RngCore, seq::IndexedRandom};
+use std::str::FromStr;
+
+use crate::ai_audit::AiView;
+use crate::engine::Board;
+use crate::types::{CellState, GRID_SIZE, SHIPS};
+
+/// One way of playing the AI's side of a game. The built-in strategies never
+/// fail; an [`ExternalBot`](crate::bot::ExternalBot) does when its process
+/// stops answering sensibly.
+pub trait AiStrategy: Send {
+    /// The AI's fleet for a new game. Forgets anything learned in the last one.
+    fn place_ships(&mut self, rng: &mut dyn RngCore) -> Result<Board>;
+
+    /// Where to fire next. Must be a cell `view` shows as not fired at.
+    fn choose_shot(&mut self, view: &AiView, rng: &mut dyn RngCore) -> Result<(usize, usize)>;
+
+    /// Learns how the shot at (x, y) went. `sunk` is the length of the ship
+    /// it sank, if it sank one.
+    fn record_shot(
+        &mut self,
+        _x: usize,
+        _y: usize,
+        _hit: bool,
+        _sunk: Option<usize>,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A random cell the AI has not fired at.
+fn random_cell(view: &AiView, rng: &mut dyn RngCore) -> (usize, usize) {
+    loop {
+        let x = rng.random_range(0..GRID_SIZE);
+        let y = rng.random_range(0..GRID_SIZE);
+        if view.cell(x, y) == CellState::Empty {
+            return (x, y);
+        }
+    }
+}
+
+/// Every shot at random.
+#[derive(Default)]
+pub struct RandomAi;
+
+impl AiStrategy for RandomAi {
+    fn place_ships(&mut self, mut rng: &mut dyn RngCore) -> Result<Board> {
+        Ok(Board::random(&mut rng))
+    }
+
+    fn choose_shot(&mut self, view: &AiView, rng: &mut dyn RngCore) -> Result<(usize, usize)> {
+        Ok(random_cell(view, rng))
+    }
+}
+
+/// Hunt-and-target shooting. The AI searches at random until it hits
+/// something, then tries the cells around the hit. Once two hits line up it
+/// keeps firing along the line until the ship sinks, and then goes back to
+/// searching.
+#[derive(Default)]
+pub struct Hunter {
+    /// Hits on ships that have not sunk yet, oldest first.
+    wounded: Vec<(usize, usize)>,
+}
+
+impl AiStrategy for Hunter {
+    fn place_ships(&mut self, mut rng: &mut dyn RngCore) -> Result<Board> {
+        *self = Self::default();
+        Ok(Board::random(&mut rng))
+    }
+
+    /// Past either end of a line of hits, else next to the oldest hit, else
+    /// anywhere.
+    fn choose_shot(&mut self, view: &AiView, rng: &mut dyn RngCore) -> Result<(usize, usize)> {
+        let open = |&(x, y): &(usize, usize)| view.cell(x, y) == CellState::Empty;
+        let line_ends = self.line_ends().into_iter().find(open);
+        let next_to_hit = || {
+            self.wounded
+                .iter()
+                .flat_map(|&cell| {
+                    [(0, -1), (1, 0), (0, 1), (-1, 0)].map(|direction| step(cell, direction))
+                })
+                .flatten()
+                .find(open)
+        };
+        Ok(line_ends
+            .or_else(next_to_hit)
+            .unwrap_or_else(|| random_cell(view, rng)))
+    }
+
+    fn record_shot(&mut self, x: usize, y: usize, hit: bool, sunk: Option<usize>) -> Result<()> {
+        self.learn(x, y, hit, sunk);
+        Ok(())
+    }
+}
+
+impl Hunter {
+    /// Records a shot as `record_shot` does, returning the cells of the ship
+    /// it sank, if any.
+    fn learn(&mut self, x: usize, y: usize, hit: bool, sunk: Option<usize>) -> Vec<(usize, usize)> {
+        if !hit {
+            return Vec::new();
+        }
+        self.wounded.push((x, y));
+        let Some(length) = sunk else {
+            return Vec::new();
+        };
+        // The sunk ship is the run of hits through (x, y) that is long
+        // enough; when ships touch in a line, the hits nearest (x, y)
+        let mut run = [(1, 0), (0, 1)]
+            .into_iter()
+            .map(|direction| self.run_through((x, y), direction))
+            .find(|run| run.len() >= length)
+            .unwrap_or_else(|| vec![(x, y)]);
+        run.sort_by_key(|&(cx, cy)| cx.abs_diff(x) + cy.abs_diff(y));
+        run.truncate(length);
+        self.wounded.retain(|cell| !run.contains(cell));
+        run
+    }
+
+    /// The unsunk hits in a line with (x, y) along `direction`, unbroken.
+    fn run_through(&self, cell: (usize, usize), (dx, dy): (isize, isize)) -> Vec<(usize, usize)> {
+        let mut run = vec![cell];
+        for direction in [(dx, dy), (-dx, -dy)] {
+            let mut at = cell;
+            while let Some(next) = step(at, direction).filter(|next| self.wounded.contains(next)) {
+                run.push(next);
+                at = next;
+            }
+        }
+        run
+    }
+
+    /// The cells just past either end of each line of two or more unsunk hits.
+    fn line_ends(&self) -> Vec<(usize, usize)> {
+        let mut ends = Vec::new();
+        for &cell in &self.wounded {
+            for (dx, dy) in [(1, 0), (0, 1)] {
+                let run = self.run_through(cell, (dx, dy));
+                if run.len() < 2 {
+                    continue;
+                }
+                for &at in &run {
+                    for direction in [(dx, dy), (-dx, -dy)] {
+                        if let Some(next) = step(at, direction)
+                            && !run.contains(&next)
+                            && !ends.contains(&next)
+                        {
+                            ends.push(next);
+                        }
+                    }
+                }
+            }
+        }
+        ends
+    }
+}
+
+/// How much more likely a placement is for each unsunk hit it covers, so
+/// the heatmap finishes off a ship it has found before searching elsewhere.
+const HIT_WEIGHT: u64 = 20;
+
+/// Probability-density shooting. Each turn the AI counts, for every cell it
+/// has not fired at, the ways the ships still afloat could lie across it
+/// without covering a miss or a sunk ship, and fires at the likeliest cell.
+pub struct Heatmap {
+    hunter: Hunter,
+    /// Cells of the ships it has sunk.
+    sunk: Vec<(usize, usize)>,
+    /// Lengths of the ships still afloat.
+    afloat: Vec<usize>,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Self {
+            hunter: Hunter::default(),
+            sunk: Vec::new(),
+            afloat: SHIPS.iter().map(|(length, _)| *length).collect(),
+        }
+    }
+}
+
+impl Heatmap {
+    /// How many ways the ships still afloat could lie across each cell
+    /// `view` shows as not fired at, weighted towards unsunk hits. Cells
+    /// already fired at, or that no ship could cover, weigh nothing.
+    pub fn weights(&self, view: &AiView) -> Vec<Vec<u64>> {
+        let known: Vec<Vec<CellState>> = (0..GRID_SIZE)
+            .map(|y| (0..GRID_SIZE).map(|x| view.cell(x, y)).collect())
+            .collect();
+        let mut weights = vec![vec![0u64; GRID_SIZE]; GRID_SIZE];
+        for &length in &self.afloat {
+            for (x, y, horizontal) in (0..GRID_SIZE)
+                .flat_map(|y| (0..GRID_SIZE).map(move |x| (x, y)))
+                .flat_map(|(x, y)| [(x, y, true), (x, y, false)])
+            {
+                let Some(cells) = Board::span(x, y, length, horizontal) else {
+                    continue;
+                };
+                let cells: Vec<(usize, usize)> = cells.collect();
+                if cells.iter().any(|&(cx, cy)| {
+                    known[cy][cx] == CellState::Miss || self.sunk.contains(&(cx, cy))
+                }) {
+                    continue;
+                }
+                let covered = cells
+                    .iter()
+                    .filter(|&&(cx, cy)| known[cy][cx] == CellState::Hit)
+                    .count();
+                let weight = HIT_WEIGHT.pow(covered as u32);
+                for &(cx, cy) in &cells {
+                    if known[cy][cx] == CellState::Empty {
+                        weights[cy][cx] += weight;
+                    }
+                }
+            }
+        }
+        weights
+    }
+}
+
+impl AiStrategy for Heatmap {
+    fn place_ships(&mut self, mut rng: &mut dyn RngCore) -> Result<Board> {
+        *self = Self::default();
+        Ok(Board::random(&mut rng))
+    }
+
+    fn choose_shot(&mut self, view: &AiView, rng: &mut dyn RngCore) -> Result<(usize, usize)> {
+        let weights = self.weights(view);
+        let best = weights.iter().flatten().copied().max().unwrap_or(0);
+        let likeliest: Vec<(usize, usize)> = (0..GRID_SIZE)
+            .flat_map(|y| (0..GRID_SIZE).map(move |x| (x, y)))
+            .filter(|&(x, y)| best > 0 && weights[y][x] == best)
+            .collect();
+        Ok(likeliest
+            .choose(rng)
+            .copied()
+            .unwrap_or_else(|| random_cell(view, rng)))
+    }
+
+    fn record_shot(&mut self, x: usize, y: usize, hit: bool, sunk: Option<usize>) -> Result<()> {
+        let cells = self.hunter.learn(x, y, hit, sunk);
+        if let Some(length) = sunk
+            && let Some(i) = self.afloat.iter().position(|&afloat| afloat == length)
+        {
+            self.afloat.remove(i);
+        }
+        self.sunk.extend(cells);
+        Ok(())
+    }
+}
+
+/// How well the AI shoots.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Difficulty {
+    /// Random shots.
+    Easy,
+    /// Hunt-and-target, see `Hunter`.
+    #[default]
+    Normal,
+    /// Probability density, see `Heatmap`.
+    Hard,
+}
+
+impl Difficulty {
+    /// The name `--difficulty` takes.
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    /// A fresh strategy that plays at this difficulty.
+    pub fn strategy(self) -> Box<dyn AiStrategy> {
+        match self {
+            Difficulty::Easy => Box::new(RandomAi),
+            Difficulty::Normal => Box::new(Hunter::default()),
+            Difficulty::Hard => Box::new(Heatmap::default()),
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            // Or by the name of the strategy
+            "easy" | "random" => Ok(Difficulty::Easy),
+            "normal" | "hunt" => Ok(Difficulty::Normal),
+            "hard" | "heatmap" => Ok(Difficulty::Hard),
+            _ => bail!(
+                "unknown difficulty '{}', expected 'easy', 'normal' or 'hard' \
+                 ('random', 'hunt' or 'heatmap')",
+                s
+            ),
+        }
+    }
+}
+
+/// The cell one step from `cell` in `direction`, if it is on the board.
+fn step((x, y): (usize, usize), (dx, dy): (isize, isize)) -> Option<(usize, usize)> {
+    let nx = x.checked_add_signed(dx).filter(|&nx| nx < GRID_SIZE)?;
+    let ny = y.checked_add_signed(dy).filter(|&ny| ny < GRID_SIZE)?;
+    Some((nx, ny))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    /// Plays `strategy` against a random fleet until it sinks it, checking
+    /// every shot is on the board and new. Returns the number of shots.
+    fn play_out(mut strategy: impl AiStrategy, seed: u64) -> usize {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut board = Board::random(&mut rng);
+        let mut view = AiView::new();
+        let mut fired = Vec::new();
+        while !board.all_sunk() {
+            let (x, y) = strategy.choose_shot(&view, &mut rng).unwrap();
+            assert!(
+                x < GRID_SIZE && y < GRID_SIZE,
+                "shot off the board at ({x}, {y})"
+            );
+            assert!(!fired.contains(&(x, y)), "fired at ({x}, {y}) twice");
+            fired.push((x, y));
+            let shot = board.fire(x, y);
+            view.record_shot(x, y, shot.hit);
+            let sunk = shot.ship.filter(|_| shot.sunk).map(|kind| SHIPS[kind].0);
+            strategy.record_shot(x, y, shot.hit, sunk).unwrap();
+        }
+        fired.len()
+    }
+
+    #[test]
+    fn random_ai_fires_at_new_cells_on_the_board() {
+        for seed in 0..10 {
+            assert!(play_out(RandomAi, seed) <= GRID_SIZE * GRID_SIZE);
+        }
+    }
+
+    #[test]
+    fn hunter_fires_at_new_cells_on_the_board() {
+        for seed in 0..10 {
+            assert!(play_out(Hunter::default(), seed) <= GRID_SIZE * GRID_SIZE);
+        }
+    }
+
+    #[test]
+    fn heatmap_fires_at_new_cells_on_the_board() {
+        for seed in 0..10 {
+            assert!(play_out(Heatmap::default(), seed) <= GRID_SIZE * GRID_SIZE);
+        }
+    }
+
+    #[test]
+    fn hunter_fires_next_to_a_hit() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut hunter = Hunter::default();
+        let mut view = AiView::new();
+        view.record_shot(5, 4, true);
+        hunter.record_shot(5, 4, true, None).unwrap();
+        for _ in 0..20 {
+            let (x, y) = hunter.choose_shot(&view, &mut rng).unwrap();
+            assert_eq!(
+                x.abs_diff(5) + y.abs_diff(4),
+                1,
+                "({x}, {y}) is not next to the hit"
+            );
+        }
+    }
+
+    #[test]
+    fn hunter_follows_a_line_of_hits() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut hunter = Hunter::default();
+        let mut view = AiView::new();
+        for x in [5, 6] {
+            view.record_shot(x, 4, true);
+            hunter.record_shot(x, 4, true, None).unwrap();
+        }
+        let shot = hunter.choose_shot(&view, &mut rng).unwrap();
+        assert!(matches!(shot, (4, 4) | (7, 4)), "{shot:?} is off the line");
+
+        // With one end missed, the other end is next
+        view.record_shot(7, 4, false);
+        hunter.record_shot(7, 4, false, None).unwrap();
+        assert_eq!(hunter.choose_shot(&view, &mut rng).unwrap(), (4, 4));
+    }
+
+    #[test]
+    fn hunter_goes_back_to_searching_once_the_ship_sinks() {
+        let mut hunter = Hunter::default();
+        for x in [5, 6] {
+            hunter.record_shot(x, 4, true, None).unwrap();
+        }
+        hunter.record_shot(7, 4, true, Some(3)).unwrap();
+        assert!(hunter.wounded.is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    fs::{File, OpenOptions},
+    io::Write,
+};
+
+use crate::types::{CellState, GRID_SIZE};
+
+/// Everything the AI is allowed to know about the player's board: the cells it
+/// has fired at and what they turned out to be. Every read is recorded so the
+/// audit log shows exactly what each decision was based on.
+pub struct AiView {
+    known: Vec<Vec<CellState>>,
+    history: Vec<ShotRecord>,
+    reads: RefCell<Vec<(usize, usize)>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShotRecord {
+    pub x: usize,
+    pub y: usize,
+    pub hit: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsultedCell {
+    pub x: usize,
+    pub y: usize,
+    pub seen: CellState,
+}
+
+/// One AI decision: the shot history it had, the cells it looked at, and the
+/// cell it chose.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub game: usize,
+    pub turn: usize,
+    pub history: Vec<ShotRecord>,
+    pub consulted: Vec<ConsultedCell>,
+    pub shot: (usize, usize),
+}
+
+impl Default for AiView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AiView {
+    pub fn new() -> Self {
+        Self {
+            known: vec![vec![CellState::Empty; GRID_SIZE]; GRID_SIZE],
+            history: Vec::new(),
+            reads: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns what the AI knows about a cell: `Empty` (not fired at), `Hit` or `Miss`.
+    pub fn cell(&self, x: usize, y: usize) -> CellState {
+        self.reads.borrow_mut().push((x, y));
+        self.known[y][x]
+    }
+
+    pub fn record_shot(&mut self, x: usize, y: usize, hit: bool) {
+        self.known[y][x] = if hit { CellState::Hit } else { CellState::Miss };
+        self.history.push(ShotRecord { x, y, hit });
+    }
+
+    /// Builds the audit entry for a decision and clears the read log.
+    fn take_entry(&self, game: usize, shot: (usize, usize)) -> AuditEntry {
+        let consulted = self
+            .reads
+            .borrow_mut()
+            .drain(..)
+            .map(|(x, y)| ConsultedCell {
+                x,
+                y,
+                seen: self.known[y][x],
+            })
+            .collect();
+        AuditEntry {
+            game,
+            turn: self.history.len() + 1,
+            history: self.history.clone(),
+            consulted,
+            shot,
+        }
+    }
+}
+
+/// Collects AI decisions into a JSON-lines file and checks them after each game.
+pub struct AuditLog {
+    file: File,
+    game: usize,
+    entries: Vec<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn open(path: &str) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file,
+            game: 1,
+            entries: Vec::new(),
+        })
+    }
+
+    pub fn record(&mut self, view: &AiView, shot: (usize, usize)) -> Result<()> {
+        let entry = view.take_entry(self.game, shot);
+        writeln!(self.file, "{}", serde_json::to_string(&entry)?)?;
+        self.file.flush()?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Verifies the finished game against the player's real board and starts a new one.
+    /// Returns a list of violations; an empty list means the AI only used fair information.
+    pub fn finish_game(&mut self, player_grid: &[Vec<CellState>]) -> Vec<String> {
+        let violations = verify(&self.entries, player_grid);
+        self.entries.clear();
+        self.game += 1;
+        violations
+    }
+}
+
+/// Checks that every decision was based solely on earlier shots and their true results,
+/// and that no decision ever saw an unhit ship cell.
+pub fn verify(entries: &[AuditEntry], player_grid: &[Vec<CellState>]) -> Vec<String> {
+    let mut violations = Vec::new();
+    let mut expected: Vec<ShotRecord> = Vec::new();
+
+    for entry in entries {
+        if entry.history != expected {
+            violations.push(format!(
+                "turn {}: history does not match the shots actually fired",
+                entry.turn
+            ));
+        }
+
+        for cell in &entry.consulted {
+            let fired = expected.iter().find(|s| (s.x, s.y) == (cell.x, cell.y));
+            let legitimate = match (fired, cell.seen) {
+                (None, CellState::Empty) => true,
+                (Some(s), CellState::Hit) => s.hit,
+                (Some(s), CellState::Miss) => !s.hit,
+                _ => false,
+            };
+            if !legitimate {
+                violations.push(format!(
+                    "turn {}: read ({}, {}) as {:?}, which the AI could not have known",
+                    entry.turn, cell.x, cell.y, cell.seen
+                ));
+            }
+        }
+
+        let (x, y) = entry.shot;
+        let hit = player_grid[y][x] == CellState::Hit;
+        expected.push(ShotRecord { x, y, hit });
+    }
+
+    violations
+}
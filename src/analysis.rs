@@ -0,0 +1,146 @@
+//! Post-game analysis: how well a player aimed, worked out from the
+//! `GameReview` the server sends when a game ends. Each shot is replayed
+//! through the probability-density AI, which says how likely the cell was to
+//! hold a ship given what the player knew at the time.
+
+use crate::ai::{AiStrategy, Heatmap};
+use crate::ai_audit::AiView;
+use crate::engine::Board;
+use crate::types::{CellState, GRID_SIZE, ReviewShot, SHIPS};
+
+/// Games the heatmap AI plays against the fleet to set its par.
+const PAR_RUNS: usize = 20;
+
+/// The story of one finished game from the player's side.
+pub struct Analysis {
+    pub shots: usize,
+    pub hits: usize,
+    /// Whether the player sank the whole fleet.
+    pub sank_fleet: bool,
+    /// Shots a perfect game takes: one for each ship cell.
+    pub optimum: usize,
+    /// Average shots the probability-density AI takes to sink the same
+    /// fleet from scratch.
+    pub par: f64,
+    /// Shots at cells no ship still afloat could have covered.
+    pub wasted: Vec<(usize, usize)>,
+    /// Shots at the likeliest cell on the board at the time.
+    pub best_shots: usize,
+    /// For each cell the player fired at, how likely it was then as a share
+    /// of the likeliest cell, from 0 to 1.
+    pub targeting: Vec<Vec<Option<f64>>>,
+    /// The opponent's fleet as the game left it.
+    pub board: Board,
+    pub opponent_shots: usize,
+    pub opponent_hits: usize,
+}
+
+impl Analysis {
+    pub fn accuracy(&self) -> f64 {
+        percent(self.hits, self.shots)
+    }
+
+    pub fn opponent_accuracy(&self) -> f64 {
+        percent(self.opponent_hits, self.opponent_shots)
+    }
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64 * 100.0
+    }
+}
+
+/// Analyses a finished game from the opponent's board as it ended and every
+/// shot fired, as a `GameReview` carries them.
+pub fn analyse(opponent_board: &Board, shots: &[ReviewShot]) -> Analysis {
+    let fleet = fresh(opponent_board);
+    let mut heatmap = Heatmap::default();
+    let mut view = AiView::new();
+    let mut targeting = vec![vec![None; GRID_SIZE]; GRID_SIZE];
+    let mut wasted = Vec::new();
+    let mut best_shots = 0;
+
+    for shot in shots.iter().filter(|shot| shot.yours) {
+        let (x, y) = (shot.x, shot.y);
+        let weights = heatmap.weights(&view);
+        let best = weights.iter().flatten().copied().max().unwrap_or(0);
+        let weight = weights[y][x];
+        if weight == 0 {
+            wasted.push((x, y));
+        }
+        if best > 0 && weight == best {
+            best_shots += 1;
+        }
+        targeting[y][x] = Some(if best == 0 {
+            0.0
+        } else {
+            weight as f64 / best as f64
+        });
+
+        view.record_shot(x, y, shot.hit);
+        let sunk = fleet
+            .ship_at(x, y)
+            .filter(|_| shot.sunk)
+            .map(|ship| ship.length());
+        let _ = heatmap.record_shot(x, y, shot.hit, sunk);
+    }
+
+    let yours = shots.iter().filter(|shot| shot.yours);
+    let theirs = shots.iter().filter(|shot| !shot.yours);
+    Analysis {
+        shots: yours.clone().count(),
+        hits: yours.filter(|shot| shot.hit).count(),
+        sank_fleet: opponent_board.all_sunk(),
+        optimum: SHIPS.iter().map(|(length, _)| length).sum(),
+        par: par(&fleet),
+        wasted,
+        best_shots,
+        targeting,
+        board: opponent_board.clone(),
+        opponent_shots: theirs.clone().count(),
+        opponent_hits: theirs.filter(|shot| shot.hit).count(),
+    }
+}
+
+/// `board` as it was before the first shot: hits back to ships, misses back
+/// to open water.
+fn fresh(board: &Board) -> Board {
+    let cells: Vec<Vec<CellState>> = board
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&cell| match cell {
+                    CellState::Hit => CellState::Ship,
+                    CellState::Miss => CellState::Empty,
+                    cell => cell,
+                })
+                .collect()
+        })
+        .collect();
+    cells.into()
+}
+
+/// Average shots the heatmap AI takes to sink `fleet`.
+fn par(fleet: &Board) -> f64 {
+    let mut rng = rand::rng();
+    let mut total = 0;
+    for _ in 0..PAR_RUNS {
+        let mut board = fleet.clone();
+        let mut heatmap = Heatmap::default();
+        let mut view = AiView::new();
+        while !board.all_sunk() {
+            let Ok((x, y)) = heatmap.choose_shot(&view, &mut rng) else {
+                break;
+            };
+            let shot = board.fire(x, y);
+            view.record_shot(x, y, shot.hit);
+            let sunk = shot.ship.filter(|_| shot.sunk).map(|kind| SHIPS[kind].0);
+            let _ = heatmap.record_shot(x, y, shot.hit, sunk);
+            total += 1;
+        }
+    }
+    total as f64 / PAR_RUNS as f64
+}
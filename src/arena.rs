@@ -0,0 +1,441 @@
+//! AI against AI, with no players: two strategies play each other game after
+//! game, either headless or with the first AI's games drawn in the TUI. Good
+//! for comparing strategies and for checking that a rule change still lets
+//! every AI finish a game. [`simulate`] plays thousands of games from a seed
+//! and sums them up, for benchmarking.
+
+use anyhow::{Result, bail};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use rand::{Rng, RngCore, SeedableRng, rngs::StdRng};
+use ratatui::{Terminal, backend::CrosstermBackend};
+use serde::Serialize;
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use crate::ai::{AiStrategy, Difficulty};
+use crate::ai_audit::AiView;
+use crate::coords;
+use crate::engine::{Board, Shot};
+use crate::game_state::GameState;
+use crate::types::{GamePhase, SHIPS, SunkShip};
+use crate::ui::draw_ui;
+
+/// How long each shot stays on screen while watching.
+const WATCH_STEP: Duration = Duration::from_millis(200);
+
+/// How long a finished game stays on screen before the next one starts.
+const WATCH_GAME_OVER: Duration = Duration::from_secs(3);
+
+/// One shot of a duel.
+pub struct Fired {
+    /// The side that fired.
+    pub side: usize,
+    pub x: usize,
+    pub y: usize,
+    pub shot: Shot,
+}
+
+/// One game between two strategies, played a shot at a time. Sides are
+/// indexed from 0.
+pub struct Duel<'a> {
+    strategies: &'a mut [Box<dyn AiStrategy>; 2],
+    /// Each side's fleet, as the other side has shot at it.
+    fleets: [Board; 2],
+    /// What each side knows of the other's fleet.
+    views: [AiView; 2],
+    shots: [usize; 2],
+    /// Time each side has spent choosing its shots.
+    thinking: [Duration; 2],
+    /// The side whose shot it is.
+    turn: usize,
+    winner: Option<usize>,
+}
+
+impl<'a> Duel<'a> {
+    /// Sets up a game in which side `first` fires first. Each strategy
+    /// places a fresh fleet, forgetting any earlier game.
+    pub fn new(
+        strategies: &'a mut [Box<dyn AiStrategy>; 2],
+        first: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<Self> {
+        let fleets = [
+            strategies[0].place_ships(rng)?,
+            strategies[1].place_ships(rng)?,
+        ];
+        Ok(Self {
+            strategies,
+            fleets,
+            views: [AiView::new(), AiView::new()],
+            shots: [0, 0],
+            thinking: [Duration::ZERO; 2],
+            turn: first,
+            winner: None,
+        })
+    }
+
+    /// Fires the next shot. Fails if a strategy fails or fires at a cell
+    /// it already tried.
+    pub fn step(&mut self, rng: &mut dyn RngCore) -> Result<Fired> {
+        let side = self.turn;
+        let target = 1 - side;
+        let started = Instant::now();
+        let (x, y) = self.strategies[side].choose_shot(&self.views[side], rng)?;
+        self.thinking[side] += started.elapsed();
+        if let Err(detail) = self.fleets[target].check_target(x, y) {
+            bail!("AI {} made an illegal shot: {}", side_label(side), detail);
+        }
+        let shot = self.fleets[target].fire(x, y);
+        self.views[side].record_shot(x, y, shot.hit);
+        let sunk = shot.ship.filter(|_| shot.sunk).map(|kind| SHIPS[kind].0);
+        self.strategies[side].record_shot(x, y, shot.hit, sunk)?;
+        self.shots[side] += 1;
+        if self.fleets[target].all_sunk() {
+            self.winner = Some(side);
+        } else {
+            self.turn = target;
+        }
+        Ok(Fired { side, x, y, shot })
+    }
+
+    /// Plays the game out and returns the winner.
+    pub fn play(&mut self, rng: &mut dyn RngCore) -> Result<usize> {
+        loop {
+            if let Some(winner) = self.winner {
+                return Ok(winner);
+            }
+            self.step(rng)?;
+        }
+    }
+
+    /// The side whose shot it is, or who won.
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn winner(&self) -> Option<usize> {
+        self.winner
+    }
+
+    /// Shots `side` has fired so far.
+    pub fn shots(&self, side: usize) -> usize {
+        self.shots[side]
+    }
+
+    /// Time `side` has spent choosing its shots so far.
+    pub fn thinking(&self, side: usize) -> Duration {
+        self.thinking[side]
+    }
+
+    /// `side`'s fleet, with the other side's shots on it.
+    pub fn fleet(&self, side: usize) -> &Board {
+        &self.fleets[side]
+    }
+}
+
+fn side_label(side: usize) -> char {
+    if side == 0 { 'A' } else { 'B' }
+}
+
+/// How one finished game went.
+struct GameResult {
+    winner: usize,
+    shots: [usize; 2],
+}
+
+/// Plays `games` games between the `ai_a` and `ai_b` difficulties, firing
+/// first in turn, and prints how each went and the final score. With
+/// `watch`, draws each game from A's side of the table as it is played.
+pub async fn run_ai_vs_ai(
+    ai_a: Difficulty,
+    ai_b: Difficulty,
+    games: u32,
+    watch: bool,
+) -> Result<()> {
+    let labels = [
+        format!("A ({})", ai_a.name()),
+        format!("B ({})", ai_b.name()),
+    ];
+    let mut strategies = [ai_a.strategy(), ai_b.strategy()];
+    let mut rng = rand::rng();
+    let mut wins = [0; 2];
+    let mut tally = |game: usize, result: &GameResult| {
+        let winner = result.winner;
+        wins[winner] += 1;
+        println!(
+            "Game {}: {} won in {} shots; {} fired {}",
+            game,
+            labels[winner],
+            result.shots[winner],
+            labels[1 - winner],
+            result.shots[1 - winner]
+        );
+    };
+
+    if watch {
+        // Watched games are printed once the terminal is handed back
+        let results = watch_games(&mut strategies, &labels, games, &mut rng)?;
+        for (i, result) in results.iter().enumerate() {
+            tally(i + 1, result);
+        }
+    } else {
+        for game in 0..games as usize {
+            let mut duel = Duel::new(&mut strategies, game % 2, &mut rng)?;
+            let winner = duel.play(&mut rng)?;
+            let shots = [duel.shots(0), duel.shots(1)];
+            tally(game + 1, &GameResult { winner, shots });
+        }
+    }
+
+    println!(
+        "{} won {} of {}, {} won {}",
+        labels[0],
+        wins[0],
+        wins[0] + wins[1],
+        labels[1],
+        wins[1]
+    );
+    Ok(())
+}
+
+/// How one AI did over a simulation.
+#[derive(Debug, Serialize)]
+pub struct SideReport {
+    /// Its difficulty.
+    pub ai: String,
+    pub wins: u32,
+    /// Wins as a share of the games, from 0 to 1.
+    pub win_rate: f64,
+    /// Mean shots it fired in the games it won; `None` if it never won.
+    pub avg_shots_to_win: Option<f64>,
+    pub fewest_shots_to_win: Option<usize>,
+    pub most_shots_to_win: Option<usize>,
+    /// Mean time it took to choose a shot, in microseconds.
+    pub avg_shot_micros: f64,
+}
+
+/// What a simulation found.
+#[derive(Debug, Serialize)]
+pub struct Simulation {
+    pub games: u32,
+    /// The seed the games were played from; the same seed plays the same
+    /// games.
+    pub seed: u64,
+    /// A, then B.
+    pub sides: [SideReport; 2],
+    /// Wall-clock time for the whole run.
+    pub seconds: f64,
+    pub games_per_second: f64,
+}
+
+/// Plays `games` headless games between the `ai_a` and `ai_b`
+/// difficulties, firing first in turn, with every random choice drawn from
+/// `seed`.
+pub fn simulate(ai_a: Difficulty, ai_b: Difficulty, games: u32, seed: u64) -> Result<Simulation> {
+    let difficulties = [ai_a, ai_b];
+    let mut strategies = difficulties.map(Difficulty::strategy);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut wins = [0; 2];
+    let mut shots_to_win: [Vec<usize>; 2] = Default::default();
+    let mut shots = [0; 2];
+    let mut thinking = [Duration::ZERO; 2];
+
+    let started = Instant::now();
+    for game in 0..games as usize {
+        let mut duel = Duel::new(&mut strategies, game % 2, &mut rng)?;
+        let winner = duel.play(&mut rng)?;
+        wins[winner] += 1;
+        shots_to_win[winner].push(duel.shots(winner));
+        for side in [0, 1] {
+            shots[side] += duel.shots(side);
+            thinking[side] += duel.thinking(side);
+        }
+    }
+    let seconds = started.elapsed().as_secs_f64();
+
+    let sides = [0, 1].map(|side| {
+        let won = &shots_to_win[side];
+        SideReport {
+            ai: difficulties[side].name().to_string(),
+            wins: wins[side],
+            win_rate: f64::from(wins[side]) / f64::from(games.max(1)),
+            avg_shots_to_win: (!won.is_empty())
+                .then(|| won.iter().sum::<usize>() as f64 / won.len() as f64),
+            fewest_shots_to_win: won.iter().min().copied(),
+            most_shots_to_win: won.iter().max().copied(),
+            avg_shot_micros: thinking[side].as_secs_f64() * 1e6 / shots[side].max(1) as f64,
+        }
+    });
+    Ok(Simulation {
+        games,
+        seed,
+        sides,
+        seconds,
+        games_per_second: if seconds > 0.0 {
+            f64::from(games) / seconds
+        } else {
+            0.0
+        },
+    })
+}
+
+/// Runs a simulation and prints it as a table, or as JSON with `json`.
+/// Without a `seed`, picks one and reports it so the run can be repeated.
+pub fn run_simulate(
+    ai_a: Difficulty,
+    ai_b: Difficulty,
+    games: u32,
+    seed: Option<u64>,
+    json: bool,
+) -> Result<()> {
+    let seed = seed.unwrap_or_else(|| rand::rng().random());
+    let report = simulate(ai_a, ai_b, games, seed)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    println!(
+        "{} games from seed {} in {:.2}s ({:.0} games/s)",
+        report.games, report.seed, report.seconds, report.games_per_second
+    );
+    println!();
+    println!(
+        "{:<12} {:>6} {:>9} {:>11} {:>7} {:>5} {:>10}",
+        "AI", "Wins", "Win rate", "Avg to win", "Fewest", "Most", "Per shot"
+    );
+    let or_dash = |n: Option<String>| n.unwrap_or_else(|| "-".to_string());
+    for (label, side) in ['A', 'B'].iter().zip(&report.sides) {
+        println!(
+            "{:<12} {:>6} {:>8.1}% {:>11} {:>7} {:>5} {:>8.1}µs",
+            format!("{} ({})", label, side.ai),
+            side.wins,
+            side.win_rate * 100.0,
+            or_dash(side.avg_shots_to_win.map(|avg| format!("{:.1}", avg))),
+            or_dash(side.fewest_shots_to_win.map(|n| n.to_string())),
+            or_dash(side.most_shots_to_win.map(|n| n.to_string())),
+            side.avg_shot_micros
+        );
+    }
+    Ok(())
+}
+
+/// Plays the games in the TUI, from A's side, until they are done or the
+/// viewer presses Q. Returns the games that finished.
+fn watch_games(
+    strategies: &mut [Box<dyn AiStrategy>; 2],
+    labels: &[String; 2],
+    games: u32,
+    rng: &mut dyn RngCore,
+) -> Result<Vec<GameResult>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let outcome = watch_loop(&mut terminal, strategies, labels, games, rng);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    outcome
+}
+
+fn watch_loop(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    strategies: &mut [Box<dyn AiStrategy>; 2],
+    labels: &[String; 2],
+    games: u32,
+    rng: &mut dyn RngCore,
+) -> Result<Vec<GameResult>> {
+    let mut results = Vec::new();
+    let mut state = GameState::new();
+    for game in 1..=games {
+        let mut duel = Duel::new(strategies, (game as usize + 1) % 2, rng)?;
+        state.reset_for_new_game();
+        state.opponent_name = Some(labels[1].clone());
+        state.own_grid = duel.fleet(0).clone();
+        state.placing_ship_idx = SHIPS.len();
+        state.messages = vec![format!(
+            "Game {} of {}: {} against {}. Press Q to stop watching.",
+            game, games, labels[0], labels[1]
+        )];
+        state.turn_count = 1;
+        state.start_turn();
+
+        let mut next_step = Instant::now();
+        loop {
+            state.phase = match duel.winner() {
+                Some(_) => GamePhase::GameOver,
+                None if duel.turn() == 0 => GamePhase::YourTurn,
+                None => GamePhase::OpponentTurn,
+            };
+            terminal.draw(|f| draw_ui(f, &state))?;
+            if quit_pressed(next_step)? {
+                return Ok(results);
+            }
+            if duel.winner().is_some() {
+                break;
+            }
+            next_step = Instant::now() + WATCH_STEP;
+
+            let Fired { side, x, y, shot } = duel.step(rng)?;
+            let coord = coords::format(x, y);
+            let ship = SunkShip::from_shot(&shot);
+            let result = match (shot.hit, &ship) {
+                (true, Some(ship)) => format!("sank the {}", ship.name),
+                (true, None) => "hit".to_string(),
+                (false, _) => "missed".to_string(),
+            };
+            state
+                .messages
+                .push(format!("{} fired at {}: {}", labels[side], coord, result));
+            if side == 0 {
+                state.enemy_grid[y][x] = duel.fleet(1)[y][x];
+                state.record_shot(x, y, shot.hit, shot.sunk);
+                state.enemy_ships_sunk.extend(ship);
+                state.end_turn();
+            } else {
+                state.own_grid = duel.fleet(0).clone();
+                state.update_ship_status();
+                state.turn_count += 1;
+                state.start_turn();
+            }
+        }
+
+        let winner = duel.winner().unwrap_or(0);
+        state.winner = Some(winner == 0);
+        state.messages.push(format!("{} wins!", labels[winner]));
+        results.push(GameResult {
+            winner,
+            shots: [duel.shots(0), duel.shots(1)],
+        });
+        terminal.draw(|f| draw_ui(f, &state))?;
+        if quit_pressed(Instant::now() + WATCH_GAME_OVER)? {
+            break;
+        }
+    }
+    Ok(results)
+}
+
+/// Waits until `until` for the viewer to press Q.
+fn quit_pressed(until: Instant) -> Result<bool> {
+    loop {
+        let left = until.saturating_duration_since(Instant::now());
+        if !event::poll(left)? {
+            return Ok(false);
+        }
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && matches!(key.code, KeyCode::Char('q') | KeyCode::Char('Q'))
+        {
+            return Ok(true);
+        }
+    }
+}
@@ -0,0 +1,157 @@
+//! The AI as a stand-in opponent on the two-player server. When a player has
+//! waited too long for someone to play, the server seats an AI across from
+//! them. The AI is an ordinary client of the server, only its connection is
+//! an in-memory pipe rather than a socket, so the session referees it exactly
+//! as it would a person.
+
+use anyhow::Result;
+use rand::Rng;
+use std::time::Duration;
+use tokio::io::DuplexStream;
+
+use crate::ai::{AiStrategy, Difficulty};
+use crate::ai_audit::AiView;
+use crate::engine::Board;
+use crate::net::{self, FrameReader, TimedWriter, Timeouts};
+use crate::types::{CellState, ErrorCode, GRID_SIZE, Message};
+
+/// Room in the in-memory pipe each way; far more than a game ever queues.
+const PIPE_CAPACITY: usize = 64 * 1024;
+
+/// When and how the server fills an empty seat with the AI.
+#[derive(Debug, Clone, Copy)]
+pub struct Backfill {
+    /// How long a player waits alone before the AI joins them.
+    pub after: Duration,
+    pub difficulty: Difficulty,
+}
+
+/// The rules the AI must place its fleet under, as its `Hello` would have
+/// been answered.
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    /// Mines to lay along with the fleet.
+    pub mines: usize,
+    /// Ships may not touch.
+    pub apart: bool,
+}
+
+/// Starts an AI playing at `difficulty` and returns the server's end of its
+/// connection, ready to be seated as a player that has already said `Hello`.
+pub fn connect(
+    difficulty: Difficulty,
+    placement: Placement,
+    timeouts: &Timeouts,
+) -> (FrameReader, TimedWriter) {
+    let (server_end, ai_end) = tokio::io::duplex(PIPE_CAPACITY);
+    tokio::spawn(play(ai_end, difficulty.strategy(), placement, *timeouts));
+    net::split_stream(server_end, timeouts)
+}
+
+/// Plays game after game over `stream` until the server or the opponent is
+/// done, or the strategy fails.
+async fn play(
+    stream: DuplexStream,
+    mut strategy: Box<dyn AiStrategy>,
+    placement: Placement,
+    timeouts: Timeouts,
+) {
+    let (mut frames, mut writer) = net::split_stream(stream, &timeouts);
+    let mut view = AiView::new();
+    let mut last_shot = None;
+    if send_fleet(strategy.as_mut(), placement, &mut writer).is_err() {
+        return;
+    }
+
+    while let Ok(Some(frame)) = frames.next_frame().await {
+        let Ok(msg) = frame.decode() else {
+            continue;
+        };
+        let fire = match msg {
+            Message::YourTurn => true,
+            Message::AttackResult {
+                x, y, hit, ship, ..
+            } => {
+                view.record_shot(x, y, hit);
+                let sunk = ship.map(|ship| ship.length);
+                if strategy.record_shot(x, y, hit, sunk).is_err() {
+                    break;
+                }
+                false
+            }
+            // Fog may hold a result back; the cell is spent either way
+            Message::Error {
+                code: ErrorCode::InvalidTarget,
+                ..
+            } => {
+                if let Some((x, y)) = last_shot {
+                    view.record_shot(x, y, false);
+                }
+                true
+            }
+            Message::NewGameStart => {
+                view = AiView::new();
+                if send_fleet(strategy.as_mut(), placement, &mut writer).is_err() {
+                    break;
+                }
+                false
+            }
+            Message::PlayAgainRequest => {
+                let _ = writer.send(&Message::PlayAgainResponse {
+                    wants_to_play: true,
+                });
+                false
+            }
+            Message::OpponentDisconnected { .. } => {
+                let _ = writer.send(&Message::ClaimVictory);
+                false
+            }
+            Message::Ping => {
+                let _ = writer.send(&Message::Pong);
+                false
+            }
+            Message::OpponentQuit | Message::PlayAgainTimeout => break,
+            _ => false,
+        };
+        if fire {
+            let Ok((x, y)) = strategy.choose_shot(&view, &mut rand::rng()) else {
+                break;
+            };
+            last_shot = Some((x, y));
+            let _ = writer.send(&Message::Attack { x, y });
+        }
+    }
+    let _ = writer.send(&Message::Quit);
+    writer.close().await;
+}
+
+fn send_fleet(
+    strategy: &mut dyn AiStrategy,
+    placement: Placement,
+    writer: &mut TimedWriter,
+) -> Result<()> {
+    let board = fleet(strategy, placement)?;
+    writer.send(&Message::PlaceShips(board))?;
+    Ok(())
+}
+
+/// A fleet from `strategy` that keeps to `placement`, with its mines laid.
+fn fleet(strategy: &mut dyn AiStrategy, placement: Placement) -> Result<Board> {
+    let mut rng = rand::rng();
+    loop {
+        let mut board = strategy.place_ships(&mut rng)?;
+        if placement.apart && board.check_apart().is_err() {
+            continue;
+        }
+        let mut laid = 0;
+        while laid < placement.mines {
+            let x = rng.random_range(0..GRID_SIZE);
+            let y = rng.random_range(0..GRID_SIZE);
+            if board[y][x] == CellState::Empty {
+                board[y][x] = CellState::Mine;
+                laid += 1;
+            }
+        }
+        return Ok(board);
+    }
+}
@@ -0,0 +1,204 @@
+//! External bots: an [`AiStrategy`] played by another program, so a bot can
+//! be written in any language while the AI server keeps the rules and the
+//! network. The server runs the bot as a child process and talks to it one
+//! line at a time over its stdin and stdout, much like a UCI chess engine.
+//!
+//! Cells are `X Y`, counted from 0 at the top left. The server sends:
+//!
+//! - `battleship SIZE` once at start-up; the bot answers `ready`
+//! - `newgame` before each game; the bot answers `fleet` followed by
+//!   `X Y h|v` for each ship in the order of [`SHIPS`], or `fleet random`
+//! - `shoot` on the bot's turn; the bot answers `fire X Y`
+//! - `result X Y miss`, `result X Y hit` or `result X Y sunk LENGTH` after
+//!   each of its shots, with no answer
+//! - `quit` when the server is done with it
+//!
+//! Lines from the bot starting with `info` are printed by the server and
+//! otherwise ignored, for debugging.
+
+use anyhow::{Context, Result, bail};
+use rand::RngCore;
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    time::Duration,
+};
+
+use crate::ai::AiStrategy;
+use crate::ai_audit::AiView;
+use crate::coords;
+use crate::engine::Board;
+use crate::types::{CellState, GRID_SIZE, SHIPS};
+
+/// How long the bot has to answer before the server gives up on it.
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A bot running as a child process.
+pub struct ExternalBot {
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines from the bot's stdout, read on a thread of their own so a
+    /// silent bot cannot hang the server.
+    lines: Receiver<String>,
+}
+
+impl ExternalBot {
+    /// Starts `command`, a program and its arguments separated by spaces,
+    /// and waits for it to say it is ready.
+    pub fn spawn(command: &str) -> Result<Self> {
+        let mut words = command.split_whitespace();
+        let program = words.next().context("the bot command is empty")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("could not start bot '{}'", command))?;
+        let stdin = child.stdin.take().context("bot has no stdin")?;
+        let stdout = child.stdout.take().context("bot has no stdout")?;
+
+        let (tx, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut bot = Self {
+            child,
+            stdin,
+            lines,
+        };
+        bot.send(&format!("battleship {}", GRID_SIZE))?;
+        match bot.receive()?.as_str() {
+            "ready" => Ok(bot),
+            other => bail!("bot answered '{}' instead of 'ready'", other),
+        }
+    }
+
+    fn send(&mut self, line: &str) -> Result<()> {
+        writeln!(self.stdin, "{}", line)
+            .and_then(|_| self.stdin.flush())
+            .context("the bot stopped reading its input")
+    }
+
+    /// The bot's next answer, skipping blank and `info` lines.
+    fn receive(&mut self) -> Result<String> {
+        loop {
+            let line = match self.lines.recv_timeout(ANSWER_TIMEOUT) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => bail!(
+                    "the bot did not answer within {}s",
+                    ANSWER_TIMEOUT.as_secs()
+                ),
+                Err(RecvTimeoutError::Disconnected) => bail!("the bot exited"),
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line == "info" || line.starts_with("info ") {
+                println!("Bot: {}", line);
+                continue;
+            }
+            return Ok(line.to_string());
+        }
+    }
+}
+
+/// Parses one coordinate of a bot's answer.
+fn coordinate(word: Option<&str>, answer: &str) -> Result<usize> {
+    word.and_then(|word| word.parse().ok())
+        .filter(|&n| n < GRID_SIZE)
+        .with_context(|| format!("bad cell in bot answer '{}'", answer))
+}
+
+impl AiStrategy for ExternalBot {
+    fn place_ships(&mut self, mut rng: &mut dyn RngCore) -> Result<Board> {
+        self.send("newgame")?;
+        let answer = self.receive()?;
+        let mut words = answer.split_whitespace();
+        if words.next() != Some("fleet") {
+            bail!("bot answered '{}' instead of a fleet", answer);
+        }
+        let words: Vec<&str> = words.collect();
+        if words == ["random"] {
+            return Ok(Board::random(&mut rng));
+        }
+        if words.len() != SHIPS.len() * 3 {
+            bail!(
+                "bot answered '{}', expected X Y h|v for each of its {} ships",
+                answer,
+                SHIPS.len()
+            );
+        }
+        let mut board = Board::default();
+        for (placement, &(length, name)) in words.chunks(3).zip(SHIPS.iter()) {
+            let x = coordinate(placement.first().copied(), &answer)?;
+            let y = coordinate(placement.get(1).copied(), &answer)?;
+            let horizontal = match placement[2] {
+                "h" => true,
+                "v" => false,
+                other => bail!("bot gave '{}' as a direction, expected h or v", other),
+            };
+            if !board.can_place(x, y, length, horizontal) {
+                bail!("bot cannot place its {} at {} {}", name, x, y);
+            }
+            board.place(x, y, length, horizontal);
+        }
+        Ok(board)
+    }
+
+    fn choose_shot(&mut self, view: &AiView, _rng: &mut dyn RngCore) -> Result<(usize, usize)> {
+        self.send("shoot")?;
+        let answer = self.receive()?;
+        let mut words = answer.split_whitespace();
+        if words.next() != Some("fire") {
+            bail!("bot answered '{}' instead of a shot", answer);
+        }
+        let words: Vec<&str> = words.collect();
+        // `fire X Y`, or the cell as the server writes it, e.g. `fire B7`
+        let (x, y) = match words[..] {
+            [cell] => coords::parse(cell)
+                .with_context(|| format!("bad cell in bot answer '{}'", answer))?,
+            _ => (
+                coordinate(words.first().copied(), &answer)?,
+                coordinate(words.get(1).copied(), &answer)?,
+            ),
+        };
+        if view.cell(x, y) != CellState::Empty {
+            bail!("bot fired at {} {} again", x, y);
+        }
+        Ok((x, y))
+    }
+
+    fn record_shot(&mut self, x: usize, y: usize, hit: bool, sunk: Option<usize>) -> Result<()> {
+        let outcome = match (hit, sunk) {
+            (_, Some(length)) => format!("sunk {}", length),
+            (true, None) => "hit".to_string(),
+            (false, None) => "miss".to_string(),
+        };
+        self.send(&format!("result {} {} {}", x, y, outcome))
+    }
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        // A moment to exit on its own, then it is stopped
+        let _ = self.send("quit");
+        for _ in 0..10 {
+            if let Ok(Some(_)) = self.child.try_wait() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
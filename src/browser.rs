@@ -0,0 +1,182 @@
+use anyhow::Result;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+};
+use std::io;
+
+use crate::directory::{self, Listing};
+use crate::lan;
+
+/// Where the server browser finds servers.
+pub enum Source {
+    /// The community directory at this URL (`--browse`).
+    Directory(String),
+    /// Servers answering discovery on the local network (`--lan`).
+    Lan,
+}
+
+impl Source {
+    fn fetch(&self) -> Result<Vec<Listing>> {
+        match self {
+            Source::Directory(url) => directory::fetch(url),
+            Source::Lan => lan::discover(),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Source::Directory(url) => format!("at {}", url),
+            Source::Lan => "on the local network".to_string(),
+        }
+    }
+}
+
+/// Lists the servers `source` knows of and returns the address the player
+/// picked, or `None` if they backed out.
+pub fn choose_server(source: &Source) -> Result<Option<String>> {
+    let mut listings = source.fetch()?;
+    let mut table = TableState::default().with_selected(Some(0));
+    let mut error = None;
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let choice = loop {
+        terminal.draw(|f| draw_browser(f, source, &listings, &mut table, error.as_deref()))?;
+
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let picked = match key.code {
+            KeyCode::Up => {
+                table.select_previous();
+                None
+            }
+            KeyCode::Down => {
+                table.select_next();
+                None
+            }
+            KeyCode::Enter => table.selected(),
+            KeyCode::Char(c @ '1'..='9') => Some(c as usize - '1' as usize),
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                match source.fetch() {
+                    Ok(fresh) => {
+                        listings = fresh;
+                        error = None;
+                    }
+                    Err(e) => error = Some(e.to_string()),
+                }
+                None
+            }
+            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => break None,
+            _ => None,
+        };
+        if let Some(address) = picked
+            .and_then(|index| listings.get(index))
+            .and_then(|listing| listing.address.clone())
+        {
+            break Some(address);
+        }
+    };
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    Ok(choice)
+}
+
+fn draw_browser(
+    f: &mut Frame,
+    source: &Source,
+    listings: &[Listing],
+    table: &mut TableState,
+    error: Option<&str>,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(3),
+        ])
+        .split(f.area());
+
+    let header = Paragraph::new(format!("🚢 Battleship servers {}", source.describe()))
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+
+    let rows = listings.iter().enumerate().map(|(i, listing)| {
+        let capacity = listing
+            .max_connections
+            .map_or_else(String::new, |max| format!(" / {}", max));
+        Row::new(vec![
+            if i < 9 {
+                (i + 1).to_string()
+            } else {
+                String::new()
+            },
+            listing.name.clone(),
+            listing.region.clone(),
+            format!("{}{}", listing.players, capacity),
+            if listing.rules.is_empty() {
+                "standard".to_string()
+            } else {
+                listing.rules.join(", ")
+            },
+            listing.address.clone().unwrap_or_default(),
+        ])
+    });
+    let title = if listings.is_empty() {
+        match source {
+            Source::Directory(_) => "No servers announced".to_string(),
+            Source::Lan => "No servers answered - R to look again".to_string(),
+        }
+    } else {
+        format!("Servers ({})", listings.len())
+    };
+    let servers = Table::new(
+        rows,
+        [
+            Constraint::Length(3),
+            Constraint::Percentage(30),
+            Constraint::Length(12),
+            Constraint::Length(9),
+            Constraint::Length(14),
+            Constraint::Min(0),
+        ],
+    )
+    .header(
+        Row::new(vec!["#", "Name", "Region", "Players", "Rules", "Address"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(Style::default().bg(Color::DarkGray))
+    .block(Block::default().borders(Borders::ALL).title(title));
+    f.render_stateful_widget(servers, chunks[1], table);
+
+    let footer = match error {
+        Some(error) => Paragraph::new(error.to_string()).style(Style::default().fg(Color::Red)),
+        None => Paragraph::new("1-9 or Enter: connect | Arrows: select | R: refresh | Q: quit"),
+    };
+    f.render_widget(
+        footer.block(Block::default().borders(Borders::ALL)),
+        chunks[2],
+    );
+}
@@ -0,0 +1,113 @@
+//! Chat between the two players. The server passes every line through
+//! [`clean`], and every display name through [`clean_name`], before relaying
+//! it, so what arrives is short and printable. Under `--chat-filter` or
+//! `--chat-blocklist` it is also free of the blocked words.
+
+use anyhow::{Context, Result};
+use std::sync::OnceLock;
+
+use crate::types::{MAX_CHAT_LEN, MAX_NAME_LEN};
+
+/// The words `--chat-filter` masks out unless `--chat-blocklist` names others.
+const BLOCKED: &[&str] = &[
+    "arse",
+    "arsehole",
+    "asshole",
+    "bastard",
+    "bitch",
+    "bollocks",
+    "cock",
+    "cunt",
+    "dick",
+    "fuck",
+    "fucked",
+    "fucker",
+    "fucking",
+    "motherfucker",
+    "piss",
+    "prick",
+    "shit",
+    "shitty",
+    "slut",
+    "twat",
+    "wanker",
+    "whore",
+];
+
+/// The words masked out, lowercased; unset while the filter is off.
+static BLOCKLIST: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Turns the word filter on with the built-in list.
+pub fn filter_default() {
+    set_blocklist(BLOCKED.iter().map(|word| word.to_string()).collect());
+}
+
+/// Turns the word filter on with the words in the file at `path`, one per
+/// line. Blank lines are skipped.
+pub fn filter_from_file(path: &str) -> Result<()> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("could not read chat blocklist {}", path))?;
+    set_blocklist(
+        text.lines()
+            .map(str::trim)
+            .filter(|word| !word.is_empty())
+            .map(str::to_string)
+            .collect(),
+    );
+    Ok(())
+}
+
+fn set_blocklist(words: Vec<String>) {
+    let words = words.iter().map(|word| word.to_lowercase()).collect();
+    let _ = BLOCKLIST.set(words);
+}
+
+/// Readies a chat line for the opponent: turns tabs and newlines into spaces,
+/// drops other control characters, trims it, caps it at `MAX_CHAT_LEN`
+/// characters and, with the filter on, replaces each blocked word with
+/// asterisks. `None` if nothing is left to send.
+pub fn clean(text: &str) -> Option<String> {
+    tidy(text, MAX_CHAT_LEN)
+}
+
+/// Readies a player's display name the same way, capped at `MAX_NAME_LEN`.
+pub fn clean_name(name: &str) -> Option<String> {
+    tidy(name, MAX_NAME_LEN)
+}
+
+fn tidy(text: &str, max_len: usize) -> Option<String> {
+    let text: String = text
+        .chars()
+        .map(|c| if c.is_whitespace() { ' ' } else { c })
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim()
+        .chars()
+        .take(max_len)
+        .collect();
+    let text = text.trim_end();
+    if text.is_empty() {
+        return None;
+    }
+    let Some(blocked) = BLOCKLIST.get() else {
+        return Some(text.to_string());
+    };
+
+    let mut cleaned = String::with_capacity(text.len());
+    let mut word = String::new();
+    for c in text.chars().chain(std::iter::once(' ')) {
+        if c.is_alphanumeric() || c == '\'' {
+            word.push(c);
+            continue;
+        }
+        if blocked.contains(&word.to_lowercase()) {
+            cleaned.extend(word.chars().map(|_| '*'));
+        } else {
+            cleaned.push_str(&word);
+        }
+        word.clear();
+        cleaned.push(c);
+    }
+    cleaned.pop();
+    Some(cleaned)
+}
@@ -0,0 +1,533 @@
+use anyhow::{Result, bail};
+use battleship_rs::ai::Difficulty;
+use battleship_rs::coords::CoordScheme;
+use battleship_rs::net::{Timeouts, Transport};
+use battleship_rs::server::TurnExpiry;
+use battleship_rs::server_ai::ThinkTime;
+use battleship_rs::settings::Settings;
+use battleship_rs::tls;
+use clap::{ArgGroup, Args, Parser, Subcommand};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+const EXAMPLES: &str = "\
+Examples:
+  # Start a server for two players
+  battleship-rs server 8080
+
+  # Connect as player 1, then as player 2 in another terminal
+  battleship-rs client 127.0.0.1:8080
+
+  # Or play against AI
+  battleship-rs server-ai 8080
+  battleship-rs client 127.0.0.1:8080
+
+  # Or play the AI in one terminal, no server needed
+  battleship-rs play-ai --difficulty hard
+
+  # Or practise against it, with three hints a game (H)
+  battleship-rs play-ai --hints 3
+
+  # Or watch two AIs play each other
+  battleship-rs ai-vs-ai --ai-a hard --ai-b easy --watch
+  battleship-rs simulate --games 10000 --ai-a hunt --ai-b random --seed 1
+
+  # Or use relay server for remote play
+  battleship-rs server-relay 8080
+  battleship-rs client your-server-ip:8080
+
+  # Or take turns at one keyboard, no server needed
+  battleship-rs hotseat --player1 Ann --player2 Bob
+
+  # Play a friend on a shared server: open a room, then join with its code
+  battleship-rs client your-server-ip:8080 --host-room
+  battleship-rs client your-server-ip:8080 --join-room K7QXM";
+
+/// 🚢 BATTLESHIP - Networked Terminal Game
+#[derive(Debug, Parser)]
+#[command(version, arg_required_else_help = true, after_help = EXAMPLES)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+
+    /// row-letters (A5 = row A) or column-letters
+    #[arg(
+        long,
+        global = true,
+        value_name = "SCHEME",
+        default_value = "row-letters"
+    )]
+    pub coords: CoordScheme,
+
+    /// Log server loop timings / show the client perf overlay (F12)
+    #[arg(long, global = true)]
+    pub perf: bool,
+
+    #[command(flatten)]
+    pub network: NetworkArgs,
+}
+
+/// Network timeouts in seconds, accepted by every mode.
+#[derive(Debug, Args)]
+#[command(next_help_heading = "Network options (seconds)")]
+pub struct NetworkArgs {
+    /// Client connect timeout
+    #[arg(long, global = true, value_name = "S", default_value_t = 10)]
+    connect_timeout: u64,
+
+    /// Drop a silent peer after this long (0 = never)
+    #[arg(long, global = true, value_name = "S", default_value_t = 600)]
+    read_timeout: u64,
+
+    /// Give up on a stalled write
+    #[arg(long, global = true, value_name = "S", default_value_t = 10)]
+    write_timeout: u64,
+
+    /// Servers stop waiting for players after this long (0 = never)
+    #[arg(long, global = true, value_name = "S", default_value_t = 0)]
+    accept_timeout: u64,
+}
+
+impl NetworkArgs {
+    /// A value of 0 disables the read and accept timeouts.
+    pub fn timeouts(&self) -> Timeouts {
+        let optional = |secs: u64| (secs > 0).then(|| Duration::from_secs(secs));
+        Timeouts {
+            connect: Duration::from_secs(self.connect_timeout),
+            read: optional(self.read_timeout),
+            write: Duration::from_secs(self.write_timeout),
+            accept: optional(self.accept_timeout),
+        }
+    }
+}
+
+/// What a server's connections are carried over; plain TCP by default.
+#[derive(Debug, Args)]
+#[command(next_help_heading = "Transport options")]
+pub struct TransportArgs {
+    /// Serve over TLS with this PEM certificate chain
+    #[arg(long, value_name = "FILE", requires = "tls_key")]
+    tls_cert: Option<String>,
+
+    /// PEM private key for --tls-cert
+    #[arg(long, value_name = "FILE", requires = "tls_cert")]
+    tls_key: Option<String>,
+
+    /// Accept WebSocket clients instead of raw TCP
+    #[arg(long)]
+    websocket: bool,
+}
+
+impl TransportArgs {
+    pub fn transport(&self) -> Result<Transport> {
+        let tls = match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => Some(tls::acceptor(cert, key)?),
+            _ => None,
+        };
+        Ok(Transport {
+            tls,
+            websocket: self.websocket,
+        })
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Two-player server
+    Server(ServerArgs),
+    /// Single-player server with an AI opponent
+    ServerAi(ServerAiArgs),
+    /// Relay messages between pairs of remote players
+    ServerRelay(ServerRelayArgs),
+    /// Connect to a server and play
+    Client(ClientArgs),
+    /// Play the AI right here, with no server
+    PlayAi(PlayAiArgs),
+    /// Two players taking turns at this terminal
+    Hotseat(HotseatArgs),
+    /// Two AIs playing each other, to compare them
+    AiVsAi(AiVsAiArgs),
+    /// Many headless AI-vs-AI games, summed up for benchmarking
+    Simulate(SimulateArgs),
+}
+
+/// Where a server listens: a port, given either positionally or with
+/// `--port`, on every interface unless `--bind` says otherwise.
+#[derive(Debug, Args)]
+pub struct ListenArgs {
+    /// Port to listen on [default: 8080]
+    #[arg(value_name = "PORT")]
+    port: Option<u16>,
+
+    /// Same as PORT
+    #[arg(long = "port", value_name = "PORT", conflicts_with = "port")]
+    port_flag: Option<u16>,
+
+    /// Address to listen on, e.g. 127.0.0.1, :: or [::1]:9000 [default: 0.0.0.0]
+    #[arg(long, value_name = "ADDR", value_parser = parse_bind)]
+    bind: Option<Bind>,
+}
+
+/// What `--bind` was given.
+#[derive(Debug, Clone, Copy)]
+enum Bind {
+    /// An address, to listen on at PORT.
+    Ip(IpAddr),
+    /// An address with its own port.
+    Socket(SocketAddr),
+}
+
+fn parse_bind(value: &str) -> Result<Bind, String> {
+    if let Ok(addr) = value.parse() {
+        return Ok(Bind::Socket(addr));
+    }
+    let ip = value.strip_prefix('[').and_then(|ip| ip.strip_suffix(']'));
+    ip.unwrap_or(value)
+        .parse()
+        .map(Bind::Ip)
+        .map_err(|_| format!("'{}' is not an IP address or IP:port", value))
+}
+
+impl ListenArgs {
+    fn port(&self) -> u16 {
+        self.port.or(self.port_flag).unwrap_or(8080)
+    }
+
+    pub fn addr(&self) -> Result<SocketAddr> {
+        match self.bind {
+            None => Ok(SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), self.port())),
+            Some(Bind::Ip(ip)) => Ok(SocketAddr::new(ip, self.port())),
+            Some(Bind::Socket(addr)) if self.port.is_none() && self.port_flag.is_none() => Ok(addr),
+            Some(Bind::Socket(addr)) => bail!("--bind {} already gives a port", addr),
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+#[command(group(ArgGroup::new("listed").args(["announce", "lan"]).multiple(true)))]
+pub struct ServerArgs {
+    #[command(flatten)]
+    pub listen: ListenArgs,
+
+    #[command(flatten)]
+    pub transport: TransportArgs,
+
+    /// Only let in clients that give this password
+    #[arg(long)]
+    pub password: Option<String>,
+
+    /// Show the live operator dashboard instead of the log
+    #[arg(long)]
+    pub dashboard: bool,
+
+    /// Cap players plus observers (at least 2)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(2..))]
+    pub max_connections: Option<u32>,
+
+    /// Cap games running at once; later pairs wait their turn
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub max_games: Option<u32>,
+
+    /// POST a JSON summary to this URL when a game ends
+    #[arg(long, value_name = "URL")]
+    pub webhook: Option<String>,
+
+    /// Mask profanity in chat and display names
+    #[arg(long)]
+    pub chat_filter: bool,
+
+    /// Mask the words in this file, one per line, instead of the built-in list
+    #[arg(long, value_name = "FILE")]
+    pub chat_blocklist: Option<String>,
+
+    /// Weather strikes between turns
+    #[arg(long)]
+    pub stormy_seas: bool,
+
+    /// Each player hides two mines that blast whoever fires on them
+    #[arg(long)]
+    pub mines: bool,
+
+    /// Ships may not touch, and sinking one reveals the water around it
+    #[arg(long)]
+    pub reveal_sunk: bool,
+
+    /// Play a series: the first to win most of N games (N odd) takes it
+    #[arg(long, value_name = "N", value_parser = parse_best_of)]
+    pub best_of: Option<u32>,
+
+    /// Give each player this many seconds per shot
+    #[arg(long, value_name = "S", value_parser = clap::value_parser!(u64).range(5..))]
+    pub turn_timeout: Option<u64>,
+
+    /// random-shot or forfeit when a turn times out
+    #[arg(
+        long,
+        value_name = "ACTION",
+        requires = "turn_timeout",
+        default_value = "random-shot"
+    )]
+    pub on_turn_timeout: TurnExpiry,
+
+    /// Seat the AI across from a player still waiting for an opponent after
+    /// this many seconds
+    #[arg(long, value_name = "S", value_parser = clap::value_parser!(u64).range(1..))]
+    pub bot_after: Option<u64>,
+
+    /// easy, normal or hard: how well the AI seated by --bot-after shoots
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        requires = "bot_after",
+        default_value = "normal"
+    )]
+    pub bot_difficulty: Difficulty,
+
+    /// List this server in the directory at URL
+    #[arg(long, value_name = "URL")]
+    pub announce: Option<String>,
+
+    /// Let clients on the local network find this server (client --lan)
+    #[arg(long)]
+    pub lan: bool,
+
+    /// Server name shown in the directory or on the LAN
+    #[arg(long, requires = "listed", default_value = "battleship-rs")]
+    pub name: String,
+
+    /// Region shown in the directory
+    #[arg(long, requires = "announce", default_value = "unknown")]
+    pub region: String,
+}
+
+fn parse_best_of(value: &str) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(games) if games % 2 == 1 => Ok(games),
+        Ok(_) => Err("a series needs an odd number of games, so it cannot tie".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ServerAiArgs {
+    #[command(flatten)]
+    pub listen: ListenArgs,
+
+    #[command(flatten)]
+    pub transport: TransportArgs,
+
+    /// Only let in clients that give this password
+    #[arg(long)]
+    pub password: Option<String>,
+
+    /// easy, normal or hard: how well the AI shoots
+    #[arg(long, value_name = "LEVEL", default_value = "normal")]
+    pub difficulty: Difficulty,
+
+    /// How long the AI thinks before each shot, in milliseconds: a range
+    /// like 500-1500, or one number [default: no delay]
+    #[arg(long, value_name = "MS")]
+    pub think_time: Option<ThinkTime>,
+
+    /// Let an external bot play the AI's side: a program and its arguments,
+    /// spoken to over stdin and stdout (see "External bots" in the README)
+    #[arg(long, value_name = "COMMAND", conflicts_with = "difficulty")]
+    pub engine: Option<String>,
+
+    /// Record every AI decision to this JSON-lines file
+    #[arg(long, value_name = "FILE")]
+    pub audit_log: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ServerRelayArgs {
+    #[command(flatten)]
+    pub listen: ListenArgs,
+
+    #[command(flatten)]
+    pub transport: TransportArgs,
+
+    /// Only let in clients that give this password
+    #[arg(long)]
+    pub password: Option<String>,
+
+    /// POST a JSON summary to this URL when a game ends
+    #[arg(long, value_name = "URL")]
+    pub webhook: Option<String>,
+}
+
+/// Client preferences: the settings file, and flags that override it.
+#[derive(Debug, Args)]
+pub struct SettingsArgs {
+    /// JSON settings file [default: ~/.config/battleship-rs/settings.json]
+    #[arg(long, value_name = "FILE")]
+    settings: Option<String>,
+
+    /// Press Enter twice to fire
+    #[arg(long)]
+    confirm_fire: bool,
+
+    /// Turn off the water animation
+    #[arg(long)]
+    reduced_motion: bool,
+
+    /// Custom ship names in placement order, comma separated
+    #[arg(long, value_name = "A,B,..")]
+    ship_names: Option<String>,
+}
+
+impl SettingsArgs {
+    pub fn load(&self) -> Result<Settings> {
+        let mut settings = Settings::load(self.settings.as_deref())?;
+        if self.reduced_motion {
+            settings.reduced_motion = true;
+        }
+        if self.confirm_fire {
+            settings.confirm_fire = true;
+        }
+        if let Some(names) = &self.ship_names {
+            settings.ship_names = names.split(',').map(str::to_string).collect();
+        }
+        Ok(settings)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct ClientArgs {
+    /// Server to connect to
+    #[arg(value_name = "HOST:PORT", default_value = "127.0.0.1:8080")]
+    pub addr: String,
+
+    /// Watch two AIs play each other locally
+    #[arg(long, conflicts_with_all = ["browse", "coach"])]
+    pub demo: bool,
+
+    /// Pick a server from the directory at URL
+    #[arg(long, value_name = "URL")]
+    pub browse: Option<String>,
+
+    /// Pick a server on the local network
+    #[arg(long, conflicts_with_all = ["demo", "browse"])]
+    pub lan: bool,
+
+    /// Connect over TLS, checking the server's certificate
+    #[arg(long, conflicts_with = "demo")]
+    pub tls: bool,
+
+    /// With --tls, accept any certificate (e.g. self-signed)
+    #[arg(long, requires = "tls")]
+    pub insecure: bool,
+
+    /// Password for a server started with --password (asked for if omitted)
+    #[arg(long)]
+    pub password: Option<String>,
+
+    /// Connect to a server started with --websocket
+    #[arg(long, conflicts_with = "demo")]
+    pub websocket: bool,
+
+    /// Ask the server for compact MessagePack instead of JSON
+    #[arg(long, conflicts_with = "demo")]
+    pub msgpack: bool,
+
+    /// Ask the server to deflate large messages, for slow links
+    #[arg(long, conflicts_with = "demo")]
+    pub compress: bool,
+
+    #[command(flatten)]
+    pub settings: SettingsArgs,
+
+    /// Display name shown to your opponent
+    #[arg(long, value_name = "NAME")]
+    pub name: Option<String>,
+
+    /// Follow a player as their coach and send hints (H), with the coach
+    /// code the server gave that player
+    #[arg(long, value_name = "CODE")]
+    pub coach: Option<String>,
+
+    /// Open a private room and get a code for your opponent
+    #[arg(long, conflicts_with_all = ["demo", "coach", "join_room"])]
+    pub host_room: bool,
+
+    /// Join the private room with this code
+    #[arg(long, value_name = "CODE", conflicts_with_all = ["demo", "coach"])]
+    pub join_room: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct PlayAiArgs {
+    /// easy, normal or hard: how well the AI shoots
+    #[arg(long, value_name = "LEVEL", default_value = "normal")]
+    pub difficulty: Difficulty,
+
+    /// How long the AI thinks before each shot, in milliseconds: a range
+    /// like 500-1500, or one number [default: no delay]
+    #[arg(long, value_name = "MS")]
+    pub think_time: Option<ThinkTime>,
+
+    /// Practice mode: press H on your turn to have the AI point out the
+    /// likeliest cell to fire at, up to this many times a game
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    pub hints: Option<u32>,
+
+    #[command(flatten)]
+    pub settings: SettingsArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct HotseatArgs {
+    /// Name of the player who places first
+    #[arg(long, value_name = "NAME", default_value = "Player 1")]
+    pub player1: String,
+
+    /// Name of the other player
+    #[arg(long, value_name = "NAME", default_value = "Player 2")]
+    pub player2: String,
+
+    /// JSON settings file [default: ~/.config/battleship-rs/settings.json]
+    #[arg(long, value_name = "FILE")]
+    pub settings: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct AiVsAiArgs {
+    /// easy, normal or hard: the first AI, A
+    #[arg(long, value_name = "LEVEL", default_value = "normal")]
+    pub ai_a: Difficulty,
+
+    /// easy, normal or hard: the second AI, B
+    #[arg(long, value_name = "LEVEL", default_value = "normal")]
+    pub ai_b: Difficulty,
+
+    /// How many games to play; the AIs take turns firing first
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    pub games: u32,
+
+    /// Watch the games in the TUI from A's side instead of only printing results
+    #[arg(long)]
+    pub watch: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct SimulateArgs {
+    /// easy, normal or hard (or random, hunt or heatmap): the first AI, A
+    #[arg(long, value_name = "LEVEL", default_value = "normal")]
+    pub ai_a: Difficulty,
+
+    /// easy, normal or hard (or random, hunt or heatmap): the second AI, B
+    #[arg(long, value_name = "LEVEL", default_value = "easy")]
+    pub ai_b: Difficulty,
+
+    /// How many games to play; the AIs take turns firing first
+    #[arg(long, value_name = "N", default_value_t = 1000)]
+    pub games: u32,
+
+    /// Seed for every random choice, to repeat a run exactly [default: random]
+    #[arg(long, value_name = "N")]
+    pub seed: Option<u64>,
+
+    /// Print the results as JSON instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
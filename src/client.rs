@@ -1,185 +1,981 @@
 use anyhow::Result;
 use crossterm::{
-    event::{self, Event, KeyEventKind},
+    event::{self, Event, KeyCode, KeyEventKind},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
+use rand::Rng;
 use ratatui::{Terminal, backend::CrosstermBackend};
 use std::{
-    io::{self, BufRead, BufReader, Write},
-    net::TcpStream,
+    io,
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tokio::sync::mpsc;
 
-use crate::game_state::GameState;
+use crate::ai::Difficulty;
+use crate::ai_audit::AiView;
+use crate::analysis;
+use crate::codec;
+use crate::coords;
+use crate::engine::Board;
+use crate::game_state::{GameState, Lobby, Series};
 use crate::input::handle_key_event;
-use crate::types::{CellState, GamePhase, Message};
+use crate::net::{self, ClientTransport, Frame, TimedWriter, Timeouts};
+use crate::notify::{Notifier, NotifyEvent};
+use crate::practice::Practice;
+use crate::server_ai::{AiGame, Reply, ThinkTime};
+use crate::settings::Settings;
+use crate::snapshot;
+use crate::types::{CellState, ErrorCode, GRID_SIZE, GamePhase, Message, SHIPS, SeaEvent};
 use crate::ui::draw_ui;
 
-pub async fn run_client(addr: &str) -> Result<()> {
-    let stream = TcpStream::connect(addr)?;
-    // Keep both streams blocking - we'll handle this properly
-    let read_stream = stream.try_clone()?;
-    let write_stream = stream;
+const RECONNECT_BASE_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+/// How long the client waits on exit for its last messages to be sent.
+const QUIT_GRACE: Duration = Duration::from_secs(1);
+const DEMO_STEP: Duration = Duration::from_millis(400);
+const DEMO_RESTART_DELAY: Duration = Duration::from_secs(5);
 
-    let (tx, mut rx) = mpsc::unbounded_channel();
-    let state = Arc::new(Mutex::new(GameState::new()));
-    let state_clone = state.clone();
-
-    // Network receiver thread - blocking reads
-    tokio::task::spawn_blocking(move || {
-        let mut reader = BufReader::new(read_stream);
-        loop {
-            let mut line = String::new();
-            match reader.read_line(&mut line) {
-                Ok(0) => {
-                    break;
-                }
-                Ok(_) => {
-                    if let Ok(msg) = serde_json::from_str::<Message>(&line) {
-                        let mut state = state_clone.lock().unwrap();
-                        match msg {
-                            Message::WaitingForOpponent => {
-                                state
-                                    .messages
-                                    .push("Waiting for opponent to place ships...".to_string());
-                            }
-                            Message::GameStart => {
-                                state.messages.push("Game starting!".to_string());
-                            }
-                            Message::YourTurn => {
-                                state.phase = GamePhase::YourTurn;
-                                state.turn_count += 1;
-                                state.start_turn();
-                                state.messages.push("Your turn!".to_string());
-                            }
-                            Message::OpponentTurn => {
-                                state.end_turn();
-                                state.phase = GamePhase::OpponentTurn;
-                                state.messages.push("Opponent's turn...".to_string());
-                            }
-                            Message::Attack { x, y } => {
-                                let hit = state.own_grid[y][x] == CellState::Ship;
-                                state.own_grid[y][x] =
-                                    if hit { CellState::Hit } else { CellState::Miss };
-                                if hit {
-                                    state.messages.push(format!(
-                                        "Enemy hit your ship at {}!",
-                                        crate::game_state::GameState::format_coordinate(x, y)
-                                    ));
-                                } else {
-                                    state.messages.push(format!(
-                                        "Enemy missed at {}",
-                                        crate::game_state::GameState::format_coordinate(x, y)
-                                    ));
-                                }
-                            }
-                            Message::AttackResult { x, y, hit, sunk } => {
-                                state.enemy_grid[y][x] =
-                                    if hit { CellState::Hit } else { CellState::Miss };
-                                state.record_shot(hit);
-                                state.update_ship_status();
-
-                                if hit {
-                                    state.messages.push(if sunk {
-                                        format!(
-                                            "HIT at {}! Ship sunk!",
-                                            crate::game_state::GameState::format_coordinate(x, y)
-                                        )
-                                    } else {
-                                        format!(
-                                            "HIT at {}!",
-                                            crate::game_state::GameState::format_coordinate(x, y)
-                                        )
-                                    });
-                                } else {
-                                    state.messages.push(format!(
-                                        "Miss at {}",
-                                        crate::game_state::GameState::format_coordinate(x, y)
-                                    ));
-                                }
-                            }
-                            Message::GameOver { won } => {
-                                state.phase = GamePhase::GameOver;
-                                state.winner = Some(won);
-                                state.messages.push(if won {
-                                    "🎉 YOU WIN! 🎉".to_string()
-                                } else {
-                                    "💀 YOU LOSE! 💀".to_string()
-                                });
-                            }
-                            Message::PlayAgainRequest => {
-                                state.phase = GamePhase::PlayAgainPrompt;
-                                state
-                                    .messages
-                                    .push("Do you want to play again? (Y/N)".to_string());
-                            }
-                            Message::PlayAgainResponse { wants_to_play } => {
-                                if wants_to_play {
-                                    state
-                                        .messages
-                                        .push("Opponent wants to play again!".to_string());
-                                } else {
-                                    state
-                                        .messages
-                                        .push("Opponent doesn't want to play again.".to_string());
-                                }
-                            }
-                            Message::PlayAgainTimeout => {
-                                state
-                                    .messages
-                                    .push("Play again timeout - ending game.".to_string());
-                            }
-                            Message::OpponentQuit => {
-                                state
-                                    .messages
-                                    .push("Opponent has quit the game.".to_string());
-                                state.phase = GamePhase::GameOver;
-                            }
-                            Message::NewGameStart => {
-                                state.reset_for_new_game();
-                                state
-                                    .messages
-                                    .push("New game starting! Place your ships.".to_string());
-                            }
-                            Message::Quit => {
-                                state.messages.push("You have quit the game.".to_string());
-                                state.phase = GamePhase::GameOver;
-                            }
-                            _ => {}
+/// Applies a message received from the server to the local game state. One
+/// that names a cell off the board, or carries a board of the wrong size, is
+/// reported and otherwise ignored.
+pub fn apply_server_message(state: &mut GameState, msg: Message) {
+    state.perf.message();
+    if !on_board(&msg) {
+        state.messages.push(format!(
+            "Ignored the server's {}: it does not fit the {}x{} board",
+            msg.name(),
+            GRID_SIZE,
+            GRID_SIZE
+        ));
+        return;
+    }
+    match msg {
+        Message::WaitingForOpponent => {
+            state
+                .messages
+                .push("Waiting for opponent to place ships...".to_string());
+        }
+        Message::GameStart => {
+            state.messages.push("Game starting!".to_string());
+        }
+        Message::YourTurn => {
+            state.phase = GamePhase::YourTurn;
+            state.viewing_turn = None;
+            state.turn_count += 1;
+            state.start_turn();
+            state.messages.push("Your turn!".to_string());
+            state.alerts.push(NotifyEvent::TurnStart);
+        }
+        Message::OpponentTurn => {
+            state.end_turn();
+            state.phase = GamePhase::OpponentTurn;
+            let text = format!("{}'s turn...", state.opponent());
+            state.messages.push(text);
+        }
+        Message::OpponentThinking => {
+            let text = format!("{} is thinking...", state.opponent());
+            state.messages.push(text);
+        }
+        Message::Attack { x, y } => {
+            let shot = state.own_grid.fire(x, y);
+            let enemy = state.opponent_name.as_deref().unwrap_or("Enemy");
+            if shot.mine {
+                let text = format!(
+                    "{} fired into your mine at {}!",
+                    enemy,
+                    coords::format(x, y)
+                );
+                state.messages.push(text);
+            } else if shot.hit {
+                let ship = shot.ship.map_or("ship", |kind| state.own_ship_name(kind));
+                let text = if shot.sunk {
+                    format!("{} sank your {} at {}!", enemy, ship, coords::format(x, y))
+                } else {
+                    format!("{} hit your {} at {}!", enemy, ship, coords::format(x, y))
+                };
+                state.messages.push(text);
+                state.alerts.push(NotifyEvent::HitReceived);
+                if shot.sunk {
+                    state.alerts.push(NotifyEvent::Sink);
+                }
+            } else {
+                let text = format!("{} missed at {}", enemy, coords::format(x, y));
+                state.messages.push(text);
+            }
+            state.snapshot_boards();
+        }
+        Message::AttackResult {
+            x,
+            y,
+            hit,
+            sunk,
+            ship,
+            revealed,
+        } => {
+            state.enemy_grid[y][x] = if hit { CellState::Hit } else { CellState::Miss };
+            for &(rx, ry) in &revealed {
+                if state.enemy_grid[ry][rx] == CellState::Empty {
+                    state.enemy_grid[ry][rx] = CellState::Miss;
+                }
+            }
+            state.record_shot(x, y, hit, sunk);
+            state.update_ship_status();
+            if let Some(practice) = state.practice.as_mut() {
+                practice.record_shot(x, y, hit, ship.as_ref().map(|ship| ship.length));
+            }
+
+            if sunk {
+                state.alerts.push(NotifyEvent::Sink);
+            }
+            if hit {
+                state.messages.push(match (sunk, &ship) {
+                    (true, Some(ship)) => format!(
+                        "HIT at {}! You sank the enemy {}!",
+                        coords::format(x, y),
+                        ship.name
+                    ),
+                    (true, None) => format!("HIT at {}! Ship sunk!", coords::format(x, y)),
+                    (false, _) => format!("HIT at {}!", coords::format(x, y)),
+                });
+                state.enemy_ships_sunk.extend(ship);
+            } else {
+                state
+                    .messages
+                    .push(format!("Miss at {}", coords::format(x, y)));
+            }
+            state.snapshot_boards();
+        }
+        Message::PlaceShips(grid) => {
+            // Only coaches are sent this: the fleet of the player they follow
+            state.own_grid = grid;
+            state.placing_ship_idx = SHIPS.len();
+            state.phase = GamePhase::WaitingForOpponent;
+        }
+        Message::Hint { text } => {
+            if state.coaching.is_some() {
+                state.messages.push(format!("You (hint): {}", text));
+            } else {
+                state.toast = Some((format!("Coach: {}", text), Instant::now()));
+                state.messages.push(format!("Coach: {}", text));
+                state.alerts.push(NotifyEvent::Chat);
+            }
+        }
+        Message::Chat { text } => {
+            let text = format!("{}: {}", state.opponent(), text);
+            state.messages.push(text);
+            state.alerts.push(NotifyEvent::Chat);
+        }
+        Message::Emote(emote) => {
+            let text = format!("{}: {}", state.opponent(), emote.text());
+            state.toast = Some((text, Instant::now()));
+            state.alerts.push(NotifyEvent::Chat);
+        }
+        Message::SeaEvent(event) => {
+            let text = match event {
+                SeaEvent::Fog { yours: true } => {
+                    "🌫 Fog rolls in: the result of your next shot arrives a turn late".to_string()
+                }
+                SeaEvent::Fog { yours: false } => {
+                    "🌫 Fog rolls over the enemy: their next result arrives a turn late".to_string()
+                }
+                SeaEvent::Current {
+                    yours: true,
+                    from,
+                    to,
+                } => {
+                    state.own_grid.move_ship(&from, &to);
+                    format!(
+                        "🌊 A current pushed your ship at {} a cell",
+                        coords::format(from[0].0, from[0].1)
+                    )
+                }
+                SeaEvent::Current { yours: false, .. } => {
+                    "🌊 A current moved one of the enemy ships".to_string()
+                }
+                SeaEvent::Squall {
+                    yours: true, x, y, ..
+                } => format!(
+                    "⛈ A squall revealed {} on your board to the enemy",
+                    coords::format(x, y)
+                ),
+                SeaEvent::Squall {
+                    yours: false,
+                    x,
+                    y,
+                    ship,
+                } => {
+                    if state.enemy_grid[y][x] == CellState::Empty {
+                        state.enemy_grid[y][x] = if ship {
+                            CellState::Ship
+                        } else {
+                            CellState::Miss
+                        };
+                    }
+                    format!(
+                        "⛈ A squall revealed {}: {}",
+                        coords::format(x, y),
+                        if ship { "a ship!" } else { "open water" }
+                    )
+                }
+            };
+            state.toast = Some((text.clone(), Instant::now()));
+            state.messages.push(text);
+        }
+        Message::Minefield { mines } => {
+            state.mines = mines;
+            if state.coaching.is_none() {
+                state.messages.push(format!(
+                    "This server plays with mines: lay {} on open water after your ships",
+                    mines
+                ));
+            }
+        }
+        Message::RevealSunk => {
+            state.ships_apart = true;
+            if state.coaching.is_none() {
+                state.messages.push(
+                    "This server reveals the water around sunk ships: place yours so none touch"
+                        .to_string(),
+                );
+            }
+        }
+        Message::MineDetonated {
+            yours,
+            x,
+            y,
+            sunk,
+            ship,
+        } => {
+            let text = if yours {
+                let shot = state.own_grid.fire(x, y);
+                state.update_ship_status();
+                state.alerts.push(NotifyEvent::HitReceived);
+                format!(
+                    "💥 You hit a mine! The blast struck your {} at {}{}",
+                    shot.ship.map_or("ship", |kind| state.own_ship_name(kind)),
+                    coords::format(x, y),
+                    if sunk { " and sank it" } else { "" }
+                )
+            } else {
+                state.enemy_grid[y][x] = CellState::Hit;
+                if sunk {
+                    state.enemy_sinks.push((x, y));
+                }
+                state.enemy_ships_sunk.extend(ship);
+                format!(
+                    "💥 {} hit your mine! The blast struck their ship at {}{}",
+                    state.opponent(),
+                    coords::format(x, y),
+                    if sunk { " and sank it" } else { "" }
+                )
+            };
+            if sunk {
+                state.alerts.push(NotifyEvent::Sink);
+            }
+            state.toast = Some((text.clone(), Instant::now()));
+            state.messages.push(text);
+            state.snapshot_boards();
+        }
+        Message::GameOver { won } => {
+            state.phase = GamePhase::GameOver;
+            state.winner = Some(won);
+            state.viewing_turn = None;
+            state.show_result_screen = true;
+            state.alerts.push(NotifyEvent::GameOver);
+            state.messages.push(if won {
+                "🎉 YOU WIN! 🎉".to_string()
+            } else {
+                "💀 YOU LOSE! 💀".to_string()
+            });
+        }
+        Message::PlayAgainRequest => {
+            state.phase = GamePhase::PlayAgainPrompt;
+            let question = state.play_again_question();
+            state.messages.push(question.to_string());
+        }
+        Message::DrawOffer => {
+            state.draw_offered = true;
+            let text = format!("{} offers a draw - Y: accept, N: decline", state.opponent());
+            state.toast = Some((text.clone(), Instant::now()));
+            state.messages.push(text);
+            state.alerts.push(NotifyEvent::Chat);
+        }
+        Message::DrawResponse { .. } => {
+            state.offered_draw = false;
+            let text = format!("{} declined the draw", state.opponent());
+            state.messages.push(text);
+        }
+        Message::GameDrawn => {
+            state.phase = GamePhase::GameOver;
+            state.drawn = true;
+            state.viewing_turn = None;
+            state.offered_draw = false;
+            state.draw_offered = false;
+            state.alerts.push(NotifyEvent::GameOver);
+            state
+                .messages
+                .push("🤝 The game is drawn by agreement".to_string());
+        }
+        Message::GamePaused { by_you } => {
+            state.phase = GamePhase::Paused;
+            state.paused_by_you = by_you;
+            state.pending_shot = None;
+            state.turn_deadline = None;
+            state.messages.push(if by_you {
+                "⏸ You paused the game".to_string()
+            } else {
+                format!("⏸ {} paused the game", state.opponent())
+            });
+        }
+        Message::GameUnpaused { your_turn } => {
+            state.phase = if your_turn {
+                GamePhase::YourTurn
+            } else {
+                GamePhase::OpponentTurn
+            };
+            state.messages.push(if your_turn {
+                "▶ The game carries on. Your turn!".to_string()
+            } else {
+                format!("▶ The game carries on. {}'s turn...", state.opponent())
+            });
+            if your_turn {
+                state.alerts.push(NotifyEvent::TurnStart);
+            }
+        }
+        Message::CoinToss { you_first } => {
+            state.messages.push(if you_first {
+                "🪙 You won the coin toss and fire first".to_string()
+            } else {
+                format!("🪙 {} won the coin toss and fires first", state.opponent())
+            });
+        }
+        Message::SeriesScore {
+            wins,
+            losses,
+            best_of,
+            won,
+        } => {
+            state.series = Some(Series {
+                wins,
+                losses,
+                best_of,
+            });
+            state.messages.push(match won {
+                Some(true) => format!("🏆 You win the series {}-{}!", wins, losses),
+                Some(false) => format!("You lose the series {}-{}", wins, losses),
+                None => format!("Series: {}-{} (best of {})", wins, losses, best_of),
+            });
+        }
+        Message::GameReview {
+            opponent_board,
+            shots,
+            ..
+        } => {
+            state.analysis = Some(analysis::analyse(&opponent_board, &shots));
+            state
+                .messages
+                .push("📊 Post-game analysis ready - A: view".to_string());
+        }
+        Message::PlayAgainResponse { wants_to_play } => {
+            if wants_to_play {
+                state
+                    .messages
+                    .push(format!("{} wants to play again!", state.opponent()));
+            } else {
+                state
+                    .messages
+                    .push(format!("{} doesn't want to play again.", state.opponent()));
+            }
+        }
+        Message::PlayAgainTimeout => {
+            state
+                .messages
+                .push("Play again timeout - ending game.".to_string());
+        }
+        Message::OpponentQuit => {
+            state
+                .messages
+                .push(format!("{} has quit the game.", state.opponent()));
+            state.phase = GamePhase::GameOver;
+        }
+        Message::OpponentDisconnected {
+            reconnect_window_secs,
+        } => {
+            state.phase = GamePhase::OpponentDisconnected;
+            state.reconnect_window_secs = reconnect_window_secs;
+            state.messages.push(format!(
+                "{} disconnected! C: claim victory, W: wait up to {}s for them",
+                state.opponent(),
+                reconnect_window_secs
+            ));
+        }
+        Message::Error {
+            code: ErrorCode::IncompatibleVersion,
+            detail,
+        } => {
+            // Reconnecting would not help, so stop here
+            state.messages.push(format!(
+                "Incompatible server: {}. Update battleship-rs on both ends.",
+                detail
+            ));
+            state.phase = GamePhase::GameOver;
+        }
+        Message::Error {
+            code: ErrorCode::RoomNotFound,
+            detail,
+        } => {
+            // Reconnecting would only be refused again
+            state.messages.push(format!("Could not join: {}", detail));
+            state.phase = GamePhase::GameOver;
+        }
+        Message::Error {
+            code: ErrorCode::Unauthorized,
+            detail,
+        } => {
+            // The connection waits for the password before trying again
+            state.messages.push(format!(
+                "The server turned us away: {}. Type its password and press Enter",
+                detail
+            ));
+            state.password_draft = Some(String::new());
+        }
+        Message::Error {
+            code: ErrorCode::SessionExpired,
+            detail,
+        } => {
+            // The next reconnect joins a new game instead
+            state.session_token = None;
+            state.messages.push(format!("Could not resume: {}", detail));
+        }
+        Message::Error { code, detail } => {
+            state
+                .messages
+                .push(format!("Server rejected request: {}", detail));
+            // The server keeps the turn or the placement open, so let the player retry
+            match code {
+                ErrorCode::InvalidTarget if state.phase == GamePhase::OpponentTurn => {
+                    state.phase = GamePhase::YourTurn;
+                }
+                ErrorCode::InvalidPlacement if state.phase == GamePhase::WaitingForOpponent => {
+                    state.phase = GamePhase::Placing;
+                }
+                _ => {}
+            }
+        }
+        Message::TurnClock {
+            seconds_left,
+            warning,
+        } => {
+            let on_turn = state.phase == GamePhase::YourTurn;
+            if seconds_left == 0 {
+                state.turn_deadline = None;
+                state.pending_shot = None;
+                state.messages.push(if on_turn {
+                    "⏰ Time's up!".to_string()
+                } else {
+                    format!("⏰ {} ran out of time", state.opponent())
+                });
+            } else {
+                state.turn_deadline = Some(Instant::now() + Duration::from_secs(seconds_left));
+                if warning && on_turn {
+                    state
+                        .messages
+                        .push(format!("⏰ {}s left to fire!", seconds_left));
+                    state.alerts.push(NotifyEvent::TurnStart);
+                }
+            }
+        }
+        Message::OpponentName { name } => {
+            if let Some(name) = &name {
+                state.messages.push(format!("You are playing {}", name));
+            }
+            state.opponent_name = name;
+        }
+        Message::Latency { rtt_ms } => {
+            state.latency = Some(Duration::from_millis(rtt_ms));
+        }
+        Message::SessionToken { token } => {
+            state.session_token = Some(token);
+        }
+        Message::CoachCode { code } => {
+            state.messages.push(format!(
+                "Your coach code is {}: a coach joins with --coach {} and sees your fleet",
+                code, code
+            ));
+        }
+        Message::GameResumed {
+            own,
+            enemy,
+            your_turn,
+        } => {
+            state.own_grid = own;
+            state.enemy_grid = enemy.to_vec();
+            state.placing_ship_idx = SHIPS.len();
+            state.update_ship_status();
+            state.phase = if your_turn {
+                GamePhase::YourTurn
+            } else {
+                GamePhase::OpponentTurn
+            };
+            state.messages.push(if your_turn {
+                "Game resumed. Your turn!".to_string()
+            } else {
+                format!("Game resumed. {}'s turn...", state.opponent())
+            });
+        }
+        Message::OpponentReconnected { your_turn } => {
+            state.phase = if your_turn {
+                GamePhase::YourTurn
+            } else {
+                GamePhase::OpponentTurn
+            };
+            state.messages.push(format!(
+                "{} reconnected. The game continues",
+                state.opponent()
+            ));
+        }
+        Message::RoomCreated { code } => {
+            state.messages.push(format!(
+                "Room {} is open. Your opponent joins with --join-room {}",
+                code, code
+            ));
+            state.lobby = Lobby::Join(code);
+        }
+        Message::NewGameStart => {
+            state.reset_for_new_game();
+            // The server starts a new series once one is decided
+            if let Some(series) = state.series.as_mut().filter(|series| series.decided()) {
+                series.wins = 0;
+                series.losses = 0;
+            }
+            state
+                .messages
+                .push("New game starting! Place your ships.".to_string());
+        }
+        Message::Quit => {
+            state.messages.push("You have quit the game.".to_string());
+            state.phase = GamePhase::GameOver;
+        }
+        _ => {}
+    }
+}
+
+/// Whether every cell and board in `msg` fits the board, so applying it
+/// cannot index past the grid.
+fn on_board(msg: &Message) -> bool {
+    let cell = |&(x, y): &(usize, usize)| x < GRID_SIZE && y < GRID_SIZE;
+    let board =
+        |board: &Board| board.len() == GRID_SIZE && board.iter().all(|row| row.len() == GRID_SIZE);
+    match msg {
+        Message::Attack { x, y } | Message::MineDetonated { x, y, .. } => cell(&(*x, *y)),
+        Message::AttackResult { x, y, revealed, .. } => {
+            cell(&(*x, *y)) && revealed.iter().all(cell)
+        }
+        Message::SeaEvent(SeaEvent::Current {
+            yours: true,
+            from,
+            to,
+        }) => !from.is_empty() && from.len() == to.len() && from.iter().chain(to).all(cell),
+        Message::SeaEvent(SeaEvent::Squall { x, y, .. }) => cell(&(*x, *y)),
+        Message::PlaceShips(grid) => board(grid),
+        Message::GameResumed { own, enemy, .. } => board(own) && board(enemy),
+        Message::GameReview {
+            your_board,
+            opponent_board,
+            shots,
+        } => {
+            board(your_board)
+                && board(opponent_board)
+                && shots.iter().all(|shot| cell(&(shot.x, shot.y)))
+        }
+        _ => true,
+    }
+}
+
+/// Why a connection ended.
+enum ReadEnd {
+    Closed,
+    /// Nothing arrived for this long.
+    TimedOut(Duration),
+    Failed,
+}
+
+/// Applies one frame from the server and returns the reply it calls for, if
+/// any. `greeted` tracks whether the server has answered our Hello yet; once
+/// it has, `writer` switches to whichever codec the server agreed to.
+fn read_frame(
+    frame: &Frame,
+    greeted: &mut bool,
+    writer: &mut TimedWriter,
+    state: &Mutex<GameState>,
+) -> Option<Message> {
+    let msg = frame.decode();
+    if !*greeted {
+        // A server that predates the handshake answers our Hello
+        // with an Error in the old format, which does not parse
+        match &msg {
+            Ok(Message::Hello {
+                codec, compression, ..
+            }) => {
+                *greeted = true;
+                if let Some(codec) = codec.as_deref().and_then(codec::named) {
+                    writer.set_codec(codec);
+                }
+                if compression.as_deref() == Some(codec::DEFLATE) {
+                    writer.set_deflate();
+                }
+            }
+            Ok(_) => {}
+            Err(_) => {
+                *greeted = true;
+                state.lock().unwrap().messages.push(
+                    "The server did not understand the version handshake; it is probably an older battleship-rs".to_string(),
+                );
+            }
+        }
+    }
+    match msg {
+        Ok(Message::Ping) => Some(Message::Pong),
+        Ok(msg) => {
+            apply_server_message(&mut state.lock().unwrap(), msg);
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+/// Delay before reconnect attempt `attempt` (1-based): exponential with up to 50% jitter.
+fn reconnect_delay(attempt: u32, rng: &mut impl Rng) -> Duration {
+    let base = RECONNECT_BASE_DELAY
+        .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+        .min(RECONNECT_MAX_DELAY);
+    base + base.mul_f64(rng.random_range(0.0..0.5))
+}
+
+/// Keeps the client connected: retries failed connects with backoff, and
+/// reconnects if the connection drops before the game is over. Outgoing
+/// messages queue up in `rx` while disconnected. Returns once the UI closes
+/// `rx` or the player quits.
+async fn run_connection(
+    addr: String,
+    timeouts: Timeouts,
+    transport: ClientTransport,
+    state: Arc<Mutex<GameState>>,
+    mut rx: mpsc::UnboundedReceiver<Message>,
+) {
+    let mut connected_before = false;
+
+    loop {
+        // Connect, backing off between failed attempts
+        let mut attempt = 0;
+        let (mut frames, mut writer) = loop {
+            attempt += 1;
+            let doing = if connected_before {
+                "Reconnecting"
+            } else {
+                "Connecting"
+            };
+            state.lock().unwrap().connection_status =
+                Some(format!("{} to {} (attempt {})...", doing, addr, attempt));
+            match net::open(&addr, &timeouts, &transport).await {
+                Ok(halves) => break halves,
+                Err(e) => {
+                    let delay = reconnect_delay(attempt, &mut rand::rng());
+                    state.lock().unwrap().messages.push(format!(
+                        "Connection attempt {} failed: {}. Retrying in {:.1}s",
+                        attempt,
+                        e,
+                        delay.as_secs_f64()
+                    ));
+                    tokio::time::sleep(delay).await;
+                    if rx.is_closed() {
+                        return;
+                    }
+                }
+            }
+        };
+
+        {
+            let mut state = state.lock().unwrap();
+            state.connection_status = None;
+            if connected_before && state.session_token.is_some() {
+                state
+                    .messages
+                    .push("Reconnected. Resuming the game...".to_string());
+            } else if connected_before {
+                state.reset_for_new_game();
+                state.messages.push(
+                    "Reconnected. The previous game was lost - place your ships again.".to_string(),
+                );
+            } else if attempt > 1 {
+                state.messages.push(format!("Connected to {}", addr));
+            }
+        }
+
+        let (coaching, lobby, token, name, password) = {
+            let state = state.lock().unwrap();
+            (
+                state.coaching.clone(),
+                state.lobby.clone(),
+                state.session_token.clone(),
+                state.settings.name.clone(),
+                state.password.clone(),
+            )
+        };
+        let mut greeting = vec![Message::hello(
+            name,
+            transport.codec.map(|codec| codec.name()),
+            transport.compress.then_some(codec::DEFLATE),
+            password,
+        )];
+        greeting.extend(coaching.map(|code| Message::Coach { code }));
+        match (token, lobby) {
+            (Some(token), _) => greeting.push(Message::ResumeGame { token }),
+            (None, Lobby::Public) => {}
+            (None, Lobby::Host) => greeting.push(Message::CreateRoom),
+            (None, Lobby::Join(code)) => greeting.push(Message::JoinRoom { code }),
+        }
+        for msg in &greeting {
+            let _ = writer.send(msg);
+        }
+
+        // Apply server messages and pump outgoing ones until the connection
+        // ends or the UI quits
+        let mut greeted = false;
+        let mut last_heard = tokio::time::Instant::now();
+        // Once the server pings, a silence of a few pings means the link is dead
+        let mut read_timeout = timeouts.read;
+        let end = loop {
+            let silence = async {
+                match read_timeout {
+                    Some(timeout) => tokio::time::sleep_until(last_heard + timeout).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                frame = frames.next_frame() => match frame {
+                    Ok(Some(frame)) => {
+                        last_heard = tokio::time::Instant::now();
+                        if let Some(reply) = read_frame(&frame, &mut greeted, &mut writer, &state) {
+                            read_timeout = Some(read_timeout.map_or(net::HEARTBEAT_TIMEOUT, |t| {
+                                t.min(net::HEARTBEAT_TIMEOUT)
+                            }));
+                            let _ = writer.send(&reply);
                         }
                     }
+                    Ok(None) => break ReadEnd::Closed,
+                    Err(_) => break ReadEnd::Failed,
+                },
+                msg = rx.recv() => {
+                    let Some(msg) = msg else {
+                        writer.close().await;
+                        return;
+                    };
+                    let quitting = matches!(msg, Message::Quit);
+                    if writer.send(&msg).is_err() {
+                        break ReadEnd::Failed;
+                    }
+                    if quitting {
+                        writer.close().await;
+                        return;
+                    }
+                }
+                _ = silence => break ReadEnd::TimedOut(read_timeout.unwrap_or_default()),
+            }
+        };
+        writer.shutdown();
+        state.lock().unwrap().latency = None;
+        // A connection the server turned away before answering Hello never
+        // got as far as a game
+        connected_before |= greeted;
+
+        if state.lock().unwrap().password_draft.is_some() {
+            // Try again once the player has typed the password
+            while state.lock().unwrap().password_draft.is_some() {
+                if rx.is_closed() {
+                    return;
                 }
-                Err(_) => break,
+                tokio::time::sleep(net::POLL_INTERVAL).await;
             }
+            continue;
         }
-    });
 
-    // Network sender - also blocking
-    tokio::task::spawn_blocking(move || {
-        let mut writer = write_stream;
-        while let Some(msg) = rx.blocking_recv() {
-            let json = serde_json::to_string(&msg).unwrap() + "\n";
-            if writer.write_all(json.as_bytes()).is_err() {
-                break;
+        let mut state = state.lock().unwrap();
+        if state.winner.is_some() || state.phase == GamePhase::GameOver {
+            // The session finished normally; the server closing is expected
+            if matches!(end, ReadEnd::TimedOut(_)) {
+                state
+                    .messages
+                    .push("Connection timed out after the game ended.".to_string());
             }
-            let _ = writer.flush();
+            return;
         }
-    });
+        state.messages.push(match end {
+            ReadEnd::TimedOut(silence) => format!(
+                "Connection timed out: no data from server for {}s. Reconnecting...",
+                silence.as_secs()
+            ),
+            ReadEnd::Closed | ReadEnd::Failed => "Connection lost. Reconnecting...".to_string(),
+        });
+    }
+}
+
+/// The other end of the client: a server over the network, or an opponent
+/// in this process. It takes the player's messages from the UI and applies
+/// whatever comes back to the shared game state.
+pub trait Backend: Send + 'static {
+    /// Runs until the UI closes `rx` or the player quits.
+    fn run(
+        self,
+        state: Arc<Mutex<GameState>>,
+        rx: mpsc::UnboundedReceiver<Message>,
+    ) -> impl Future<Output = ()> + Send;
+}
+
+/// A server at `addr`, reconnected to whenever the connection drops.
+pub struct Remote {
+    pub addr: String,
+    pub timeouts: Timeouts,
+    pub transport: ClientTransport,
+}
+
+impl Backend for Remote {
+    fn run(
+        self,
+        state: Arc<Mutex<GameState>>,
+        rx: mpsc::UnboundedReceiver<Message>,
+    ) -> impl Future<Output = ()> + Send {
+        run_connection(self.addr, self.timeouts, self.transport, state, rx)
+    }
+}
+
+/// The AI opponent, played in this process with no server or network.
+pub struct LocalAi {
+    pub difficulty: Difficulty,
+    pub think_time: Option<ThinkTime>,
+}
+
+impl Backend for LocalAi {
+    async fn run(self, state: Arc<Mutex<GameState>>, mut rx: mpsc::UnboundedReceiver<Message>) {
+        let strategy = self.difficulty.strategy();
+        let Ok(mut game) = AiGame::new(strategy, self.think_time, None, false) else {
+            return;
+        };
+        let apply = |replies: Vec<Message>| {
+            let mut state = state.lock().unwrap();
+            for reply in replies {
+                apply_server_message(&mut state, reply);
+            }
+        };
+        while let Some(msg) = rx.recv().await {
+            match game.handle(msg) {
+                Ok(Reply::Send(replies)) => apply(replies),
+                Ok(Reply::Think { now, delay, then }) => {
+                    apply(now);
+                    tokio::time::sleep(delay).await;
+                    apply(then);
+                }
+                Ok(Reply::Reject(code, detail)) => apply(vec![Message::Error { code, detail }]),
+                Ok(Reply::End) | Err(_) => return,
+            }
+        }
+    }
+}
+
+/// Runs the client UI. With `coach` set, follows the player who was given
+/// that coach code instead of playing; `lobby` picks how the player is matched. `password`
+/// is sent in `Hello`; if the server wants one, the player is asked for it.
+pub async fn run_client(
+    addr: &str,
+    timeouts: Timeouts,
+    transport: ClientTransport,
+    settings: Settings,
+    coach: Option<String>,
+    lobby: Lobby,
+    password: Option<String>,
+) -> Result<()> {
+    let mut game_state = GameState::with_settings(settings);
+    game_state.password = password;
+    if let Lobby::Join(code) = &lobby {
+        game_state.messages = vec![format!("Joining room {}...", code)];
+    }
+    game_state.lobby = lobby;
+    if coach.is_some() {
+        game_state.coaching = coach;
+        game_state.messages = vec!["Coaching. Press H to send your player a hint".to_string()];
+    }
+    let remote = Remote {
+        addr: addr.to_string(),
+        timeouts,
+        transport,
+    };
+    play(game_state, remote).await
+}
+
+/// Plays against the AI without a server: the AI runs inside the client.
+/// With `hints` set, this is practice mode, and the player may ask for that
+/// many hints a game.
+pub async fn run_play_ai(
+    settings: Settings,
+    difficulty: Difficulty,
+    think_time: Option<ThinkTime>,
+    hints: Option<usize>,
+) -> Result<()> {
+    let mut game_state = GameState::with_settings(settings);
+    game_state.opponent_name = Some("AI".to_string());
+    game_state.practice = hints.map(Practice::new);
+    let local = LocalAi {
+        difficulty,
+        think_time,
+    };
+    play(game_state, local).await
+}
+
+/// Runs the UI over `game_state` while `backend` carries the player's
+/// messages and applies the replies.
+async fn play(game_state: GameState, backend: impl Backend) -> Result<()> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let state = Arc::new(Mutex::new(game_state));
+    let connection = tokio::spawn(backend.run(state.clone(), rx));
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
+    let mut notifier = Notifier::new();
 
     loop {
-        terminal.draw(|f| {
+        let snapshot = state.lock().unwrap().snapshot_request.take();
+        let frame_start = Instant::now();
+        let frame = terminal.draw(|f| {
             let state = state.lock().unwrap();
             draw_ui(f, &state);
         })?;
+        {
+            let mut state = state.lock().unwrap();
+            state.perf.work(frame_start.elapsed());
+            state.perf.iteration();
+            state.perf.roll();
+        }
+        if let Some(format) = snapshot {
+            let saved = snapshot::save(frame.buffer, format);
+            state.lock().unwrap().messages.push(match saved {
+                Ok(path) => format!("Screen saved to {}", path.display()),
+                Err(e) => format!("Could not save screen: {}", e),
+            });
+        }
+
+        {
+            let mut state = state.lock().unwrap();
+            let alerts = std::mem::take(&mut state.alerts);
+            for event in alerts {
+                notifier.fire(event, &state.settings.notifications, terminal.backend_mut())?;
+            }
+        }
+        notifier.tick(terminal.backend_mut())?;
 
         if event::poll(Duration::from_millis(100))?
             && let Event::Key(key) = event::read()?
@@ -199,5 +995,119 @@ pub async fn run_client(addr: &str) -> Result<()> {
 
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+
+    // Give a final Quit the chance to reach the other end
+    drop(tx);
+    let _ = tokio::time::timeout(QUIT_GRACE, connection).await;
+    Ok(())
+}
+
+/// Attract mode: plays AI against AI locally and renders it in the normal UI.
+/// The "own" fleet belongs to the left-hand AI, which fires through the UI's
+/// channel at a `LocalAi` on the right as a player would; a new game starts a
+/// few seconds after each one ends. Only Q (quit) and S (side panel) are
+/// handled.
+pub async fn run_demo() -> Result<()> {
+    let mut rng = rand::rng();
+    let (tx, rx) = mpsc::unbounded_channel();
+    let state = Arc::new(Mutex::new(GameState::new()));
+    let right = LocalAi {
+        difficulty: Difficulty::Normal,
+        think_time: Some(ThinkTime {
+            min: DEMO_STEP,
+            max: DEMO_STEP,
+        }),
+    };
+    let opponent = tokio::spawn(right.run(state.clone(), rx));
+    let mut left = Difficulty::Normal.strategy();
+    let mut view = AiView::new();
+    // The left AI's shot still waiting for its result
+    let mut aimed: Option<(usize, usize)> = None;
+    let mut sinks = 0;
+    let mut next_step = Instant::now();
+    let mut restart_at: Option<Instant> = None;
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    loop {
+        terminal.draw(|f| draw_ui(f, &state.lock().unwrap()))?;
+
+        if event::poll(Duration::from_millis(50))?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Char('Q') => break,
+                KeyCode::Char('s') | KeyCode::Char('S') => {
+                    let mut state = state.lock().unwrap();
+                    state.side_panel = state.side_panel.next();
+                }
+                _ => {}
+            }
+        }
+
+        let mut state = state.lock().unwrap();
+        if let Some((x, y)) = aimed
+            && state.enemy_grid[y][x] != CellState::Empty
+        {
+            let hit = state.enemy_grid[y][x] == CellState::Hit;
+            let sunk = state.enemy_ships_sunk.get(sinks).map(|ship| ship.length);
+            sinks = state.enemy_ships_sunk.len();
+            view.record_shot(x, y, hit);
+            left.record_shot(x, y, hit, sunk)?;
+            aimed = None;
+        }
+
+        match state.phase {
+            // A fresh game, first or after a play-again
+            GamePhase::Placing => {
+                let fleet = left.place_ships(&mut rng)?;
+                view = AiView::new();
+                aimed = None;
+                sinks = 0;
+                state.own_grid = fleet.clone();
+                state.placing_ship_idx = SHIPS.len();
+                state.phase = GamePhase::WaitingForOpponent;
+                state.messages = vec!["Demo mode - AI vs AI. Press Q to quit.".to_string()];
+                let _ = tx.send(Message::PlaceShips(fleet));
+            }
+            GamePhase::YourTurn if aimed.is_none() && Instant::now() >= next_step => {
+                let (x, y) = left.choose_shot(&view, &mut rng)?;
+                state.cursor = (x, y);
+                aimed = Some((x, y));
+                next_step = Instant::now() + DEMO_STEP;
+                let _ = tx.send(Message::Attack { x, y });
+            }
+            GamePhase::GameOver | GamePhase::PlayAgainPrompt => match restart_at {
+                None => {
+                    restart_at = Some(Instant::now() + DEMO_RESTART_DELAY);
+                    state.show_result_screen = false;
+                    let side = if state.winner == Some(true) {
+                        "Left"
+                    } else {
+                        "Right"
+                    };
+                    state.messages.push(format!("🎉 {} fleet wins! 🎉", side));
+                }
+                Some(at) if Instant::now() >= at => {
+                    restart_at = None;
+                    let _ = tx.send(Message::PlayAgainResponse {
+                        wants_to_play: true,
+                    });
+                }
+                Some(_) => {}
+            },
+            _ => {}
+        }
+    }
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    drop(tx);
+    let _ = tokio::time::timeout(QUIT_GRACE, opponent).await;
     Ok(())
 }
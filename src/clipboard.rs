@@ -0,0 +1,20 @@
+use anyhow::{Result, anyhow};
+use arboard::Clipboard;
+use std::sync::{Mutex, OnceLock};
+
+/// Kept open for the life of the process: on X11 the copied text disappears
+/// as soon as the clipboard handle that owns it is dropped.
+static CLIPBOARD: OnceLock<Mutex<Option<Clipboard>>> = OnceLock::new();
+
+/// Places `text` on the system clipboard.
+pub fn copy(text: String) -> Result<()> {
+    let mut clipboard = CLIPBOARD.get_or_init(|| Mutex::new(None)).lock().unwrap();
+    if clipboard.is_none() {
+        *clipboard = Some(Clipboard::new().map_err(|e| anyhow!("{}", e))?);
+    }
+    clipboard
+        .as_mut()
+        .unwrap()
+        .set_text(text)
+        .map_err(|e| anyhow!("{}", e))
+}
@@ -0,0 +1,258 @@
+//! How messages are encoded on the wire. Every connection starts out in
+//! JSON, one message per line. A client may ask for MessagePack in its
+//! `Hello`; once the server agrees, each side sends length-prefixed
+//! MessagePack frames instead, which are a fraction of the size.
+//!
+//! Receivers tell the two apart frame by frame (see [`for_frame`]), so
+//! either side can switch as soon as it knows the other can read it, and
+//! JSON-only tools keep working against any server.
+//!
+//! Separately, a client may ask for `deflate` compression. Once agreed, each
+//! side may send a large frame deflated (see [`deflate`]), whatever its
+//! codec; small ones still go as they are.
+
+use anyhow::{Result, bail};
+use flate2::{Compression, read::DeflateDecoder, write::DeflateEncoder};
+use std::io::{Read, Write};
+
+use crate::net::MAX_FRAME_LEN;
+use crate::types::Message;
+
+/// The compression a client may ask for in `Hello`.
+pub const DEFLATE: &str = "deflate";
+
+/// Frames up to this many bytes are not worth deflating; a shot is a few
+/// dozen, a board or a game review a few kilobytes.
+const DEFLATE_ABOVE: usize = 256;
+
+/// The first payload byte of a deflated frame. MessagePack never uses it, so
+/// it cannot start a MessagePack payload.
+const DEFLATED: u8 = 0xc1;
+
+/// How the frames of a codec are told apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Each frame ends with a newline.
+    Line,
+    /// Each frame starts with its length, as `LENGTH_PREFIX` big-endian bytes.
+    LengthPrefixed,
+}
+
+/// Bytes in the length prefix of a `LengthPrefixed` frame.
+pub const LENGTH_PREFIX: usize = 4;
+
+/// One wire encoding for `Message`s.
+pub trait Codec: Sync {
+    /// The name a client asks for in `Hello`.
+    fn name(&self) -> &'static str;
+
+    fn framing(&self) -> Framing;
+
+    /// Encodes `msg` as one complete frame, ready to be written.
+    fn encode(&self, msg: &Message) -> Result<Vec<u8>>;
+
+    /// Decodes the payload of one frame, without its framing.
+    fn decode(&self, payload: &[u8]) -> Result<Message>;
+}
+
+/// JSON, one message per line. What every connection starts with.
+pub struct Json;
+
+impl Codec for Json {
+    fn name(&self) -> &'static str {
+        "json"
+    }
+
+    fn framing(&self) -> Framing {
+        Framing::Line
+    }
+
+    fn encode(&self, msg: &Message) -> Result<Vec<u8>> {
+        let mut frame = serde_json::to_vec(msg)?;
+        frame.push(b'\n');
+        Ok(frame)
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Message> {
+        Ok(serde_json::from_slice(payload)?)
+    }
+}
+
+/// MessagePack, each message prefixed with its length. Fields are encoded
+/// by name, so peers one field apart still agree.
+pub struct MessagePack;
+
+impl Codec for MessagePack {
+    fn name(&self) -> &'static str {
+        "msgpack"
+    }
+
+    fn framing(&self) -> Framing {
+        Framing::LengthPrefixed
+    }
+
+    fn encode(&self, msg: &Message) -> Result<Vec<u8>> {
+        let payload = rmp_serde::to_vec_named(msg)?;
+        let mut frame = Vec::with_capacity(LENGTH_PREFIX + payload.len());
+        frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        frame.extend_from_slice(&payload);
+        Ok(frame)
+    }
+
+    fn decode(&self, payload: &[u8]) -> Result<Message> {
+        match payload.split_first() {
+            Some((&DEFLATED, deflated)) => inflate(deflated),
+            _ => Ok(rmp_serde::from_slice(payload)?),
+        }
+    }
+}
+
+/// Deflates `frame`, a complete frame in any codec, into a length-prefixed
+/// frame whose payload is `DEFLATED` and the deflated bytes. Small frames,
+/// and any that would not shrink, are returned as they are.
+pub fn deflate(frame: Vec<u8>) -> Vec<u8> {
+    if frame.len() <= DEFLATE_ABOVE {
+        return frame;
+    }
+    let mut encoder = DeflateEncoder::new(vec![DEFLATED], Compression::default());
+    let Ok(payload) = encoder.write_all(&frame).and_then(|()| encoder.finish()) else {
+        return frame;
+    };
+    if LENGTH_PREFIX + payload.len() >= frame.len() {
+        return frame;
+    }
+    let mut deflated = Vec::with_capacity(LENGTH_PREFIX + payload.len());
+    deflated.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    deflated.extend_from_slice(&payload);
+    deflated
+}
+
+/// Decodes the frame inside a deflated payload. It may inflate to at most
+/// `MAX_FRAME_LEN` bytes, like any other frame, and may not be deflated again.
+fn inflate(deflated: &[u8]) -> Result<Message> {
+    let mut frame = Vec::new();
+    DeflateDecoder::new(deflated)
+        .take(MAX_FRAME_LEN as u64 + 1)
+        .read_to_end(&mut frame)?;
+    if frame.len() > MAX_FRAME_LEN {
+        bail!("deflated frame longer than {} bytes", MAX_FRAME_LEN);
+    }
+    let Some(&first) = frame.first() else {
+        bail!("empty deflated frame");
+    };
+    let codec = for_frame(first);
+    let payload = match codec.framing() {
+        Framing::Line => &frame[..],
+        Framing::LengthPrefixed => &frame[LENGTH_PREFIX.min(frame.len())..],
+    };
+    if payload.first() == Some(&DEFLATED) {
+        bail!("frame deflated twice");
+    }
+    codec.decode(payload)
+}
+
+/// The codec called `name` in a `Hello`, if this build has it.
+pub fn named(name: &str) -> Option<&'static dyn Codec> {
+    match name {
+        "json" => Some(&Json),
+        "msgpack" => Some(&MessagePack),
+        _ => None,
+    }
+}
+
+/// The codec of a frame that starts with `first`. Frames are far shorter
+/// than 16 MiB, so a length prefix always starts with a zero byte, which a
+/// line of JSON never does.
+pub fn for_frame(first: u8) -> &'static dyn Codec {
+    if first == 0 { &MessagePack } else { &Json }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ErrorCode;
+
+    /// A message big enough to be worth deflating.
+    fn long_error() -> Message {
+        Message::Error {
+            code: ErrorCode::Malformed,
+            detail: "the quick brown fox ".repeat(100),
+        }
+    }
+
+    fn detail(msg: Message) -> String {
+        match msg {
+            Message::Error { detail, .. } => detail,
+            other => panic!("expected an error, got {:?}", other),
+        }
+    }
+
+    /// Decodes one complete frame the way a receiver does.
+    fn receive(frame: &[u8]) -> Result<Message> {
+        let codec = for_frame(frame[0]);
+        match codec.framing() {
+            Framing::Line => codec.decode(frame.strip_suffix(b"\n").unwrap()),
+            Framing::LengthPrefixed => codec.decode(&frame[LENGTH_PREFIX..]),
+        }
+    }
+
+    /// `payload` deflated, behind the `DEFLATED` marker.
+    fn deflated_payload(payload: &[u8]) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(vec![DEFLATED], Compression::default());
+        encoder.write_all(payload).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn frames_are_told_apart_by_their_first_byte() {
+        assert_eq!(for_frame(0).name(), "msgpack");
+        assert_eq!(for_frame(b'{').name(), "json");
+        assert_eq!(for_frame(b'"').name(), "json");
+    }
+
+    #[test]
+    fn deflated_frames_round_trip_in_either_codec() {
+        for codec in [&Json as &dyn Codec, &MessagePack] {
+            let frame = codec.encode(&long_error()).unwrap();
+            let deflated = deflate(frame.clone());
+            assert!(
+                deflated.len() < frame.len(),
+                "{} did not shrink",
+                codec.name()
+            );
+            assert_eq!(deflated[LENGTH_PREFIX], DEFLATED);
+            assert_eq!(detail(receive(&deflated).unwrap()), detail(long_error()));
+        }
+    }
+
+    #[test]
+    fn small_frames_are_not_deflated() {
+        let frame = Json.encode(&Message::Quit).unwrap();
+        assert_eq!(deflate(frame.clone()), frame);
+    }
+
+    #[test]
+    fn messagepack_never_starts_a_payload_with_the_deflated_marker() {
+        for msg in [Message::Quit, Message::NewGameStart, long_error()] {
+            let frame = MessagePack.encode(&msg).unwrap();
+            assert_ne!(frame[LENGTH_PREFIX], DEFLATED);
+        }
+    }
+
+    #[test]
+    fn a_frame_deflated_twice_is_refused() {
+        let once = deflate(MessagePack.encode(&long_error()).unwrap());
+        let twice = deflated_payload(&once);
+        let err = MessagePack.decode(&twice).unwrap_err();
+        assert!(err.to_string().contains("deflated twice"), "{}", err);
+    }
+
+    #[test]
+    fn inflating_stops_at_the_frame_limit() {
+        // A few kilobytes that would inflate to a megabyte of zeros
+        let bomb = deflated_payload(&vec![0; 1024 * 1024]);
+        assert!(bomb.len() < MAX_FRAME_LEN / 10);
+        let err = MessagePack.decode(&bomb).unwrap_err();
+        assert!(err.to_string().contains("longer than"), "{}", err);
+    }
+}
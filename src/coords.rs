@@ -0,0 +1,145 @@
+use anyhow::{Result, bail};
+use std::{str::FromStr, sync::OnceLock};
+
+use crate::types::GRID_SIZE;
+
+/// How grid positions are written, e.g. "B7".
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CoordScheme {
+    /// Rows are letters, columns are numbers: "B7" is row B, column 7.
+    #[default]
+    RowLetters,
+    /// Columns are letters, rows are numbers: "B7" is column B, row 7.
+    ColumnLetters,
+}
+
+impl FromStr for CoordScheme {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "row-letters" => Ok(CoordScheme::RowLetters),
+            "column-letters" => Ok(CoordScheme::ColumnLetters),
+            _ => bail!(
+                "unknown coordinate scheme '{}', expected 'row-letters' or 'column-letters'",
+                s
+            ),
+        }
+    }
+}
+
+static SCHEME: OnceLock<CoordScheme> = OnceLock::new();
+
+/// Sets the scheme for the whole process. Only the first call has any effect.
+pub fn set_scheme(scheme: CoordScheme) {
+    let _ = SCHEME.set(scheme);
+}
+
+pub fn scheme() -> CoordScheme {
+    SCHEME.get().copied().unwrap_or_default()
+}
+
+fn letter(i: usize) -> char {
+    (b'A' + i as u8) as char
+}
+
+/// Formats grid position (x = column, y = row) using the active scheme.
+pub fn format(x: usize, y: usize) -> String {
+    format_in(scheme(), x, y)
+}
+
+fn format_in(scheme: CoordScheme, x: usize, y: usize) -> String {
+    match scheme {
+        CoordScheme::RowLetters => format!("{}{}", letter(y), x + 1),
+        CoordScheme::ColumnLetters => format!("{}{}", letter(x), y + 1),
+    }
+}
+
+/// Reads a grid position written as `format` writes it, e.g. "B7" or "b7",
+/// using the active scheme. Returns (x = column, y = row), or `None` if
+/// `text` names no cell on the board.
+pub fn parse(text: &str) -> Option<(usize, usize)> {
+    parse_in(scheme(), text)
+}
+
+fn parse_in(scheme: CoordScheme, text: &str) -> Option<(usize, usize)> {
+    let text = text.trim();
+    let mut chars = text.chars();
+    let letter = chars.next()?.to_ascii_uppercase();
+    let digits = chars.as_str();
+    if !letter.is_ascii_uppercase()
+        || digits.is_empty()
+        || !digits.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let lettered = usize::from(letter as u8 - b'A');
+    let numbered = digits.parse::<usize>().ok()?.checked_sub(1)?;
+    if lettered >= GRID_SIZE || numbered >= GRID_SIZE {
+        return None;
+    }
+    Some(match scheme {
+        CoordScheme::RowLetters => (numbered, lettered),
+        CoordScheme::ColumnLetters => (lettered, numbered),
+    })
+}
+
+/// Axis label for column `x`.
+pub fn column_label(x: usize) -> String {
+    match scheme() {
+        CoordScheme::RowLetters => (x + 1).to_string(),
+        CoordScheme::ColumnLetters => letter(x).to_string(),
+    }
+}
+
+/// Axis label for row `y`.
+pub fn row_label(y: usize) -> String {
+    match scheme() {
+        CoordScheme::RowLetters => letter(y).to_string(),
+        CoordScheme::ColumnLetters => (y + 1).to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMES: [CoordScheme; 2] = [CoordScheme::RowLetters, CoordScheme::ColumnLetters];
+
+    #[test]
+    fn format_puts_the_letter_on_the_scheme_axis() {
+        assert_eq!(format_in(CoordScheme::RowLetters, 6, 1), "B7");
+        assert_eq!(format_in(CoordScheme::ColumnLetters, 1, 6), "B7");
+        assert_eq!(format_in(CoordScheme::RowLetters, 0, 0), "A1");
+        assert_eq!(format_in(CoordScheme::RowLetters, 9, 9), "J10");
+    }
+
+    #[test]
+    fn parse_reads_back_every_cell() {
+        for scheme in SCHEMES {
+            for y in 0..GRID_SIZE {
+                for x in 0..GRID_SIZE {
+                    let text = format_in(scheme, x, y);
+                    assert_eq!(parse_in(scheme, &text), Some((x, y)), "{}", text);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_takes_lowercase_and_spaces_around() {
+        assert_eq!(parse_in(CoordScheme::RowLetters, " b7 "), Some((6, 1)));
+        assert_eq!(parse_in(CoordScheme::ColumnLetters, "j10"), Some((9, 9)));
+    }
+
+    #[test]
+    fn parse_rejects_cells_off_the_board_and_other_text() {
+        for text in [
+            "", "B", "7", "7B", "B0", "B11", "K1", "B+7", "B 7", "B7x", "é7",
+        ] {
+            for scheme in SCHEMES {
+                assert_eq!(parse_in(scheme, text), None, "{:?}", text);
+            }
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use anyhow::Result;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph},
+};
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io,
+    net::SocketAddr,
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crate::engine::Board;
+use crate::types::{CellState, GRID_SIZE};
+
+const MAX_EVENTS: usize = 200;
+/// Columns one game's panel takes: two boards of two characters a cell, side
+/// by side inside a border.
+const GAME_PANEL_WIDTH: u16 = 2 * (2 * GRID_SIZE as u16 + 2) + 2;
+/// Rows one game's panel takes: a label over each board, inside a border.
+const GAME_PANEL_HEIGHT: u16 = GRID_SIZE as u16 + 3;
+/// Rows left for the event log however many games are running.
+const MIN_EVENT_ROWS: u16 = 6;
+
+/// Live view of the server shown by `server --dashboard`.
+struct Dashboard {
+    addr: SocketAddr,
+    started: Instant,
+    /// Running games by ID.
+    games: BTreeMap<u64, GamePanel>,
+    /// Players waiting for an opponent.
+    queue: usize,
+    observers: usize,
+    refused: usize,
+    kicked: usize,
+    events: VecDeque<String>,
+}
+
+/// What the dashboard shows of one running game.
+#[derive(Default)]
+struct GamePanel {
+    players: [String; 2],
+    /// Invalid messages from each seated player's current connection.
+    violations: [u32; 2],
+    boards: [Option<Board>; 2],
+}
+
+static DASHBOARD: OnceLock<Mutex<Dashboard>> = OnceLock::new();
+
+tokio::task_local! {
+    /// The game the current task is running, if any.
+    static GAME: u64;
+}
+
+/// Runs `fut` as game `id`: everything it logs is tagged with the ID, and
+/// its board and violation updates go to that game's panel.
+pub async fn in_game<F: Future>(id: u64, fut: F) -> F::Output {
+    GAME.scope(id, fut).await
+}
+
+fn current_game() -> Option<u64> {
+    GAME.try_with(|id| *id).ok()
+}
+
+/// Logs a server event: to the dashboard when it is running, stdout otherwise.
+pub fn log(msg: impl Into<String>) {
+    let mut msg = msg.into();
+    if let Some(id) = current_game() {
+        msg = msg
+            .split('\n')
+            .map(|line| {
+                if line.trim().is_empty() {
+                    line.to_string()
+                } else {
+                    format!("[game {}] {}", id, line)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+    match DASHBOARD.get() {
+        Some(dashboard) => {
+            let mut dashboard = dashboard.lock().unwrap();
+            for line in msg.lines().filter(|l| !l.trim().is_empty()) {
+                dashboard.events.push_back(line.to_string());
+            }
+            while dashboard.events.len() > MAX_EVENTS {
+                dashboard.events.pop_front();
+            }
+        }
+        None => println!("{}", msg),
+    }
+}
+
+fn update(f: impl FnOnce(&mut Dashboard)) {
+    if let Some(dashboard) = DASHBOARD.get() {
+        f(&mut dashboard.lock().unwrap());
+    }
+}
+
+/// Applies `f` to the panel of the game the current task is running.
+fn update_game(f: impl FnOnce(&mut GamePanel)) {
+    if let Some(id) = current_game() {
+        update(|d| {
+            if let Some(game) = d.games.get_mut(&id) {
+                f(game);
+            }
+        });
+    }
+}
+
+pub fn set_queue(waiting: usize) {
+    update(|d| d.queue = waiting);
+}
+
+pub fn observer_connected() {
+    update(|d| d.observers += 1);
+}
+
+pub fn observer_disconnected() {
+    update(|d| d.observers = d.observers.saturating_sub(1));
+}
+
+pub fn connection_refused() {
+    update(|d| d.refused += 1);
+}
+
+pub fn player_kicked() {
+    update(|d| d.kicked += 1);
+}
+
+/// Adds a panel for the current game, seating `players`.
+pub fn game_started(players: [String; 2]) {
+    if let Some(id) = current_game() {
+        update(|d| {
+            d.games.insert(
+                id,
+                GamePanel {
+                    players,
+                    ..GamePanel::default()
+                },
+            );
+        });
+    }
+}
+
+pub fn game_ended() {
+    if let Some(id) = current_game() {
+        update(|d| {
+            d.games.remove(&id);
+        });
+    }
+}
+
+/// A new connection took `player`'s seat (1 or 2) in the current game.
+pub fn player_reconnected(player: usize, addr: String) {
+    update_game(|game| {
+        game.players[player - 1] = addr;
+        game.violations[player - 1] = 0;
+    });
+}
+
+pub fn set_violations(player: usize, count: u32) {
+    update_game(|game| game.violations[player - 1] = count);
+}
+
+/// Records the latest authoritative grid for a player (1 or 2).
+pub fn update_board(player: usize, grid: Option<&Board>) {
+    update_game(|game| game.boards[player - 1] = grid.cloned());
+}
+
+/// Takes over the terminal and renders the dashboard until `stop` is set.
+/// Pressing Q in the dashboard sets `shutdown`.
+pub fn start(
+    addr: SocketAddr,
+    shutdown: Arc<Mutex<bool>>,
+    stop: Arc<Mutex<bool>>,
+) -> tokio::task::JoinHandle<Result<()>> {
+    let _ = DASHBOARD.set(Mutex::new(Dashboard {
+        addr,
+        started: Instant::now(),
+        games: BTreeMap::new(),
+        queue: 0,
+        observers: 0,
+        refused: 0,
+        kicked: 0,
+        events: VecDeque::new(),
+    }));
+
+    tokio::task::spawn_blocking(move || {
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen)?;
+        let backend = CrosstermBackend::new(stdout);
+        let mut terminal = Terminal::new(backend)?;
+
+        while !*stop.lock().unwrap() {
+            terminal.draw(|f| {
+                let dashboard = DASHBOARD.get().unwrap().lock().unwrap();
+                draw_dashboard(f, &dashboard);
+            })?;
+
+            if event::poll(Duration::from_millis(200))?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && matches!(key.code, KeyCode::Char('q') | KeyCode::Char('Q'))
+            {
+                *shutdown.lock().unwrap() = true;
+                log("Shutdown requested from dashboard");
+            }
+        }
+
+        disable_raw_mode()?;
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        Ok(())
+    })
+}
+
+fn draw_dashboard(f: &mut Frame, dashboard: &Dashboard) {
+    // As many rows of game panels as the games need and the screen allows
+    let area = f.area();
+    let per_row = ((area.width * 3 / 5).saturating_sub(2) / GAME_PANEL_WIDTH).max(1);
+    let rows_needed = (dashboard.games.len() as u16).div_ceil(per_row).max(1);
+    let rows_fit = (area.height.saturating_sub(3 + MIN_EVENT_ROWS + 2) / GAME_PANEL_HEIGHT).max(1);
+    let games_height = rows_needed.min(rows_fit) * GAME_PANEL_HEIGHT + 2;
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(games_height.max(14)),
+            Constraint::Min(0),
+        ])
+        .split(area);
+
+    let uptime = dashboard.started.elapsed().as_secs();
+    let header = Paragraph::new(format!(
+        "🚢 Battleship Server {} | Uptime {:02}:{:02}:{:02} | Press Q to shut down",
+        dashboard.addr,
+        uptime / 3600,
+        (uptime / 60) % 60,
+        uptime % 60
+    ))
+    .style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    )
+    .block(Block::default().borders(Borders::ALL));
+    f.render_widget(header, chunks[0]);
+
+    let middle = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .split(chunks[1]);
+
+    // Every game gets its boards; if they do not all fit, the newest do
+    let games_block = Block::default().borders(Borders::ALL);
+    let games_inner = games_block.inner(middle[0]);
+    let per_row = (games_inner.width / GAME_PANEL_WIDTH).max(1);
+    let rows = (games_inner.height / GAME_PANEL_HEIGHT).max(1);
+    let total = dashboard.games.len();
+    let shown = total.min(usize::from(per_row * rows));
+    let games_title = if shown < total {
+        format!("Active Games ({}) - showing the newest {}", total, shown)
+    } else {
+        format!("Active Games ({})", total)
+    };
+    f.render_widget(games_block.title(games_title), middle[0]);
+    for (slot, (id, game)) in dashboard.games.iter().skip(total - shown).enumerate() {
+        let slot = slot as u16;
+        let panel = Rect {
+            x: games_inner.x + slot % per_row * GAME_PANEL_WIDTH,
+            y: games_inner.y + slot / per_row * GAME_PANEL_HEIGHT,
+            width: GAME_PANEL_WIDTH.min(games_inner.width),
+            height: GAME_PANEL_HEIGHT.min(games_inner.height),
+        };
+        draw_game(f, panel, *id, game);
+    }
+
+    let mut player_lines: Vec<String> = dashboard
+        .games
+        .iter()
+        .map(|(id, game)| {
+            format!(
+                "Game {}: {} vs {} (invalid {}/{})",
+                id, game.players[0], game.players[1], game.violations[0], game.violations[1]
+            )
+        })
+        .collect();
+    player_lines.push(String::new());
+    player_lines.push(format!("Waiting in queue: {}", dashboard.queue));
+    player_lines.push(format!("Observers: {}", dashboard.observers));
+    player_lines.push(format!("Refused: {}", dashboard.refused));
+    player_lines.push(format!("Kicked: {}", dashboard.kicked));
+    let players = Paragraph::new(player_lines.join("\n"))
+        .block(Block::default().borders(Borders::ALL).title("Connections"));
+    f.render_widget(players, middle[1]);
+
+    let visible = chunks[2].height.saturating_sub(2) as usize;
+    let events: Vec<ListItem> = dashboard
+        .events
+        .iter()
+        .rev()
+        .take(visible)
+        .map(|e| ListItem::new(e.clone()))
+        .collect();
+    let events = List::new(events).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Recent Events"),
+    );
+    f.render_widget(events, chunks[2]);
+}
+
+/// Draws game `id`'s two boards side by side in `area`.
+fn draw_game(f: &mut Frame, area: Rect, id: u64, game: &GamePanel) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Game {}", id));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let board_chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(inner);
+    for (i, board) in game.boards.iter().enumerate() {
+        let mut lines = vec![Line::from(Span::styled(
+            format!("Player {}", i + 1),
+            Style::default().add_modifier(Modifier::BOLD),
+        ))];
+        match board {
+            Some(grid) => lines.extend(grid.iter().map(|row| mini_row(row))),
+            None => lines.push(Line::from("(placing ships)")),
+        }
+        f.render_widget(Paragraph::new(lines), board_chunks[i]);
+    }
+}
+
+fn mini_row(row: &[CellState]) -> Line<'static> {
+    Line::from(
+        row.iter()
+            .map(|cell| match cell {
+                CellState::Empty => Span::styled("· ", Style::default().fg(Color::Blue)),
+                CellState::Ship => Span::styled("■ ", Style::default().fg(Color::Green)),
+                CellState::Hit => Span::styled("X ", Style::default().fg(Color::Red)),
+                CellState::Miss => Span::styled("o ", Style::default().fg(Color::DarkGray)),
+                CellState::Mine => Span::styled("* ", Style::default().fg(Color::Magenta)),
+            })
+            .collect::<Vec<_>>(),
+    )
+}
@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use crate::dashboard;
+
+/// How often a server re-announces itself; directories can expire entries
+/// that miss a few rounds.
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a request to the directory may take, connecting included, as
+/// for the webhook; a directory that hangs must not stall the announcements
+/// or `--browse`.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+static PLAYERS_ONLINE: AtomicUsize = AtomicUsize::new(0);
+
+/// One server as listed by a community directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Listing {
+    pub name: String,
+    pub region: String,
+    pub port: u16,
+    pub players: usize,
+    pub max_connections: Option<usize>,
+    /// Optional rules in effect, e.g. "stormy-seas".
+    pub rules: Vec<String>,
+    /// `host:port` to connect to. Filled in by the directory from where the
+    /// announcement came from; servers leave it out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+/// Counts a player who connected, for the next announcement.
+pub fn player_joined() {
+    PLAYERS_ONLINE.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Counts a player who left. Never goes below zero, should a leaver be
+/// counted out twice.
+pub fn player_left() {
+    let _ = PLAYERS_ONLINE.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |online| {
+        online.checked_sub(1)
+    });
+}
+
+/// Players connected right now, as announced.
+pub fn players_online() -> usize {
+    PLAYERS_ONLINE.load(Ordering::Relaxed)
+}
+
+/// POSTs `listing` to the directory at `url` now and every 30 seconds after,
+/// on a background thread, with the current player count.
+pub fn announce(url: String, mut listing: Listing) {
+    std::thread::spawn(move || {
+        let agent = agent();
+        let mut failing = false;
+        loop {
+            listing.players = players_online();
+            match agent.post(&url).send_json(&listing) {
+                Ok(_) if failing => {
+                    dashboard::log(format!("Announcing to {} again", url));
+                    failing = false;
+                }
+                Ok(_) => {}
+                // Only log the first failure of a streak; the directory may be down for a while
+                Err(e) if !failing => {
+                    dashboard::log(format!("Could not announce to {}: {}", url, e));
+                    failing = true;
+                }
+                Err(_) => {}
+            }
+            std::thread::sleep(ANNOUNCE_INTERVAL);
+        }
+    });
+}
+
+/// Fetches the servers currently listed by the directory at `url`.
+pub fn fetch(url: &str) -> Result<Vec<Listing>> {
+    let listings: Vec<Listing> = agent()
+        .get(url)
+        .call()
+        .with_context(|| format!("could not reach server directory {}", url))?
+        .body_mut()
+        .read_json()
+        .with_context(|| format!("{} did not return a server list", url))?;
+    Ok(listings
+        .into_iter()
+        .filter(|listing| listing.address.is_some())
+        .collect())
+}
+
+fn agent() -> ureq::Agent {
+    ureq::Agent::config_builder()
+        .timeout_global(Some(TIMEOUT))
+        .build()
+        .into()
+}
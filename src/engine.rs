@@ -0,0 +1,742 @@
+use crate::coords;
+use crate::types::{CellState, GRID_SIZE, SHIPS};
+use rand::Rng;
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
+use std::ops::{Deref, DerefMut};
+
+/// What a shot did to the board it landed on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shot {
+    pub hit: bool,
+    /// The hit finished off the ship it landed on.
+    pub sunk: bool,
+    /// The shot set off a mine.
+    pub mine: bool,
+    /// The `SHIPS` entry of the ship it hit.
+    pub ship: Option<usize>,
+}
+
+/// One ship of a fleet, tracked by the cells it lies on rather than read back
+/// off the grid, so ships that touch stay apart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ship {
+    /// Its entry in `SHIPS`.
+    pub kind: usize,
+    pub cells: Vec<(usize, usize)>,
+    pub hits: usize,
+}
+
+impl Ship {
+    pub fn name(&self) -> &'static str {
+        SHIPS[self.kind].1
+    }
+
+    pub fn length(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_sunk(&self) -> bool {
+        self.hits == self.cells.len()
+    }
+
+    /// The cells touching the ship, corners included.
+    pub fn border(&self) -> Vec<(usize, usize)> {
+        border(&self.cells)
+    }
+}
+
+/// The cells on the board touching `cells`, corners included, but not among them.
+fn border(cells: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut around = Vec::new();
+    for &(x, y) in cells {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let nx = x.checked_add_signed(dx).filter(|&nx| nx < GRID_SIZE);
+                let ny = y.checked_add_signed(dy).filter(|&ny| ny < GRID_SIZE);
+                if let (Some(nx), Some(ny)) = (nx, ny)
+                    && !cells.contains(&(nx, ny))
+                    && !around.contains(&(nx, ny))
+                {
+                    around.push((nx, ny));
+                }
+            }
+        }
+    }
+    around
+}
+
+/// One player's board with their fleet on it. Servers, the AI server and the
+/// client all place ships, resolve shots and detect sinks through this type,
+/// so the rules live in one place.
+///
+/// Derefs to its rows, so cells read as `board[y][x]`. Writing a cell directly
+/// does not move the fleet; ships change only through the methods below. On
+/// the wire it is the plain grid of cells, and a board read back from one
+/// finds its fleet the way `check_fleet` does.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "Vec<Vec<CellState>>", into = "Vec<Vec<CellState>>")]
+pub struct Board {
+    cells: Vec<Vec<CellState>>,
+    ships: Vec<Ship>,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        vec![vec![CellState::Empty; GRID_SIZE]; GRID_SIZE].into()
+    }
+}
+
+impl From<Vec<Vec<CellState>>> for Board {
+    fn from(cells: Vec<Vec<CellState>>) -> Self {
+        let ships = Self::find_ships(&cells);
+        Self { cells, ships }
+    }
+}
+
+impl From<Board> for Vec<Vec<CellState>> {
+    fn from(board: Board) -> Self {
+        board.cells
+    }
+}
+
+impl Deref for Board {
+    type Target = Vec<Vec<CellState>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.cells
+    }
+}
+
+impl DerefMut for Board {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.cells
+    }
+}
+
+impl Board {
+    /// Builds a board with the full fleet placed at random, non-overlapping positions.
+    pub fn random(rng: &mut impl Rng) -> Self {
+        let mut board = Self::default();
+        for (length, _name) in SHIPS {
+            loop {
+                let x = rng.random_range(0..GRID_SIZE);
+                let y = rng.random_range(0..GRID_SIZE);
+                let horizontal = rng.random_bool(0.5);
+                if board.can_place(x, y, length, horizontal) {
+                    board.place(x, y, length, horizontal);
+                    break;
+                }
+            }
+        }
+        board
+    }
+
+    /// The cells a ship of `length` would cover from (x, y), or `None` if it
+    /// would run off the board.
+    pub fn span(
+        x: usize,
+        y: usize,
+        length: usize,
+        horizontal: bool,
+    ) -> Option<impl Iterator<Item = (usize, usize)>> {
+        let end = if horizontal { x } else { y } + length;
+        (x < GRID_SIZE && y < GRID_SIZE && end <= GRID_SIZE)
+            .then(|| (0..length).map(move |i| if horizontal { (x + i, y) } else { (x, y + i) }))
+    }
+
+    pub fn can_place(&self, x: usize, y: usize, length: usize, horizontal: bool) -> bool {
+        Self::span(x, y, length, horizontal)
+            .is_some_and(|mut cells| cells.all(|(cx, cy)| self[cy][cx] == CellState::Empty))
+    }
+
+    /// Like `can_place`, but the ship may not touch another, even at a corner.
+    pub fn can_place_apart(&self, x: usize, y: usize, length: usize, horizontal: bool) -> bool {
+        self.can_place(x, y, length, horizontal)
+            && Self::span(x, y, length, horizontal).is_some_and(|cells| {
+                border(&cells.collect::<Vec<_>>())
+                    .iter()
+                    .all(|&(cx, cy)| self[cy][cx] != CellState::Ship)
+            })
+    }
+
+    /// Puts a ship on the board as the first ship of `length` in `SHIPS` not
+    /// placed yet. Check `can_place` first; cells off the board are skipped.
+    pub fn place(&mut self, x: usize, y: usize, length: usize, horizontal: bool) {
+        let cells: Vec<_> = Self::span(x, y, length, horizontal)
+            .into_iter()
+            .flatten()
+            .collect();
+        for &(cx, cy) in &cells {
+            self[cy][cx] = CellState::Ship;
+        }
+        let kind = (0..SHIPS.len())
+            .find(|&kind| SHIPS[kind].0 == length && self.ships.iter().all(|s| s.kind != kind))
+            .unwrap_or(self.ships.len());
+        self.ships.push(Ship {
+            kind,
+            cells,
+            hits: 0,
+        });
+        self.ships.sort_by_key(|ship| ship.kind);
+    }
+
+    /// The fleet on the board, in `SHIPS` order. Ships not placed yet are
+    /// missing.
+    pub fn ships(&self) -> &[Ship] {
+        &self.ships
+    }
+
+    /// The ship lying on (x, y), if any.
+    pub fn ship_at(&self, x: usize, y: usize) -> Option<&Ship> {
+        self.ships.iter().find(|ship| ship.cells.contains(&(x, y)))
+    }
+
+    /// Moves the ship on `from` to `to`, cell for cell. The caller checks
+    /// that `to` is open water.
+    pub fn move_ship(&mut self, from: &[(usize, usize)], to: &[(usize, usize)]) {
+        for &(x, y) in from {
+            self[y][x] = CellState::Empty;
+        }
+        for &(x, y) in to {
+            self[y][x] = CellState::Ship;
+        }
+        if let Some(ship) = self.ships.iter_mut().find(|ship| ship.cells == from) {
+            ship.cells = to.to_vec();
+        }
+    }
+
+    /// Moves every ship and mine one step. Returns false, leaving the board
+    /// untouched, if one would leave it.
+    pub fn shift(&mut self, dx: isize, dy: isize) -> bool {
+        let step = |(x, y): (usize, usize)| {
+            let nx = x.checked_add_signed(dx).filter(|&nx| nx < GRID_SIZE)?;
+            let ny = y.checked_add_signed(dy).filter(|&ny| ny < GRID_SIZE)?;
+            Some((nx, ny))
+        };
+        let mut shifted = vec![vec![CellState::Empty; GRID_SIZE]; GRID_SIZE];
+        for (y, row) in self.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if *cell == CellState::Empty {
+                    continue;
+                }
+                let Some((nx, ny)) = step((x, y)) else {
+                    return false;
+                };
+                shifted[ny][nx] = *cell;
+            }
+        }
+        self.cells = shifted;
+        for ship in &mut self.ships {
+            ship.cells = ship.cells.iter().filter_map(|&cell| step(cell)).collect();
+        }
+        true
+    }
+
+    /// Mirrors every ship and mine left-to-right (`horizontal`) or top-to-bottom.
+    pub fn mirror(&mut self, horizontal: bool) {
+        let flip = |(x, y): (usize, usize)| {
+            if horizontal {
+                (GRID_SIZE - 1 - x, y)
+            } else {
+                (x, GRID_SIZE - 1 - y)
+            }
+        };
+        if horizontal {
+            for row in self.cells.iter_mut() {
+                row.reverse();
+            }
+        } else {
+            self.cells.reverse();
+        }
+        for ship in &mut self.ships {
+            ship.cells = ship.cells.iter().map(|&cell| flip(cell)).collect();
+        }
+    }
+
+    /// Checks a freshly placed board from a client: the right size, no shots
+    /// on it, exactly `mines` mines, and ship cells that split exactly into
+    /// the fleet in `SHIPS`.
+    pub fn check_fleet(&self, mines: usize) -> Result<(), String> {
+        if self.len() != GRID_SIZE || self.iter().any(|row| row.len() != GRID_SIZE) {
+            return Err(format!("the board must be {}x{}", GRID_SIZE, GRID_SIZE));
+        }
+        if self
+            .iter()
+            .flatten()
+            .any(|cell| matches!(cell, CellState::Hit | CellState::Miss))
+        {
+            return Err("a new board cannot have shots on it".to_string());
+        }
+        let laid = self
+            .iter()
+            .flatten()
+            .filter(|cell| **cell == CellState::Mine)
+            .count();
+        if laid != mines {
+            return Err(match mines {
+                0 => "this server is not playing with mines".to_string(),
+                _ => format!("the board must have {} mines, not {}", mines, laid),
+            });
+        }
+        let mut cells: Vec<Vec<bool>> = self
+            .iter()
+            .map(|row| row.iter().map(|cell| *cell == CellState::Ship).collect())
+            .collect();
+        let mut lengths: Vec<usize> = SHIPS.iter().map(|(length, _)| *length).collect();
+        if Self::split_into_ships(&mut cells, &mut lengths, &mut Vec::new()) {
+            return Ok(());
+        }
+        let fleet: Vec<String> = SHIPS.iter().map(|(length, _)| length.to_string()).collect();
+        Err(format!(
+            "the ships must be straight lines of {} cells",
+            fleet.join(", ")
+        ))
+    }
+
+    /// Checks that no two ships of a fleet `check_fleet` accepted touch,
+    /// even at a corner.
+    pub fn check_apart(&self) -> Result<(), String> {
+        let touching = self.ships.iter().any(|ship| {
+            ship.border()
+                .iter()
+                .any(|&(x, y)| self.ship_at(x, y).is_some())
+        });
+        if touching {
+            return Err("ships may not touch, not even at a corner".to_string());
+        }
+        Ok(())
+    }
+
+    /// The water around the ship on (x, y) that has not been fired at, once
+    /// it is sunk: what the reveal-sunk rule shows the shooter.
+    pub fn water_around_sunk(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let Some(ship) = self.ship_at(x, y).filter(|ship| ship.is_sunk()) else {
+            return Vec::new();
+        };
+        ship.border()
+            .into_iter()
+            .filter(|&(cx, cy)| matches!(self[cy][cx], CellState::Empty | CellState::Mine))
+            .collect()
+    }
+
+    /// The fleet on a grid that came without one: its ship cells, hit or
+    /// not, split into `SHIPS` as `check_fleet` splits them. Where two ships
+    /// of the same length could swap, the first in reading order is the
+    /// first in `SHIPS`. Empty if the cells do not split into the fleet.
+    fn find_ships(grid: &[Vec<CellState>]) -> Vec<Ship> {
+        if grid.len() != GRID_SIZE || grid.iter().any(|row| row.len() != GRID_SIZE) {
+            return Vec::new();
+        }
+        let mut cells: Vec<Vec<bool>> = grid
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| matches!(cell, CellState::Ship | CellState::Hit))
+                    .collect()
+            })
+            .collect();
+        let mut lengths: Vec<usize> = SHIPS.iter().map(|(length, _)| *length).collect();
+        let mut spans = Vec::new();
+        if !Self::split_into_ships(&mut cells, &mut lengths, &mut spans) {
+            return Vec::new();
+        }
+        let mut ships: Vec<Ship> = Vec::new();
+        for cells in spans {
+            let kind = (0..SHIPS.len())
+                .find(|&kind| SHIPS[kind].0 == cells.len() && ships.iter().all(|s| s.kind != kind))
+                .unwrap_or_default();
+            let hits = cells
+                .iter()
+                .filter(|&&(x, y)| grid[y][x] == CellState::Hit)
+                .count();
+            ships.push(Ship { kind, cells, hits });
+        }
+        ships.sort_by_key(|ship| ship.kind);
+        ships
+    }
+
+    /// Whether the marked cells can be covered exactly by straight ships of
+    /// `lengths`, collecting the ships' cells in `spans` if so. Backtracks over
+    /// the first marked cell in reading order, which has to be the top or left
+    /// end of its ship.
+    fn split_into_ships(
+        cells: &mut [Vec<bool>],
+        lengths: &mut Vec<usize>,
+        spans: &mut Vec<Vec<(usize, usize)>>,
+    ) -> bool {
+        let first = (0..GRID_SIZE)
+            .flat_map(|y| (0..GRID_SIZE).map(move |x| (x, y)))
+            .find(|&(x, y)| cells[y][x]);
+        let Some((x, y)) = first else {
+            return lengths.is_empty();
+        };
+        for i in 0..lengths.len() {
+            let length = lengths[i];
+            if lengths[..i].contains(&length) {
+                continue;
+            }
+            for horizontal in [true, false] {
+                let Some(span) = Self::span(x, y, length, horizontal) else {
+                    continue;
+                };
+                let span: Vec<_> = span.collect();
+                if !span.iter().all(|&(cx, cy)| cells[cy][cx]) {
+                    continue;
+                }
+                span.iter().for_each(|&(cx, cy)| cells[cy][cx] = false);
+                lengths.remove(i);
+                spans.push(span);
+                if Self::split_into_ships(cells, lengths, spans) {
+                    return true;
+                }
+                let span = spans.pop().unwrap_or_default();
+                lengths.insert(i, length);
+                span.iter().for_each(|&(cx, cy)| cells[cy][cx] = true);
+            }
+        }
+        false
+    }
+
+    /// Checks that (x, y) is on the board and has not been fired at yet,
+    /// describing the problem otherwise. Servers call this before `fire` since
+    /// the coordinates come straight from the client.
+    pub fn check_target(&self, x: usize, y: usize) -> Result<(), String> {
+        match self.get(y).and_then(|row| row.get(x)) {
+            None => Err(format!(
+                "({}, {}) is off the {}x{} board",
+                x, y, GRID_SIZE, GRID_SIZE
+            )),
+            Some(CellState::Hit | CellState::Miss) => Err(format!(
+                "{} has already been fired at",
+                coords::format(x, y)
+            )),
+            Some(CellState::Empty | CellState::Ship | CellState::Mine) => Ok(()),
+        }
+    }
+
+    /// Resolves a shot at (x, y), marking the cell as hit or missed. A mine
+    /// goes off and leaves a miss. A cell that was already shot is left alone
+    /// and counts as a miss.
+    pub fn fire(&mut self, x: usize, y: usize) -> Shot {
+        let miss = Shot {
+            hit: false,
+            sunk: false,
+            mine: false,
+            ship: None,
+        };
+        match self[y][x] {
+            CellState::Ship => {
+                self[y][x] = CellState::Hit;
+                let ship = self
+                    .ships
+                    .iter_mut()
+                    .find(|ship| ship.cells.contains(&(x, y)));
+                let (sunk, ship) = match ship {
+                    Some(ship) => {
+                        ship.hits += 1;
+                        (ship.is_sunk(), Some(ship.kind))
+                    }
+                    None => (false, None),
+                };
+                Shot {
+                    hit: true,
+                    sunk,
+                    mine: false,
+                    ship,
+                }
+            }
+            CellState::Empty => {
+                self[y][x] = CellState::Miss;
+                miss
+            }
+            CellState::Mine => {
+                self[y][x] = CellState::Miss;
+                Shot { mine: true, ..miss }
+            }
+            CellState::Hit | CellState::Miss => miss,
+        }
+    }
+
+    /// A random ship cell that has not been hit, for a mine's blast to strike.
+    pub fn random_ship_cell(&self, rng: &mut impl Rng) -> Option<(usize, usize)> {
+        let intact: Vec<(usize, usize)> = (0..GRID_SIZE)
+            .flat_map(|y| (0..GRID_SIZE).map(move |x| (x, y)))
+            .filter(|&(x, y)| self[y][x] == CellState::Ship)
+            .collect();
+        intact.choose(rng).copied()
+    }
+
+    pub fn all_sunk(&self) -> bool {
+        !self.iter().flatten().any(|c| *c == CellState::Ship)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    /// The fleet along the left edge, one ship every other row, none touching.
+    fn apart() -> Board {
+        let mut board = Board::default();
+        for (i, (length, _)) in SHIPS.iter().enumerate() {
+            board.place(0, 2 * i, *length, true);
+        }
+        board
+    }
+
+    /// The fleet on the top five rows, each ship against the next.
+    fn stacked() -> Board {
+        let mut board = Board::default();
+        for (i, (length, _)) in SHIPS.iter().enumerate() {
+            board.place(0, i, *length, true);
+        }
+        board
+    }
+
+    /// The cells marked `Ship` on `board`, in reading order.
+    fn grid_cells(board: &Board) -> Vec<(usize, usize)> {
+        (0..GRID_SIZE)
+            .flat_map(|y| (0..GRID_SIZE).map(move |x| (x, y)))
+            .filter(|&(x, y)| board[y][x] == CellState::Ship)
+            .collect()
+    }
+
+    /// The cells of `board`'s fleet, in reading order.
+    fn fleet_cells(board: &Board) -> Vec<(usize, usize)> {
+        let mut cells: Vec<_> = board.ships().iter().flat_map(|s| s.cells.clone()).collect();
+        cells.sort_by_key(|&(x, y)| (y, x));
+        cells
+    }
+
+    #[test]
+    fn check_fleet_accepts_placed_and_random_fleets() {
+        assert_eq!(apart().check_fleet(0), Ok(()));
+        assert_eq!(stacked().check_fleet(0), Ok(()));
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..50 {
+            assert_eq!(Board::random(&mut rng).check_fleet(0), Ok(()));
+        }
+    }
+
+    #[test]
+    fn check_fleet_rejects_a_bad_board() {
+        let mut short: Vec<Vec<CellState>> = apart().into();
+        short.pop();
+        assert!(Board::from(short).check_fleet(0).is_err());
+
+        let mut shot = apart();
+        shot[9][9] = CellState::Miss;
+        assert!(shot.check_fleet(0).is_err());
+
+        let mut missing = apart();
+        missing[8][0] = CellState::Empty;
+        missing[8][1] = CellState::Empty;
+        assert!(missing.check_fleet(0).is_err());
+
+        let mut extra = apart();
+        extra[9][9] = CellState::Ship;
+        assert!(extra.check_fleet(0).is_err());
+
+        // The carrier bent round a corner
+        let mut bent = apart();
+        bent[0][4] = CellState::Empty;
+        bent[1][3] = CellState::Ship;
+        assert!(bent.check_fleet(0).is_err());
+
+        // The destroyer's cells meeting only at a corner
+        let mut diagonal = apart();
+        diagonal[8][1] = CellState::Empty;
+        diagonal[9][1] = CellState::Ship;
+        assert!(diagonal.check_fleet(0).is_err());
+    }
+
+    #[test]
+    fn check_fleet_counts_mines() {
+        let mut mined = apart();
+        mined[9][8] = CellState::Mine;
+        mined[9][9] = CellState::Mine;
+        assert_eq!(mined.check_fleet(2), Ok(()));
+        assert!(mined.check_fleet(0).is_err());
+        assert!(apart().check_fleet(2).is_err());
+    }
+
+    #[test]
+    fn touching_ships_are_legal_unless_kept_apart() {
+        assert_eq!(apart().check_apart(), Ok(()));
+        assert!(stacked().check_apart().is_err());
+        assert!(apart().can_place_apart(5, 9, 2, true));
+        assert!(!apart().can_place_apart(5, 1, 2, true));
+    }
+
+    #[test]
+    fn find_ships_splits_ships_that_touch() {
+        // Read back off the plain grid, as a board arriving on the wire is
+        let grid: Vec<Vec<CellState>> = stacked().into();
+        let board = Board::from(grid);
+        let lengths: Vec<usize> = board.ships().iter().map(Ship::length).collect();
+        let expected: Vec<usize> = SHIPS.iter().map(|(length, _)| *length).collect();
+        assert_eq!(lengths, expected);
+        assert_eq!(grid_cells(&board), fleet_cells(&board));
+
+        // Carrier and destroyer end to end make one line of seven cells
+        let mut grid = vec![vec![CellState::Empty; GRID_SIZE]; GRID_SIZE];
+        for (x, y, length) in [(0, 0, 5), (5, 0, 2), (0, 2, 4), (0, 4, 3), (3, 4, 3)] {
+            for i in 0..length {
+                grid[y][x + i] = CellState::Ship;
+            }
+        }
+        let board = Board::from(grid);
+        assert_eq!(board.check_fleet(0), Ok(()));
+        assert_eq!(board.ships().len(), SHIPS.len());
+        assert_eq!(board.ship_at(6, 0).map(Ship::name), Some(SHIPS[4].1));
+        assert_eq!(board.ship_at(4, 0).map(Ship::name), Some(SHIPS[0].1));
+    }
+
+    #[test]
+    fn find_ships_keeps_hits_and_gives_up_on_a_broken_fleet() {
+        let mut board = apart();
+        board.fire(0, 0);
+        board.fire(1, 0);
+        let read_back = Board::from(Vec::<Vec<CellState>>::from(board));
+        assert_eq!(read_back.ships()[0].hits, 2);
+
+        let mut grid: Vec<Vec<CellState>> = apart().into();
+        grid[9][9] = CellState::Ship;
+        assert!(Board::from(grid).ships().is_empty());
+    }
+
+    #[test]
+    fn split_into_ships_backtracks() {
+        // A 2x2 block is two destroyers side by side, whichever way round
+        let mut cells = vec![vec![false; GRID_SIZE]; GRID_SIZE];
+        for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            cells[y][x] = true;
+        }
+        let mut spans = Vec::new();
+        assert!(Board::split_into_ships(
+            &mut cells.clone(),
+            &mut vec![2, 2],
+            &mut spans
+        ));
+        assert_eq!(spans.len(), 2);
+        assert!(!Board::split_into_ships(
+            &mut cells.clone(),
+            &mut vec![4],
+            &mut Vec::new()
+        ));
+        assert!(!Board::split_into_ships(
+            &mut cells,
+            &mut vec![2, 2, 2],
+            &mut Vec::new()
+        ));
+    }
+
+    #[test]
+    fn fire_hits_sinks_and_misses() {
+        let mut board = apart();
+        let destroyer = SHIPS.len() - 1;
+
+        let miss = board.fire(9, 9);
+        assert!(!miss.hit && !miss.sunk && !miss.mine);
+        assert_eq!(board[9][9], CellState::Miss);
+
+        let hit = board.fire(0, 8);
+        assert!(hit.hit && !hit.sunk);
+        assert_eq!(hit.ship, Some(destroyer));
+
+        let sunk = board.fire(1, 8);
+        assert!(sunk.hit && sunk.sunk);
+        assert_eq!(sunk.ship, Some(destroyer));
+        assert!(board.ships()[destroyer].is_sunk());
+
+        // Firing again at a hit cell changes nothing
+        assert!(!board.fire(1, 8).hit);
+        assert_eq!(board.ships()[destroyer].hits, 2);
+        assert!(!board.all_sunk());
+    }
+
+    #[test]
+    fn fire_sets_off_mines_and_sinks_the_whole_fleet() {
+        let mut board = apart();
+        board[9][9] = CellState::Mine;
+        let blast = board.fire(9, 9);
+        assert!(blast.mine && !blast.hit);
+        assert_eq!(board[9][9], CellState::Miss);
+
+        let sinks = grid_cells(&board)
+            .into_iter()
+            .filter(|&(x, y)| board.fire(x, y).sunk)
+            .count();
+        assert_eq!(sinks, SHIPS.len());
+        assert!(board.all_sunk());
+    }
+
+    #[test]
+    fn check_target_refuses_cells_off_the_board_or_fired_at() {
+        let mut board = apart();
+        assert_eq!(board.check_target(0, 0), Ok(()));
+        assert_eq!(board.check_target(9, 9), Ok(()));
+        assert!(board.check_target(GRID_SIZE, 0).is_err());
+        assert!(board.check_target(0, GRID_SIZE).is_err());
+        board.fire(0, 0);
+        board.fire(9, 9);
+        assert!(board.check_target(0, 0).is_err());
+        assert!(board.check_target(9, 9).is_err());
+    }
+
+    #[test]
+    fn water_around_sunk_shows_only_unshot_water_of_a_sunk_ship() {
+        let mut board = apart();
+        board.fire(0, 8);
+        assert!(board.water_around_sunk(0, 8).is_empty());
+        board[9][2] = CellState::Mine;
+        board.fire(0, 9);
+        board.fire(1, 8);
+
+        let mut water = board.water_around_sunk(1, 8);
+        water.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(water, vec![(0, 7), (1, 7), (2, 7), (2, 8), (1, 9), (2, 9)]);
+        assert!(board.water_around_sunk(9, 9).is_empty());
+    }
+
+    #[test]
+    fn shift_moves_the_fleet_and_stays_on_the_board() {
+        let mut board = apart();
+        assert!(board.shift(1, 1));
+        assert_eq!(board[1][1], CellState::Ship);
+        assert_eq!(board[0][0], CellState::Empty);
+        assert_eq!(grid_cells(&board), fleet_cells(&board));
+        assert_eq!(board.check_fleet(0), Ok(()));
+
+        // The carrier would fall off the left edge, so nothing moves
+        let mut edge = apart();
+        assert!(!edge.shift(-1, 0));
+        assert_eq!(edge, apart());
+        let mut bottom = apart();
+        assert!(bottom.shift(0, 1));
+        assert!(!bottom.shift(0, 1));
+        assert_eq!(grid_cells(&bottom), fleet_cells(&bottom));
+    }
+
+    #[test]
+    fn mirror_keeps_the_fleet_whole_and_on_the_board() {
+        for horizontal in [true, false] {
+            let mut board = apart();
+            board.fire(0, 0);
+            board.mirror(horizontal);
+            let mut unhit = fleet_cells(&board);
+            unhit.retain(|&(x, y)| board[y][x] == CellState::Ship);
+            assert_eq!(grid_cells(&board), unhit);
+            let corner = if horizontal { (9, 0) } else { (0, 9) };
+            assert_eq!(board[corner.1][corner.0], CellState::Hit);
+            assert_eq!(board.ship_at(corner.0, corner.1).map(|s| s.hits), Some(1));
+
+            board.mirror(horizontal);
+            let mut original = apart();
+            original.fire(0, 0);
+            assert_eq!(board, original);
+        }
+    }
+}
@@ -1,5 +1,13 @@
-use crate::types::{CellState, GRID_SIZE, GamePhase, SHIPS};
-use std::time::Instant;
+use crate::analysis::Analysis;
+use crate::coords;
+use crate::engine::Board;
+use crate::notify::NotifyEvent;
+use crate::perf::{self, LoopStats};
+use crate::practice::Practice;
+use crate::settings::Settings;
+use crate::snapshot::SnapshotFormat;
+use crate::types::{CellState, GRID_SIZE, GamePhase, SHIPS, SunkShip};
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
 pub struct ShipStatus {
@@ -9,32 +17,187 @@ pub struct ShipStatus {
     pub sunk: bool,
 }
 
-pub struct GameState {
+/// Streaks and personal records for the current game, counted in turns.
+#[derive(Debug, Clone, Default)]
+pub struct Streaks {
+    pub hit_streak: usize,
+    pub best_hit_streak: usize,
+    pub miss_streak: usize,
+    pub longest_drought: usize,
+    /// Turn of the first hit since the last sink.
+    pub hunt_started: Option<usize>,
+    /// Fewest turns from the first hit on a ship to sinking it.
+    pub fastest_sink: Option<usize>,
+    pub first_hit_turn: Option<usize>,
+}
+
+/// How a player asks to be matched with an opponent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Lobby {
+    /// Play whoever connects next.
+    #[default]
+    Public,
+    /// Open a private room (`--host-room`).
+    Host,
+    /// Join the room with this code (`--join-room`). A host switches to this
+    /// once the server hands out the code, so a reconnect returns to the room.
+    Join(String),
+}
+
+/// What the side panel shows. S steps through them in order.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SidePanelMode {
+    #[default]
+    Hidden,
+    /// Your fleet, statistics and intel on the enemy.
+    Overview,
+    /// The enemy fleet, crossed off as it sinks.
+    EnemyFleet,
+}
+
+impl SidePanelMode {
+    pub fn next(self) -> Self {
+        match self {
+            SidePanelMode::Hidden => SidePanelMode::Overview,
+            SidePanelMode::Overview => SidePanelMode::EnemyFleet,
+            SidePanelMode::EnemyFleet => SidePanelMode::Hidden,
+        }
+    }
+}
+
+/// The running score of a `--best-of` series.
+#[derive(Debug, Clone, Copy)]
+pub struct Series {
+    pub wins: u32,
+    pub losses: u32,
+    pub best_of: u32,
+}
+
+impl Series {
+    /// Whether either player has won most of the games.
+    pub fn decided(&self) -> bool {
+        self.wins.max(self.losses) * 2 > self.best_of
+    }
+}
+
+/// Both boards as they stood after one shot.
+pub struct BoardSnapshot {
     pub own_grid: Vec<Vec<CellState>>,
     pub enemy_grid: Vec<Vec<CellState>>,
+}
+
+pub struct GameState {
+    pub own_grid: Board,
+    pub enemy_grid: Vec<Vec<CellState>>,
     pub phase: GamePhase,
     pub cursor: (usize, usize),
     pub placing_ship_idx: usize,
     pub placing_horizontal: bool,
+    // Mines to lay after the fleet, on a server started with --mines
+    pub mines: usize,
+    // Ships may not touch, on a server started with --reveal-sunk
+    pub ships_apart: bool,
     pub messages: Vec<String>,
     pub winner: Option<bool>,
+    // The game ended in an agreed draw
+    pub drawn: bool,
+    // Score of the series, on a server started with --best-of
+    pub series: Option<Series>,
+    pub settings: Settings,
+    // Shot waiting for a second Enter when confirm-before-fire is on
+    pub pending_shot: Option<(usize, usize)>,
+    // Name being typed for the ship currently being placed
+    pub naming_ship: Option<String>,
     // Side panel and stats
-    pub show_side_panel: bool,
+    pub side_panel: SidePanelMode,
     pub ship_status: Vec<ShipStatus>,
     pub total_shots: usize,
     pub total_hits: usize,
     pub turn_count: usize,
+    pub streaks: Streaks,
+    // Boards after every shot, and the one being viewed instead of the live boards
+    pub history: Vec<BoardSnapshot>,
+    pub viewing_turn: Option<usize>,
+    // Where each of our sinking shots landed, for the intel panel
+    pub enemy_sinks: Vec<(usize, usize)>,
+    // The enemy ships we have sunk, when the server names them
+    pub enemy_ships_sunk: Vec<SunkShip>,
     pub turn_start_time: Option<Instant>,
     pub turn_times: Vec<f64>, // Store last 10 turn times
+    // When the current turn runs out, on a server with a turn timeout
+    pub turn_deadline: Option<Instant>,
+    // We offered a draw and await the answer
+    pub offered_draw: bool,
+    // The opponent offered a draw; Y/N answers it
+    pub draw_offered: bool,
+    // While paused, whether we asked for the pause
+    pub paused_by_you: bool,
     // Play again functionality
     pub play_again_response: Option<bool>,
     pub waiting_for_play_again: bool,
+    // Alerts raised by server messages, delivered by the UI loop
+    pub alerts: Vec<NotifyEvent>,
+    pub show_help: bool,
+    pub show_readout: bool,
+    // Render/network loop timings, shown by the F12 overlay
+    pub perf: LoopStats,
+    pub show_perf: bool,
+    // Victory/defeat art shown after GameOver until a key is pressed
+    pub show_result_screen: bool,
+    // Post-game analysis from the server's GameReview, and whether it is open (A)
+    pub analysis: Option<Analysis>,
+    pub show_analysis: bool,
+    // Hints in practice mode against the AI (`play-ai --hints`)
+    pub practice: Option<Practice>,
+    // Set by F8/F9; the UI loop saves the next rendered frame
+    pub snapshot_request: Option<SnapshotFormat>,
+    // Start of the ocean animation clock
+    pub animation_epoch: Instant,
+    // Latest emote from the opponent, shown briefly over the board
+    pub toast: Option<(String, Instant)>,
+    // Shown in the status line while (re)connecting to the server
+    pub connection_status: Option<String>,
+    // Round trip to the server, measured by its heartbeat
+    pub latency: Option<Duration>,
+    // How long the server will hold a dropped opponent's seat
+    pub reconnect_window_secs: u64,
+    // The coach code of the player we follow as their coach (`--coach`)
+    pub coaching: Option<String>,
+    // Hint being typed by a coach
+    pub hint_draft: Option<String>,
+    // Chat line being typed to the opponent (T)
+    pub chat_draft: Option<String>,
+    pub lobby: Lobby,
+    // The opponent's display name, if they gave one
+    pub opponent_name: Option<String>,
+    // Lets a dropped connection resume its seat; issued by the server per game
+    pub session_token: Option<String>,
+    // Sent in Hello, for servers started with --password
+    pub password: Option<String>,
+    // Password being typed after the server turned us away
+    pub password_draft: Option<String>,
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl GameState {
     pub fn new() -> Self {
+        Self::with_settings(Settings::default())
+    }
+
+    pub fn with_settings(settings: Settings) -> Self {
         let mut ship_status = Vec::new();
-        for (length, name) in SHIPS.iter() {
+        for (i, (length, name)) in SHIPS.iter().enumerate() {
+            let name = settings
+                .ship_names
+                .get(i)
+                .map(|custom| custom.trim())
+                .filter(|custom| !custom.is_empty())
+                .unwrap_or(name);
             ship_status.push(ShipStatus {
                 name: name.to_string(),
                 length: *length,
@@ -44,107 +207,137 @@ impl GameState {
         }
 
         Self {
-            own_grid: vec![vec![CellState::Empty; GRID_SIZE]; GRID_SIZE],
+            own_grid: Board::default(),
             enemy_grid: vec![vec![CellState::Empty; GRID_SIZE]; GRID_SIZE],
             phase: GamePhase::Placing,
             cursor: (0, 0),
             placing_ship_idx: 0,
             placing_horizontal: true,
+            mines: 0,
+            ships_apart: false,
             messages: vec!["Place your ships! Use arrows, R to rotate, Enter to place".to_string()],
             winner: None,
+            drawn: false,
+            series: None,
+            settings,
+            pending_shot: None,
+            naming_ship: None,
             // Side panel and stats
-            show_side_panel: false,
+            side_panel: SidePanelMode::Hidden,
             ship_status,
             total_shots: 0,
             total_hits: 0,
             turn_count: 0,
+            streaks: Streaks::default(),
+            history: Vec::new(),
+            viewing_turn: None,
+            enemy_sinks: Vec::new(),
+            enemy_ships_sunk: Vec::new(),
             turn_start_time: None,
             turn_times: Vec::new(),
+            turn_deadline: None,
+            offered_draw: false,
+            draw_offered: false,
+            paused_by_you: false,
             // Play again functionality
             play_again_response: None,
             waiting_for_play_again: false,
+            alerts: Vec::new(),
+            show_help: false,
+            show_readout: false,
+            perf: LoopStats::new(Duration::from_secs(1)),
+            show_perf: perf::enabled(),
+            show_result_screen: false,
+            analysis: None,
+            show_analysis: false,
+            practice: None,
+            snapshot_request: None,
+            animation_epoch: Instant::now(),
+            toast: None,
+            connection_status: None,
+            latency: None,
+            reconnect_window_secs: 0,
+            coaching: None,
+            hint_draft: None,
+            chat_draft: None,
+            lobby: Lobby::Public,
+            opponent_name: None,
+            session_token: None,
+            password: None,
+            password_draft: None,
         }
     }
 
-    pub fn can_place_ship(&self, x: usize, y: usize, length: usize, horizontal: bool) -> bool {
-        if horizontal {
-            if x + length > GRID_SIZE {
-                return false;
-            }
-            for i in 0..length {
-                if self.own_grid[y][x + i] != CellState::Empty {
-                    return false;
-                }
-            }
-        } else {
-            if y + length > GRID_SIZE {
-                return false;
-            }
-            for i in 0..length {
-                if self.own_grid[y + i][x] != CellState::Empty {
-                    return false;
-                }
-            }
-        }
-        true
+    /// Mirrors every placed ship left-to-right (`horizontal`) or top-to-bottom.
+    pub fn mirror_fleet(&mut self, horizontal: bool) {
+        self.own_grid.mirror(horizontal);
     }
 
-    pub fn place_ship(&mut self, x: usize, y: usize, length: usize, horizontal: bool) {
-        if horizontal {
-            for i in 0..length {
-                self.own_grid[y][x + i] = CellState::Ship;
-            }
+    /// Whether a ship of `length` fits at (x, y) the way it is turned, keeping
+    /// clear of the others when the server asks for ships apart.
+    pub fn can_place_ship(&self, x: usize, y: usize, length: usize) -> bool {
+        let horizontal = self.placing_horizontal;
+        if self.ships_apart {
+            self.own_grid.can_place_apart(x, y, length, horizontal)
         } else {
-            for i in 0..length {
-                self.own_grid[y + i][x] = CellState::Ship;
-            }
+            self.own_grid.can_place(x, y, length, horizontal)
         }
     }
 
-    pub fn all_ships_sunk(grid: &[Vec<CellState>]) -> bool {
-        !grid.iter().flatten().any(|c| *c == CellState::Ship)
+    /// Mines still to lay before the fleet can be confirmed.
+    pub fn mines_left(&self) -> usize {
+        let laid = self
+            .own_grid
+            .iter()
+            .flatten()
+            .filter(|cell| **cell == CellState::Mine)
+            .count();
+        self.mines.saturating_sub(laid)
     }
 
-    pub fn is_ship_sunk_at(grid: &[Vec<CellState>], x: usize, y: usize) -> bool {
-        // Check if ship is horizontal or vertical
-        let horiz = (x > 0 && matches!(grid[y][x - 1], CellState::Ship | CellState::Hit))
-            || (x + 1 < GRID_SIZE && matches!(grid[y][x + 1], CellState::Ship | CellState::Hit));
+    /// Moves every placed ship and mine one step in the given direction.
+    /// Returns false, leaving the grid untouched, if one would leave the board.
+    pub fn shift_fleet(&mut self, dx: isize, dy: isize) -> bool {
+        self.own_grid.shift(dx, dy)
+    }
 
-        if horiz {
-            // Check horizontal ship
-            let mut lx = x as isize;
-            while lx >= 0 && matches!(grid[y][lx as usize], CellState::Ship | CellState::Hit) {
-                if grid[y][lx as usize] == CellState::Ship {
-                    return false;
+    /// Describes a board row by row in plain words, for screen readers and
+    /// anywhere the grid can't be drawn. `own` boards list intact ship cells;
+    /// on enemy boards unshot cells are unknown.
+    pub fn describe_board(grid: &[Vec<CellState>], own: bool) -> Vec<String> {
+        let rest = if own { "open water" } else { "unknown" };
+        grid.iter()
+            .enumerate()
+            .map(|(y, row)| {
+                let columns = |state: CellState| -> Vec<String> {
+                    row.iter()
+                        .enumerate()
+                        .filter(|(_, cell)| **cell == state)
+                        .map(|(x, _)| coords::column_label(x))
+                        .collect()
+                };
+                let mut parts = Vec::new();
+                for (state, label) in [
+                    (CellState::Hit, "hit"),
+                    (CellState::Miss, "miss"),
+                    (CellState::Ship, "ship"),
+                    (CellState::Mine, "mine"),
+                ] {
+                    let cols = columns(state);
+                    if !cols.is_empty() {
+                        parts.push(format!("{} at {}", label, cols.join(", ")));
+                    }
                 }
-                lx -= 1;
-            }
-            let mut rx = x + 1;
-            while rx < GRID_SIZE && matches!(grid[y][rx], CellState::Ship | CellState::Hit) {
-                if grid[y][rx] == CellState::Ship {
-                    return false;
-                }
-                rx += 1;
-            }
-            true
-        } else {
-            // Check vertical ship
-            let mut uy = y as isize;
-            while uy >= 0 && matches!(grid[uy as usize][x], CellState::Ship | CellState::Hit) {
-                if grid[uy as usize][x] == CellState::Ship {
-                    return false;
-                }
-                uy -= 1;
-            }
-            let mut dy = y + 1;
-            while dy < GRID_SIZE && matches!(grid[dy][x], CellState::Ship | CellState::Hit) {
-                if grid[dy][x] == CellState::Ship {
-                    return false;
-                }
-                dy += 1;
-            }
-            true
-        }
+                let described = if parts.is_empty() {
+                    format!("all {}", rest)
+                } else if row.contains(&CellState::Empty) {
+                    format!("{}, rest {}", parts.join("; "), rest)
+                } else {
+                    parts.join("; ")
+                };
+                format!("Row {}: {}", coords::row_label(y), described)
+            })
+            .collect()
     }
 
     // Statistics and overlay methods
@@ -163,40 +356,116 @@ impl GameState {
         self.turn_start_time = None;
     }
 
-    pub fn record_shot(&mut self, hit: bool) {
+    /// The opponent's display name, or "Opponent".
+    pub fn opponent(&self) -> &str {
+        self.opponent_name.as_deref().unwrap_or("Opponent")
+    }
+
+    /// What the play-again prompt asks: mid-series, whether to play on.
+    pub fn play_again_question(&self) -> &'static str {
+        match self.series {
+            Some(series) if !series.decided() => "Play the next game of the series? (Y/N)",
+            Some(_) => "Play another series? (Y/N)",
+            None => "Do you want to play again? (Y/N)",
+        }
+    }
+
+    /// Whole seconds left on the turn clock, if the server times turns.
+    pub fn turn_seconds_left(&self) -> Option<u64> {
+        self.turn_deadline.map(|deadline| {
+            deadline
+                .saturating_duration_since(Instant::now())
+                .as_secs_f64()
+                .ceil() as u64
+        })
+    }
+
+    /// Spends a practice-mode hint, which the enemy board then highlights.
+    pub fn take_hint(&mut self) {
+        let Some(practice) = self.practice.as_mut() else {
+            return;
+        };
+        let text = match practice.take_hint() {
+            Some((x, y)) => format!(
+                "💡 Hint: fire at {} ({} left this game)",
+                coords::format(x, y),
+                practice.hints_left
+            ),
+            None => "No hints left this game".to_string(),
+        };
+        self.messages.push(text);
+    }
+
+    pub fn record_shot(&mut self, x: usize, y: usize, hit: bool, sunk: bool) {
         self.total_shots += 1;
+        if sunk {
+            self.enemy_sinks.push((x, y));
+        }
+        let turn = self.turn_count;
+        let streaks = &mut self.streaks;
         if hit {
             self.total_hits += 1;
+            streaks.hit_streak += 1;
+            streaks.best_hit_streak = streaks.best_hit_streak.max(streaks.hit_streak);
+            streaks.miss_streak = 0;
+            streaks.first_hit_turn.get_or_insert(turn);
+            let hunt_started = *streaks.hunt_started.get_or_insert(turn);
+            if sunk {
+                let turns = turn - hunt_started + 1;
+                streaks.fastest_sink = Some(
+                    streaks
+                        .fastest_sink
+                        .map_or(turns, |fastest| fastest.min(turns)),
+                );
+                streaks.hunt_started = None;
+            }
+        } else {
+            streaks.hit_streak = 0;
+            streaks.miss_streak += 1;
+            streaks.longest_drought = streaks.longest_drought.max(streaks.miss_streak);
         }
     }
 
-    pub fn update_ship_status(&mut self) {
-        // Count hits on each ship by analyzing the grid
-        for ship in &mut self.ship_status {
-            ship.hits = 0;
-            ship.sunk = false;
+    /// Records the boards as they are now, after a shot from either side.
+    pub fn snapshot_boards(&mut self) {
+        self.history.push(BoardSnapshot {
+            own_grid: self.own_grid.to_vec(),
+            enemy_grid: self.enemy_grid.clone(),
+        });
+    }
+
+    /// Moves the history view one shot back, or forward if `forward` is set.
+    /// Stepping past the latest shot returns to the live boards.
+    pub fn scrub_history(&mut self, forward: bool) {
+        let latest = self.history.len().saturating_sub(1);
+        self.viewing_turn = match (self.viewing_turn, forward) {
+            (None, false) if latest > 0 => Some(latest - 1),
+            (Some(turn), false) => Some(turn.saturating_sub(1)),
+            (Some(turn), true) if turn + 1 < latest => Some(turn + 1),
+            _ => None,
+        };
+    }
+
+    /// The boards to draw: a past snapshot while scrubbing, otherwise the live ones.
+    pub fn displayed_grids(&self) -> (&[Vec<CellState>], &[Vec<CellState>]) {
+        match self.viewing_turn.and_then(|turn| self.history.get(turn)) {
+            Some(snapshot) => (&snapshot.own_grid, &snapshot.enemy_grid),
+            None => (&self.own_grid, &self.enemy_grid),
         }
+    }
 
-        // Simple approach: count all hits on own grid and distribute to ships
-        // This is a simplified version - in a real implementation you'd track ship positions
-        let total_hits = self
-            .own_grid
-            .iter()
-            .flatten()
-            .filter(|&&cell| cell == CellState::Hit)
-            .count();
+    /// What the player calls their ship of `SHIPS` entry `kind`, custom
+    /// name or not.
+    pub fn own_ship_name(&self, kind: usize) -> &str {
+        &self.ship_status[kind].name
+    }
 
-        // Distribute hits across ships (this is simplified - real implementation would track exact positions)
-        let mut remaining_hits = total_hits;
-        for ship in &mut self.ship_status {
-            if remaining_hits >= ship.length {
-                ship.hits = ship.length;
-                ship.sunk = true;
-                remaining_hits -= ship.length;
-            } else {
-                ship.hits = remaining_hits;
-                remaining_hits = 0;
-            }
+    /// Copies hits and sinks from the fleet on `own_grid` into `ship_status`.
+    pub fn update_ship_status(&mut self) {
+        for (kind, status) in self.ship_status.iter_mut().enumerate() {
+            let ship = self.own_grid.ships().iter().find(|ship| ship.kind == kind);
+            status.hits = ship.map_or(0, |ship| ship.hits);
+            status.sunk = ship.is_some_and(|ship| ship.is_sunk());
         }
     }
 
@@ -220,12 +489,8 @@ impl GameState {
         self.ship_status.iter().filter(|ship| ship.sunk).count()
     }
 
-    pub fn format_coordinate(x: usize, y: usize) -> String {
-        format!("{}{}", (b'A' + y as u8) as char, x + 1)
-    }
-
     pub fn reset_for_new_game(&mut self) {
-        self.own_grid = vec![vec![CellState::Empty; GRID_SIZE]; GRID_SIZE];
+        self.own_grid = Board::default();
         self.enemy_grid = vec![vec![CellState::Empty; GRID_SIZE]; GRID_SIZE];
         self.phase = GamePhase::Placing;
         self.cursor = (0, 0);
@@ -234,11 +499,28 @@ impl GameState {
         self.messages =
             vec!["Place your ships! Use arrows, R to rotate, Enter to place".to_string()];
         self.winner = None;
+        self.drawn = false;
+        self.pending_shot = None;
+        self.naming_ship = None;
+        self.show_result_screen = false;
+        self.analysis = None;
+        self.show_analysis = false;
+        if let Some(practice) = self.practice.as_mut() {
+            practice.reset();
+        }
         self.total_shots = 0;
         self.total_hits = 0;
         self.turn_count = 0;
+        self.streaks = Streaks::default();
+        self.history.clear();
+        self.viewing_turn = None;
+        self.enemy_sinks.clear();
+        self.enemy_ships_sunk.clear();
         self.turn_start_time = None;
         self.turn_times.clear();
+        self.turn_deadline = None;
+        self.offered_draw = false;
+        self.draw_offered = false;
         self.play_again_response = None;
         self.waiting_for_play_again = false;
 
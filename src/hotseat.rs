@@ -0,0 +1,255 @@
+//! Hotseat: two players taking turns at one terminal, with no server. Each
+//! player keeps their own `GameState`, fed the same messages a server would
+//! send, and a privacy screen covers the boards while the keyboard changes
+//! hands.
+
+use anyhow::Result;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{Terminal, backend::CrosstermBackend};
+use std::{io, time::Duration};
+use tokio::sync::mpsc;
+
+use crate::client::apply_server_message;
+use crate::coords;
+use crate::engine::{Board, Shot};
+use crate::game_state::GameState;
+use crate::input::handle_key_event;
+use crate::settings::Settings;
+use crate::snapshot;
+use crate::types::{ErrorCode, Message, SunkShip};
+use crate::ui::{draw_handover, draw_ui};
+
+/// Both players' views of one hotseat match, and the referee's copy of
+/// their fleets. Players are indexed from 0 here.
+struct Hotseat {
+    names: [String; 2],
+    states: [GameState; 2],
+    /// Fleets as placed; `None` until a player confirms theirs.
+    boards: [Option<Board>; 2],
+    /// The player at the keyboard.
+    seat: usize,
+    /// The player whose shot it is.
+    turn: usize,
+    /// Who fires first this game; rematches alternate.
+    first: usize,
+    /// Set while the privacy screen waits for `seat` to take the keyboard.
+    handing_over: bool,
+    /// What the privacy screen tells the next player, e.g. the last shot.
+    note: Option<String>,
+}
+
+impl Hotseat {
+    fn new(settings: Settings, names: [String; 2]) -> Self {
+        let states = [0, 1].map(|player| {
+            let mut state = GameState::with_settings(Settings {
+                name: Some(names[player].clone()),
+                ..settings.clone()
+            });
+            state.opponent_name = Some(names[1 - player].clone());
+            state.reset_for_new_game();
+            state
+        });
+        Self {
+            names,
+            states,
+            boards: [None, None],
+            seat: 0,
+            turn: 0,
+            first: 0,
+            handing_over: true,
+            note: None,
+        }
+    }
+
+    fn tell(&mut self, player: usize, msg: Message) {
+        apply_server_message(&mut self.states[player], msg);
+    }
+
+    /// Covers the boards until `player` presses Enter.
+    fn hand_over(&mut self, player: usize, note: Option<String>) {
+        self.seat = player;
+        self.handing_over = true;
+        self.note = note;
+    }
+
+    /// Plays a message `player` sent as a server would. Returns true once the
+    /// match is over and the terminal should be handed back.
+    fn handle(&mut self, player: usize, msg: Message) -> bool {
+        let opponent = 1 - player;
+        match msg {
+            Message::PlaceShips(board) => {
+                if let Err(detail) = board.check_fleet(0) {
+                    let code = ErrorCode::InvalidPlacement;
+                    self.tell(player, Message::Error { code, detail });
+                    return false;
+                }
+                self.boards[player] = Some(board);
+                if self.boards[opponent].is_none() {
+                    self.hand_over(opponent, Some("Your turn to place ships".to_string()));
+                    return false;
+                }
+                self.turn = self.first;
+                for player in [0, 1] {
+                    self.tell(player, Message::GameStart);
+                }
+                self.tell(self.first, Message::YourTurn);
+                self.tell(1 - self.first, Message::OpponentTurn);
+                let note = format!("{} fires first", self.names[self.first]);
+                self.hand_over(self.first, Some(note));
+            }
+            Message::Attack { x, y } => {
+                let Some(board) = self.boards[opponent].as_mut() else {
+                    return false;
+                };
+                if player != self.turn || board.check_target(x, y).is_err() {
+                    return false;
+                }
+                let shot = board.fire(x, y);
+                let Shot { hit, sunk, .. } = shot;
+                let fleet_sunk = board.all_sunk();
+                let ship = SunkShip::from_shot(&shot);
+                self.tell(
+                    player,
+                    Message::AttackResult {
+                        x,
+                        y,
+                        hit,
+                        sunk,
+                        ship,
+                        revealed: Vec::new(),
+                    },
+                );
+                self.tell(opponent, Message::Attack { x, y });
+                if fleet_sunk {
+                    self.tell(player, Message::GameOver { won: true });
+                    self.tell(opponent, Message::GameOver { won: false });
+                    self.tell(player, Message::PlayAgainRequest);
+                    return false;
+                }
+                self.turn = opponent;
+                self.tell(player, Message::OpponentTurn);
+                self.tell(opponent, Message::YourTurn);
+                // The note is for the player who was shot at, in their ship names
+                let result = match (hit, shot.ship.filter(|_| sunk)) {
+                    (true, Some(kind)) => {
+                        format!("sank your {}", self.states[opponent].own_ship_name(kind))
+                    }
+                    (true, None) => "hit".to_string(),
+                    (false, _) => "missed".to_string(),
+                };
+                let note = format!(
+                    "{} fired at {} and {}",
+                    self.names[player],
+                    coords::format(x, y),
+                    result
+                );
+                self.hand_over(opponent, Some(note));
+            }
+            // Both players read these when they next sit down
+            Message::Chat { .. }
+            | Message::Emote(_)
+            | Message::DrawOffer
+            | Message::DrawResponse { accept: false } => self.tell(opponent, msg),
+            Message::DrawResponse { accept: true } => {
+                for player in [0, 1] {
+                    self.tell(player, Message::GameDrawn);
+                }
+                self.tell(player, Message::PlayAgainRequest);
+            }
+            Message::Pause => {
+                self.tell(player, Message::GamePaused { by_you: true });
+                self.tell(opponent, Message::GamePaused { by_you: false });
+            }
+            Message::Unpause => {
+                for player in [0, 1] {
+                    let your_turn = player == self.turn;
+                    self.tell(player, Message::GameUnpaused { your_turn });
+                }
+            }
+            Message::PlayAgainResponse { wants_to_play } => {
+                if !wants_to_play {
+                    return true;
+                }
+                self.boards = [None, None];
+                self.first = 1 - self.first;
+                for player in [0, 1] {
+                    self.tell(player, Message::NewGameStart);
+                }
+                self.hand_over(0, Some("New game: place your ships".to_string()));
+            }
+            Message::Quit => return true,
+            _ => {}
+        }
+        false
+    }
+}
+
+/// Runs a match for two players sharing this terminal: each places their
+/// fleet and fires in turn, with a privacy screen between turns so neither
+/// sees the other's board.
+pub async fn run_hotseat(settings: Settings, names: [String; 2]) -> Result<()> {
+    let mut hotseat = Hotseat::new(settings, names);
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    loop {
+        let seat = hotseat.seat;
+        let snapshot = hotseat.states[seat].snapshot_request.take();
+        let frame = terminal.draw(|f| {
+            if hotseat.handing_over {
+                draw_handover(f, &hotseat.names[seat], hotseat.note.as_deref());
+            } else {
+                draw_ui(f, &hotseat.states[seat]);
+            }
+        })?;
+        if let Some(format) = snapshot {
+            let saved = snapshot::save(frame.buffer, format);
+            hotseat.states[seat].messages.push(match saved {
+                Ok(path) => format!("Screen saved to {}", path.display()),
+                Err(e) => format!("Could not save screen: {}", e),
+            });
+        }
+        // Bells and desktop alerts would only give away the other player's turn
+        for state in &mut hotseat.states {
+            state.alerts.clear();
+        }
+
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        if hotseat.handing_over {
+            match key.code {
+                KeyCode::Enter => hotseat.handing_over = false,
+                KeyCode::Char('q') | KeyCode::Char('Q') => break,
+                _ => {}
+            }
+            continue;
+        }
+        let mut done = handle_key_event(&mut hotseat.states[seat], key, &tx);
+        while let Ok(msg) = rx.try_recv() {
+            done |= hotseat.handle(seat, msg);
+        }
+        if done {
+            break;
+        }
+    }
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    Ok(())
+}
@@ -1,15 +1,165 @@
+use crate::clipboard;
+use crate::coords;
 use crate::game_state::GameState;
-use crate::types::{CellState, GRID_SIZE, GamePhase, Message, SHIPS};
-use crossterm::event::{KeyCode, KeyEvent};
+use crate::snapshot::SnapshotFormat;
+use crate::types::{
+    CellState, Emote, GRID_SIZE, GamePhase, MAX_CHAT_LEN, MAX_HINT_LEN, Message, SHIPS,
+};
+use crate::ui::grid_to_text;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use tokio::sync::mpsc;
 
+const MAX_SHIP_NAME_LEN: usize = 24;
+
 pub fn handle_key_event(
     state: &mut GameState,
     key: KeyEvent,
     tx: &mpsc::UnboundedSender<Message>,
 ) -> bool {
+    if state.password_draft.is_some() {
+        return handle_password_key(state, key);
+    }
+    if state.naming_ship.is_some() {
+        handle_naming_key(state, key);
+        return false;
+    }
+    if state.hint_draft.is_some() {
+        handle_hint_key(state, key, tx);
+        return false;
+    }
+    if state.chat_draft.is_some() {
+        handle_chat_key(state, key, tx);
+        return false;
+    }
+
+    // Any key leaves the result screen and then acts as usual, so Y/N still answer the prompt
+    state.show_result_screen = false;
+
+    match key.code {
+        KeyCode::Char('?') => {
+            state.show_help = !state.show_help;
+            return false;
+        }
+        KeyCode::Esc if state.show_help => {
+            state.show_help = false;
+            return false;
+        }
+        KeyCode::Char('d') | KeyCode::Char('D') => {
+            state.show_readout = !state.show_readout;
+            return false;
+        }
+        KeyCode::Esc if state.show_readout => {
+            state.show_readout = false;
+            return false;
+        }
+        KeyCode::Char('a') | KeyCode::Char('A') if state.analysis.is_some() => {
+            state.show_analysis = !state.show_analysis;
+            return false;
+        }
+        KeyCode::Esc if state.show_analysis => {
+            state.show_analysis = false;
+            return false;
+        }
+        _ => {}
+    }
+
+    if let KeyCode::F(n @ 5..=7) = key.code {
+        copy_boards(state, n);
+        return false;
+    }
+
+    match key.code {
+        KeyCode::F(12) => state.show_perf = !state.show_perf,
+        KeyCode::F(8) => state.snapshot_request = Some(SnapshotFormat::Text),
+        KeyCode::F(9) => state.snapshot_request = Some(SnapshotFormat::Ansi),
+        _ => {}
+    }
+
+    // Coaches watch and advise but cannot act
+    if state.coaching.is_some() {
+        match key.code {
+            KeyCode::Char('h') | KeyCode::Char('H') => state.hint_draft = Some(String::new()),
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                state.side_panel = state.side_panel.next();
+            }
+            KeyCode::Char('[') => state.scrub_history(false),
+            KeyCode::Char(']') => state.scrub_history(true),
+            KeyCode::Esc => state.viewing_turn = None,
+            KeyCode::Char('q') => {
+                let _ = tx.send(Message::Quit);
+                return true;
+            }
+            _ => {}
+        }
+        return false;
+    }
+
+    if let KeyCode::F(n @ 1..=4) = key.code {
+        let emote = Emote::ALL[n as usize - 1];
+        let _ = tx.send(Message::Emote(emote));
+        state.messages.push(format!("You: {}", emote.text()));
+        return false;
+    }
+    if let KeyCode::Char('t') | KeyCode::Char('T') = key.code {
+        state.chat_draft = Some(String::new());
+        return false;
+    }
+
+    let in_battle = matches!(state.phase, GamePhase::YourTurn | GamePhase::OpponentTurn);
+    if in_battle && state.draw_offered {
+        let accept = match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => true,
+            KeyCode::Char('n') | KeyCode::Char('N') => false,
+            _ => return false,
+        };
+        state.draw_offered = false;
+        let _ = tx.send(Message::DrawResponse { accept });
+        if !accept {
+            state.messages.push("You declined the draw".to_string());
+        }
+        return false;
+    }
+    if in_battle && let KeyCode::Char('p') | KeyCode::Char('P') = key.code {
+        let _ = tx.send(Message::Pause);
+        return false;
+    }
+    if in_battle && let KeyCode::Char('o') | KeyCode::Char('O') = key.code {
+        if !state.offered_draw {
+            state.offered_draw = true;
+            let _ = tx.send(Message::DrawOffer);
+            state.messages.push(format!(
+                "You offered {} a draw",
+                state.opponent_name.as_deref().unwrap_or("your opponent")
+            ));
+        }
+        return false;
+    }
+
     match state.phase {
         GamePhase::Placing => match key.code {
+            KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right
+                if key.modifiers.contains(KeyModifiers::SHIFT) =>
+            {
+                let (dx, dy) = match key.code {
+                    KeyCode::Up => (0, -1),
+                    KeyCode::Down => (0, 1),
+                    KeyCode::Left => (-1, 0),
+                    _ => (1, 0),
+                };
+                if !state.shift_fleet(dx, dy) {
+                    state
+                        .messages
+                        .push("Can't shift the fleet off the board".to_string());
+                }
+            }
+            KeyCode::Char('h') | KeyCode::Char('H') => {
+                state.mirror_fleet(true);
+                state.messages.push("Fleet mirrored left-right".to_string());
+            }
+            KeyCode::Char('v') | KeyCode::Char('V') => {
+                state.mirror_fleet(false);
+                state.messages.push("Fleet mirrored top-bottom".to_string());
+            }
             KeyCode::Up => {
                 state.cursor.1 = state.cursor.1.saturating_sub(1);
             }
@@ -53,30 +203,56 @@ pub fn handle_key_event(
                     }
                 }
             }
-            KeyCode::Enter => {
-                if state.placing_ship_idx < SHIPS.len() {
-                    let (length, name) = SHIPS[state.placing_ship_idx];
-                    let (x, y) = state.cursor;
-                    if state.can_place_ship(x, y, length, state.placing_horizontal) {
-                        state.place_ship(x, y, length, state.placing_horizontal);
-                        state.messages.push(format!("{} placed!", name));
-                        state.placing_ship_idx += 1;
-
-                        if state.placing_ship_idx >= SHIPS.len() {
-                            state
-                                .messages
-                                .push("All ships placed! Waiting for opponent...".to_string());
-                            state.phase = GamePhase::WaitingForOpponent;
-                            let _ = tx.send(Message::PlaceShips(state.own_grid.clone()));
-                        } else {
-                            state.messages.push(format!(
-                                "Place {} (length {})",
-                                SHIPS[state.placing_ship_idx].1, SHIPS[state.placing_ship_idx].0
-                            ));
-                        }
+            KeyCode::Char('n') | KeyCode::Char('N') if state.placing_ship_idx < SHIPS.len() => {
+                state.naming_ship = Some(String::new());
+            }
+            KeyCode::Enter if state.placing_ship_idx < SHIPS.len() => {
+                let (length, _) = SHIPS[state.placing_ship_idx];
+                let (x, y) = state.cursor;
+                if state.can_place_ship(x, y, length) {
+                    state.own_grid.place(x, y, length, state.placing_horizontal);
+                    let name = &state.ship_status[state.placing_ship_idx].name;
+                    state.messages.push(format!("{} placed!", name));
+                    state.placing_ship_idx += 1;
+
+                    if state.placing_ship_idx >= SHIPS.len() && state.mines > 0 {
+                        state.messages.push(format!(
+                            "All ships placed! Now lay {} mines on open water",
+                            state.mines
+                        ));
+                    } else if state.placing_ship_idx >= SHIPS.len() {
+                        state.messages.push(
+                            "All ships placed! Press Enter to confirm or adjust the fleet"
+                                .to_string(),
+                        );
+                    } else {
+                        let ship = &state.ship_status[state.placing_ship_idx];
+                        state.messages.push(format!(
+                            "Place {} (length {}) - N to rename",
+                            ship.name, ship.length
+                        ));
                     }
                 }
             }
+            KeyCode::Enter if state.mines_left() > 0 => {
+                let (x, y) = state.cursor;
+                if state.own_grid[y][x] == CellState::Empty {
+                    state.own_grid[y][x] = CellState::Mine;
+                    state.messages.push(match state.mines_left() {
+                        0 => "Mines laid! Press Enter to confirm or adjust the fleet".to_string(),
+                        left => format!("Mine laid at {}, {} to go", coords::format(x, y), left),
+                    });
+                } else {
+                    state.messages.push("Mines go on open water".to_string());
+                }
+            }
+            KeyCode::Enter => {
+                state
+                    .messages
+                    .push("Fleet confirmed! Waiting for opponent...".to_string());
+                state.phase = GamePhase::WaitingForOpponent;
+                let _ = tx.send(Message::PlaceShips(state.own_grid.clone()));
+            }
             KeyCode::Char('q') => {
                 let _ = tx.send(Message::Quit);
                 return true;
@@ -84,23 +260,64 @@ pub fn handle_key_event(
             _ => {}
         },
         GamePhase::YourTurn => match key.code {
-            KeyCode::Up => state.cursor.1 = state.cursor.1.saturating_sub(1),
-            KeyCode::Down => state.cursor.1 = (state.cursor.1 + 1).min(GRID_SIZE - 1),
-            KeyCode::Left => state.cursor.0 = state.cursor.0.saturating_sub(1),
-            KeyCode::Right => state.cursor.0 = (state.cursor.0 + 1).min(GRID_SIZE - 1),
+            KeyCode::Up => {
+                state.cursor.1 = state.cursor.1.saturating_sub(1);
+                state.pending_shot = None;
+            }
+            KeyCode::Down => {
+                state.cursor.1 = (state.cursor.1 + 1).min(GRID_SIZE - 1);
+                state.pending_shot = None;
+            }
+            KeyCode::Left => {
+                state.cursor.0 = state.cursor.0.saturating_sub(1);
+                state.pending_shot = None;
+            }
+            KeyCode::Right => {
+                state.cursor.0 = (state.cursor.0 + 1).min(GRID_SIZE - 1);
+                state.pending_shot = None;
+            }
+            KeyCode::Esc if state.pending_shot.is_some() => {
+                state.pending_shot = None;
+                state.messages.push("Shot cancelled".to_string());
+            }
             KeyCode::Enter => {
                 let (x, y) = state.cursor;
-                if state.enemy_grid[y][x] == CellState::Empty {
-                    let _ = tx.send(Message::Attack { x, y });
-                    state.phase = GamePhase::OpponentTurn;
-                    state.messages.push(format!(
-                        "Firing at {}...",
-                        crate::game_state::GameState::format_coordinate(x, y)
-                    ));
+                // Ship cells on the enemy board were sighted by a squall
+                if matches!(state.enemy_grid[y][x], CellState::Empty | CellState::Ship) {
+                    if state.settings.confirm_fire && state.pending_shot != Some((x, y)) {
+                        state.pending_shot = Some((x, y));
+                        state.messages.push(format!(
+                            "Press Enter again to fire at {} (Esc to cancel)",
+                            coords::format(x, y)
+                        ));
+                    } else {
+                        state.pending_shot = None;
+                        let _ = tx.send(Message::Attack { x, y });
+                        state.phase = GamePhase::OpponentTurn;
+                        state
+                            .messages
+                            .push(format!("Firing at {}...", coords::format(x, y)));
+                    }
                 }
             }
+            KeyCode::Char('h') | KeyCode::Char('H') if state.practice.is_some() => {
+                state.take_hint();
+            }
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                state.side_panel = state.side_panel.next();
+            }
+            KeyCode::Char('q') => {
+                let _ = tx.send(Message::Quit);
+                return true;
+            }
+            _ => {}
+        },
+        GamePhase::Paused => match key.code {
+            KeyCode::Char('p') | KeyCode::Char('P') => {
+                let _ = tx.send(Message::Unpause);
+            }
             KeyCode::Char('s') | KeyCode::Char('S') => {
-                state.show_side_panel = !state.show_side_panel;
+                state.side_panel = state.side_panel.next();
             }
             KeyCode::Char('q') => {
                 let _ = tx.send(Message::Quit);
@@ -137,10 +354,33 @@ pub fn handle_key_event(
             }
             _ => {}
         },
+        GamePhase::OpponentDisconnected => match key.code {
+            KeyCode::Char('c') | KeyCode::Char('C') => {
+                let _ = tx.send(Message::ClaimVictory);
+                state.messages.push("Claiming victory...".to_string());
+                state.phase = GamePhase::WaitingForOpponent;
+            }
+            KeyCode::Char('w') | KeyCode::Char('W') => {
+                let _ = tx.send(Message::WaitForReconnect);
+                state.messages.push(format!(
+                    "Waiting up to {}s for opponent to reconnect...",
+                    state.reconnect_window_secs
+                ));
+                state.phase = GamePhase::WaitingForOpponent;
+            }
+            KeyCode::Char('q') => {
+                let _ = tx.send(Message::Quit);
+                return true;
+            }
+            _ => {}
+        },
         GamePhase::WaitingForOpponent | GamePhase::OpponentTurn => match key.code {
             KeyCode::Char('s') | KeyCode::Char('S') => {
-                state.show_side_panel = !state.show_side_panel;
+                state.side_panel = state.side_panel.next();
             }
+            KeyCode::Char('[') => state.scrub_history(false),
+            KeyCode::Char(']') => state.scrub_history(true),
+            KeyCode::Esc => state.viewing_turn = None,
             KeyCode::Char('q') => {
                 let _ = tx.send(Message::Quit);
                 return true;
@@ -150,3 +390,116 @@ pub fn handle_key_event(
     }
     false
 }
+
+/// Copies your board (F5), the enemy board (F6) or both (F7) to the clipboard.
+fn copy_boards(state: &mut GameState, key: u8) {
+    let own = grid_to_text(&state.own_grid, "Your Fleet");
+    let enemy = grid_to_text(&state.enemy_grid, "Enemy Waters");
+    let (text, what) = match key {
+        5 => (own, "Your board"),
+        6 => (enemy, "Enemy board"),
+        _ => (format!("{}\n{}", own, enemy), "Both boards"),
+    };
+    state.messages.push(match clipboard::copy(text) {
+        Ok(()) => format!("{} copied to clipboard", what),
+        Err(e) => format!("Could not copy to clipboard: {}", e),
+    });
+}
+
+/// Edits the name of the ship being placed. Enter saves it, Esc keeps the old one.
+fn handle_naming_key(state: &mut GameState, key: KeyEvent) {
+    let Some(name) = state.naming_ship.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Char(c) if name.chars().count() < MAX_SHIP_NAME_LEN => name.push(c),
+        KeyCode::Backspace => {
+            name.pop();
+        }
+        KeyCode::Enter => {
+            let name = state.naming_ship.take().unwrap_or_default();
+            let name = name.trim();
+            if !name.is_empty() && state.placing_ship_idx < SHIPS.len() {
+                let ship = &mut state.ship_status[state.placing_ship_idx];
+                state
+                    .messages
+                    .push(format!("{} renamed to {}", ship.name, name));
+                ship.name = name.to_string();
+            }
+        }
+        KeyCode::Esc => state.naming_ship = None,
+        _ => {}
+    }
+}
+
+/// Edits the password the server asked for. Enter connects with it, Esc
+/// quits. Returns whether to quit.
+fn handle_password_key(state: &mut GameState, key: KeyEvent) -> bool {
+    let Some(password) = state.password_draft.as_mut() else {
+        return false;
+    };
+    match key.code {
+        KeyCode::Char(c) => password.push(c),
+        KeyCode::Backspace => {
+            password.pop();
+        }
+        KeyCode::Enter => {
+            state.password = state.password_draft.take();
+            state
+                .messages
+                .push("Connecting with the password...".to_string());
+        }
+        KeyCode::Esc => return true,
+        _ => {}
+    }
+    false
+}
+
+/// Edits a coach's hint. Enter sends it to the coached player, Esc discards it.
+fn handle_hint_key(state: &mut GameState, key: KeyEvent, tx: &mpsc::UnboundedSender<Message>) {
+    let Some(hint) = state.hint_draft.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Char(c) if hint.chars().count() < MAX_HINT_LEN => hint.push(c),
+        KeyCode::Backspace => {
+            hint.pop();
+        }
+        KeyCode::Enter => {
+            let hint = state.hint_draft.take().unwrap_or_default();
+            let text = hint.trim();
+            if !text.is_empty() {
+                let _ = tx.send(Message::Hint {
+                    text: text.to_string(),
+                });
+            }
+        }
+        KeyCode::Esc => state.hint_draft = None,
+        _ => {}
+    }
+}
+
+/// Edits a chat line. Enter sends it to the opponent, Esc discards it.
+fn handle_chat_key(state: &mut GameState, key: KeyEvent, tx: &mpsc::UnboundedSender<Message>) {
+    let Some(line) = state.chat_draft.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Char(c) if line.chars().count() < MAX_CHAT_LEN => line.push(c),
+        KeyCode::Backspace => {
+            line.pop();
+        }
+        KeyCode::Enter => {
+            let line = state.chat_draft.take().unwrap_or_default();
+            let text = line.trim();
+            if !text.is_empty() {
+                let _ = tx.send(Message::Chat {
+                    text: text.to_string(),
+                });
+                state.messages.push(format!("You: {}", text));
+            }
+        }
+        KeyCode::Esc => state.chat_draft = None,
+        _ => {}
+    }
+}
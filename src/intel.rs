@@ -0,0 +1,105 @@
+use crate::types::{CellState, GRID_SIZE, SHIPS};
+
+/// What can be worked out about the enemy fleet from our shots alone.
+pub struct Intel {
+    /// Lengths of the ships we have sunk, in the order they went down.
+    pub sunk: Vec<usize>,
+    /// Lengths of the ships still afloat, longest first.
+    pub afloat: Vec<usize>,
+    /// Unshot cells that no remaining ship could cover.
+    pub impossible: Vec<Vec<bool>>,
+}
+
+impl Intel {
+    pub fn impossible_count(&self) -> usize {
+        self.impossible
+            .iter()
+            .flatten()
+            .filter(|&&cell| cell)
+            .count()
+    }
+}
+
+/// Deduces the enemy fleet's state from our view of their board and the cells
+/// where each sinking shot landed.
+pub fn deduce(enemy_grid: &[Vec<CellState>], sinks: &[(usize, usize)]) -> Intel {
+    let mut afloat: Vec<usize> = SHIPS.iter().map(|(length, _)| *length).collect();
+    let mut claimed = vec![vec![false; GRID_SIZE]; GRID_SIZE];
+    let mut sunk = Vec::new();
+
+    for &(x, y) in sinks {
+        let open = |cx: usize, cy: usize| enemy_grid[cy][cx] == CellState::Hit && !claimed[cy][cx];
+        let horizontal = run(x, |i| open(i, y));
+        let vertical = run(y, |i| open(x, i));
+        let ((start, end), horizontal) = if horizontal.1 - horizontal.0 >= vertical.1 - vertical.0 {
+            (horizontal, true)
+        } else {
+            (vertical, false)
+        };
+
+        // The longest ship still afloat that fits the run of hits
+        let Some(index) = afloat
+            .iter()
+            .enumerate()
+            .filter(|(_, length)| **length <= end - start + 1)
+            .max_by_key(|(_, length)| **length)
+            .map(|(index, _)| index)
+        else {
+            continue;
+        };
+        let length = afloat.remove(index);
+        sunk.push(length);
+
+        // The sinking shot is part of the ship, so keep the claimed cells around it
+        let pos = if horizontal { x } else { y };
+        let first = start.max((pos + 1).saturating_sub(length));
+        if horizontal {
+            claimed[y][first..first + length].fill(true);
+        } else {
+            for row in &mut claimed[first..first + length] {
+                row[x] = true;
+            }
+        }
+    }
+
+    let usable = |x: usize, y: usize| enemy_grid[y][x] != CellState::Miss && !claimed[y][x];
+    let mut impossible = vec![vec![false; GRID_SIZE]; GRID_SIZE];
+    for y in 0..GRID_SIZE {
+        for x in 0..GRID_SIZE {
+            if enemy_grid[y][x] != CellState::Empty {
+                continue;
+            }
+            let fits = afloat.iter().any(|&length| {
+                fits_through(x, length, |i| usable(i, y))
+                    || fits_through(y, length, |i| usable(x, i))
+            });
+            impossible[y][x] = !fits;
+        }
+    }
+
+    afloat.sort_unstable_by(|a, b| b.cmp(a));
+    Intel {
+        sunk,
+        afloat,
+        impossible,
+    }
+}
+
+/// The inclusive range of consecutive indices around `pos` for which `open` holds.
+fn run(pos: usize, open: impl Fn(usize) -> bool) -> (usize, usize) {
+    let mut start = pos;
+    while start > 0 && open(start - 1) {
+        start -= 1;
+    }
+    let mut end = pos;
+    while end + 1 < GRID_SIZE && open(end + 1) {
+        end += 1;
+    }
+    (start, end)
+}
+
+/// Whether a ship of `length` could lie along a line through `pos`.
+fn fits_through(pos: usize, length: usize, usable: impl Fn(usize) -> bool) -> bool {
+    let (start, end) = run(pos, usable);
+    end - start + 1 >= length
+}
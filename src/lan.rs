@@ -0,0 +1,113 @@
+//! LAN discovery, so friends on the same network can find a game without
+//! swapping IP addresses. A server started with `--lan` answers a UDP
+//! broadcast on `DISCOVERY_PORT` with its directory `Listing`; `client --lan`
+//! sends one and lists whoever answers.
+
+use anyhow::{Context, Result, bail};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use crate::dashboard;
+use crate::directory::{self, Listing};
+
+/// The UDP port servers listen on for discovery queries.
+pub const DISCOVERY_PORT: u16 = 48080;
+
+/// What a client broadcasts to ask who is there.
+const QUERY: &[u8] = b"battleship-rs discover";
+
+/// How long a client collects answers.
+const LISTEN_FOR: Duration = Duration::from_millis(1500);
+
+/// Answers discovery queries with `listing` on a background thread until the
+/// server exits. Several servers on one machine can all answer.
+pub fn respond(mut listing: Listing) -> Result<()> {
+    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    socket.set_reuse_address(true)?;
+    let addr = SocketAddr::from((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT));
+    socket.bind(&addr.into()).with_context(|| {
+        format!(
+            "could not listen for LAN discovery on UDP port {}",
+            DISCOVERY_PORT
+        )
+    })?;
+    let socket = UdpSocket::from(socket);
+    dashboard::log(format!(
+        "Answering LAN discovery on UDP port {}",
+        DISCOVERY_PORT
+    ));
+
+    std::thread::spawn(move || {
+        let mut query = [0; 64];
+        loop {
+            let Ok((len, from)) = socket.recv_from(&mut query) else {
+                continue;
+            };
+            // Only neighbours get an answer, so the server cannot be used
+            // to bounce traffic at someone across the internet
+            if &query[..len] != QUERY || !on_lan(from.ip()) {
+                continue;
+            }
+            listing.players = directory::players_online();
+            if let Ok(answer) = serde_json::to_vec(&listing) {
+                let _ = socket.send_to(&answer, from);
+            }
+        }
+    });
+    Ok(())
+}
+
+fn on_lan(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => ip.is_private() || ip.is_loopback() || ip.is_link_local(),
+        IpAddr::V6(ip) => ip.is_loopback(),
+    }
+}
+
+/// Broadcasts a discovery query and returns the servers that answered,
+/// each with the address it answered from.
+pub fn discover() -> Result<Vec<Listing>> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .context("could not open a UDP socket for LAN discovery")?;
+    socket.set_broadcast(true)?;
+    // The broadcast reaches servers on this machine too; without a network,
+    // at least look there
+    if socket
+        .send_to(QUERY, (Ipv4Addr::BROADCAST, DISCOVERY_PORT))
+        .is_err()
+        && socket
+            .send_to(QUERY, (Ipv4Addr::LOCALHOST, DISCOVERY_PORT))
+            .is_err()
+    {
+        bail!("could not send a LAN discovery query");
+    }
+
+    let deadline = Instant::now() + LISTEN_FOR;
+    let mut listings: Vec<Listing> = Vec::new();
+    let mut answer = [0; 2048];
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        socket.set_read_timeout(Some(left))?;
+        let Ok((len, from)) = socket.recv_from(&mut answer) else {
+            break;
+        };
+        let Ok(mut listing) = serde_json::from_slice::<Listing>(&answer[..len]) else {
+            continue;
+        };
+        let address = SocketAddr::new(from.ip(), listing.port).to_string();
+        if listings
+            .iter()
+            .all(|known| known.address.as_deref() != Some(address.as_str()))
+        {
+            listing.address = Some(address);
+            listings.push(listing);
+        }
+    }
+    Ok(listings)
+}
@@ -0,0 +1,45 @@
+//! Networked terminal Battleship: the game rules, the client and every server
+//! mode. The `battleship-rs` binary is a thin command line over this crate;
+//! bots, tests and other frontends can depend on it directly.
+//!
+//! Start a game with [`server::run_server`], [`server_ai::run_server_ai`] or
+//! [`server_relay::run_server_relay`], and play with [`client::run_client`];
+//! [`hotseat::run_hotseat`] plays two people at one terminal without either.
+//! [`engine::Board`] holds the rules, and [`types::Message`] is the wire
+//! protocol.
+
+pub mod ai;
+pub mod ai_audit;
+pub mod analysis;
+pub mod arena;
+pub mod backfill;
+pub mod bot;
+pub mod browser;
+pub mod chat;
+pub mod client;
+mod clipboard;
+pub mod codec;
+pub mod coords;
+pub mod dashboard;
+pub mod directory;
+pub mod engine;
+pub mod game_state;
+pub mod hotseat;
+mod input;
+pub mod intel;
+pub mod lan;
+pub mod net;
+pub mod notify;
+pub mod perf;
+pub mod practice;
+pub mod server;
+pub mod server_ai;
+pub mod server_relay;
+pub mod settings;
+pub mod snapshot;
+pub mod tls;
+pub mod types;
+mod ui;
+pub mod weather;
+pub mod webhook;
+pub mod websocket;
@@ -1,72 +1,199 @@
-mod client;
-mod game_state;
-mod input;
-mod server;
-mod server_ai;
-mod server_relay;
-mod types;
-mod ui;
+mod cli;
 
 use anyhow::Result;
-use client::run_client;
-use server::run_server;
-use server_ai::run_server_ai;
-use server_relay::run_server_relay;
+use battleship_rs::ai::AiStrategy;
+use battleship_rs::arena::{run_ai_vs_ai, run_simulate};
+use battleship_rs::backfill::Backfill;
+use battleship_rs::bot::ExternalBot;
+use battleship_rs::client::{run_client, run_demo, run_play_ai};
+use battleship_rs::codec::{self, Codec};
+use battleship_rs::game_state::Lobby;
+use battleship_rs::hotseat::run_hotseat;
+use battleship_rs::net::ClientTransport;
+use battleship_rs::server::{Hosting, TurnLimit, run_server};
+use battleship_rs::server_ai::run_server_ai;
+use battleship_rs::server_relay::run_server_relay;
+use battleship_rs::settings::Settings;
+use battleship_rs::types::MINES;
+use battleship_rs::{browser, chat, coords, directory, lan, perf, tls, weather, webhook};
+use clap::Parser;
+use cli::{Cli, Command};
+use std::time::Duration;
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args: Vec<String> = std::env::args().collect();
-
-    if args.len() < 2 {
-        println!("🚢 BATTLESHIP - Networked Terminal Game\n");
-        println!("Usage:");
-        println!("  Two-player server: {} server <port>", args[0]);
-        println!("  AI opponent:       {} server-ai <port>", args[0]);
-        println!("  Relay server:      {} server-relay <port>", args[0]);
-        println!("  Client:            {} client <host:port>", args[0]);
-        println!("\nExamples:");
-        println!("  # Start a server for two players");
-        println!("  {} server 8080", args[0]);
-        println!();
-        println!("  # Connect as player 1");
-        println!("  {} client 127.0.0.1:8080", args[0]);
-        println!();
-        println!("  # Connect as player 2 (in another terminal)");
-        println!("  {} client 127.0.0.1:8080", args[0]);
-        println!();
-        println!("  # Or play against AI");
-        println!("  {} server-ai 8080", args[0]);
-        println!("  {} client 127.0.0.1:8080", args[0]);
-        println!();
-        println!("  # Or use relay server for remote play");
-        println!("  {} server-relay 8080", args[0]);
-        println!("  {} client your-server-ip:8080  # Player 1", args[0]);
-        println!("  {} client your-server-ip:8080  # Player 2", args[0]);
-        println!();
-        return Ok(());
+    let cli = Cli::parse();
+    let timeouts = cli.network.timeouts();
+    coords::set_scheme(cli.coords);
+    if cli.perf {
+        perf::enable();
     }
 
-    match args[1].as_str() {
-        "server" => {
-            let port = args.get(2).map(|s| s.as_str()).unwrap_or("8080");
-            run_server(port).await
+    match cli.command {
+        Command::Server(args) => {
+            let addr = args.listen.addr()?;
+            if let Some(url) = args.webhook {
+                webhook::set_url(url);
+            }
+            if let Some(path) = &args.chat_blocklist {
+                chat::filter_from_file(path)?;
+            } else if args.chat_filter {
+                chat::filter_default();
+            }
+            let max_connections = args.max_connections.map(|max| max as usize);
+            if args.announce.is_some() || args.lan {
+                let mut rules = Vec::new();
+                if args.stormy_seas {
+                    rules.push("stormy-seas".to_string());
+                }
+                if args.mines {
+                    rules.push("mines".to_string());
+                }
+                if args.reveal_sunk {
+                    rules.push("reveal-sunk".to_string());
+                }
+                if let Some(games) = args.best_of {
+                    rules.push(format!("best-of {}", games));
+                }
+                if let Some(secs) = args.turn_timeout {
+                    rules.push(format!("turn-timeout {}s", secs));
+                }
+                if args.password.is_some() {
+                    rules.push("password".to_string());
+                }
+                let listing = directory::Listing {
+                    name: args.name,
+                    region: args.region,
+                    port: addr.port(),
+                    players: 0,
+                    max_connections,
+                    rules,
+                    address: None,
+                };
+                if args.lan {
+                    lan::respond(directory::Listing {
+                        region: "LAN".to_string(),
+                        ..listing.clone()
+                    })?;
+                }
+                if let Some(url) = args.announce {
+                    directory::announce(url, listing);
+                }
+            }
+            let sea: fn() -> Box<dyn weather::EventGenerator> = if args.stormy_seas {
+                || Box::new(weather::StormySeas::default())
+            } else {
+                || Box::new(weather::CalmSeas)
+            };
+            let hosting = Hosting {
+                max_games: args.max_games.map(|max| max as usize),
+                max_connections,
+                sea,
+                turn_limit: args.turn_timeout.map(|secs| TurnLimit {
+                    timeout: Duration::from_secs(secs),
+                    expiry: args.on_turn_timeout,
+                }),
+                mines: if args.mines { MINES } else { 0 },
+                reveal_sunk: args.reveal_sunk,
+                best_of: args.best_of,
+                backfill: args.bot_after.map(|secs| Backfill {
+                    after: Duration::from_secs(secs),
+                    difficulty: args.bot_difficulty,
+                }),
+                password: args.password,
+                transport: args.transport.transport()?,
+                timeouts,
+            };
+            run_server(addr, args.dashboard, hosting).await
+        }
+        Command::ServerAi(args) => {
+            let strategy: Box<dyn AiStrategy> = match &args.engine {
+                Some(command) => Box::new(ExternalBot::spawn(command)?),
+                None => args.difficulty.strategy(),
+            };
+            run_server_ai(
+                args.listen.addr()?,
+                strategy,
+                args.think_time,
+                args.audit_log.as_deref(),
+                args.password.as_deref(),
+                args.transport.transport()?,
+                timeouts,
+            )
+            .await
+        }
+        Command::ServerRelay(args) => {
+            if let Some(url) = args.webhook {
+                webhook::set_url(url);
+            }
+            run_server_relay(
+                args.listen.addr()?,
+                args.password,
+                args.transport.transport()?,
+                timeouts,
+            )
+            .await
         }
-        "server-ai" => {
-            let port = args.get(2).map(|s| s.as_str()).unwrap_or("8080");
-            run_server_ai(port).await
+        Command::PlayAi(args) => {
+            run_play_ai(
+                args.settings.load()?,
+                args.difficulty,
+                args.think_time,
+                args.hints.map(|hints| hints as usize),
+            )
+            .await
         }
-        "server-relay" => {
-            let port = args.get(2).map(|s| s.as_str()).unwrap_or("8080");
-            run_server_relay(port).await
+        Command::Hotseat(args) => {
+            let settings = Settings::load(args.settings.as_deref())?;
+            run_hotseat(settings, [args.player1, args.player2]).await
         }
-        "client" => {
-            let addr = args.get(2).map(|s| s.as_str()).unwrap_or("127.0.0.1:8080");
-            run_client(addr).await
+        Command::AiVsAi(args) => run_ai_vs_ai(args.ai_a, args.ai_b, args.games, args.watch).await,
+        Command::Simulate(args) => {
+            run_simulate(args.ai_a, args.ai_b, args.games, args.seed, args.json)
         }
-        _ => {
-            println!("Invalid command. Use 'server', 'server-ai', 'server-relay', or 'client'");
-            println!("Run without arguments for help");
-            Ok(())
+        Command::Client(args) if args.demo => run_demo().await,
+        Command::Client(args) => {
+            let source = match args.browse {
+                Some(url) => Some(browser::Source::Directory(url)),
+                None if args.lan => Some(browser::Source::Lan),
+                None => None,
+            };
+            let addr = match source {
+                Some(source) => match browser::choose_server(&source)? {
+                    Some(addr) => addr,
+                    None => return Ok(()),
+                },
+                None => args.addr,
+            };
+            let mut settings = args.settings.load()?;
+            if args.name.is_some() {
+                settings.name = args.name;
+            }
+            let coach = args.coach.map(|code| code.trim().to_uppercase());
+            let lobby = match args.join_room {
+                Some(code) => Lobby::Join(code.trim().to_uppercase()),
+                None if args.host_room => Lobby::Host,
+                None => Lobby::Public,
+            };
+            let transport = ClientTransport {
+                tls: args
+                    .tls
+                    .then(|| tls::connector(!args.insecure))
+                    .transpose()?,
+                websocket: args.websocket,
+                codec: args.msgpack.then_some(&codec::MessagePack as &dyn Codec),
+                compress: args.compress,
+            };
+            run_client(
+                &addr,
+                timeouts,
+                transport,
+                settings,
+                coach,
+                lobby,
+                args.password,
+            )
+            .await
         }
     }
 }
@@ -0,0 +1,726 @@
+use anyhow::{Context, Result, anyhow};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::{
+    io::{self, Write},
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    task::{Context as TaskContext, Poll, Waker, ready},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncBufRead, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    task::JoinHandle,
+};
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+use crate::codec::{self, Codec, Framing, Json};
+use crate::types::Message;
+use crate::{tls, websocket};
+
+/// How often loops waiting on sockets wake up anyway, to notice a shutdown
+/// request or an expired timeout.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often the two-player server pings each player.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(10);
+/// How long either end waits without hearing anything, pings included,
+/// before giving the connection up for dead. Several pings fit in it, so one
+/// late reply is not enough.
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Network timeouts shared by the client and all servers.
+/// `None` means wait forever.
+#[derive(Debug, Clone, Copy)]
+pub struct Timeouts {
+    /// How long the client waits for the TCP connection to be established.
+    pub connect: Duration,
+    /// How long a peer may stay silent before the connection is considered dead.
+    pub read: Option<Duration>,
+    /// How long a single message may take to be written to the socket.
+    pub write: Duration,
+    /// How long a server waits for its players to connect.
+    pub accept: Option<Duration>,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(10),
+            read: Some(Duration::from_secs(600)),
+            write: Duration::from_secs(10),
+            accept: None,
+        }
+    }
+}
+
+impl Timeouts {
+    /// Returns true once `since` is older than the read timeout.
+    pub fn read_expired(&self, since: Instant) -> bool {
+        self.read.is_some_and(|timeout| since.elapsed() > timeout)
+    }
+
+    /// Returns true once `since` is older than the accept timeout.
+    pub fn accept_expired(&self, since: Instant) -> bool {
+        self.accept.is_some_and(|timeout| since.elapsed() > timeout)
+    }
+}
+
+/// Connects to `addr`, trying each resolved address within the connect timeout.
+/// The read timeout is up to the caller, which knows when it expects data.
+pub async fn connect(addr: &str, timeouts: &Timeouts) -> Result<TcpStream> {
+    let addrs: Vec<_> = tokio::net::lookup_host(addr)
+        .await
+        .with_context(|| format!("could not resolve {}", addr))?
+        .collect();
+
+    let mut last_error = None;
+    for socket_addr in addrs {
+        match tokio::time::timeout(timeouts.connect, TcpStream::connect(socket_addr)).await {
+            Ok(Ok(stream)) => return Ok(stream),
+            Ok(Err(e)) => last_error = Some(e),
+            Err(_) => last_error = Some(io::ErrorKind::TimedOut.into()),
+        }
+    }
+
+    Err(match last_error {
+        Some(e) if e.kind() == io::ErrorKind::TimedOut => anyhow!(
+            "connecting to {} timed out after {}s",
+            addr,
+            timeouts.connect.as_secs()
+        ),
+        Some(e) => anyhow!("could not connect to {}: {}", addr, e),
+        None => anyhow!("{} did not resolve to any address", addr),
+    })
+}
+
+/// How long a TLS or WebSocket handshake may take before the connection is
+/// dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How the client reaches its server, matching the server's `Transport`.
+#[derive(Clone, Default)]
+pub struct ClientTransport {
+    /// Wrap the connection in TLS (`--tls`).
+    pub tls: Option<TlsConnector>,
+    /// Speak WebSocket instead of raw lines (`--websocket`).
+    pub websocket: bool,
+    /// The codec to ask the server for instead of JSON (`--msgpack`).
+    pub codec: Option<&'static dyn Codec>,
+    /// Ask the server to deflate large messages (`--compress`).
+    pub compress: bool,
+}
+
+/// Connects to the server at `addr` like `connect`, completes whatever
+/// handshakes `transport` asks for, and splits the connection.
+pub async fn open(
+    addr: &str,
+    timeouts: &Timeouts,
+    transport: &ClientTransport,
+) -> Result<(FrameReader, TimedWriter)> {
+    let stream = connect(addr, timeouts).await?;
+    let Some(tls) = &transport.tls else {
+        return open_websocket(addr, "ws", stream, timeouts, transport.websocket).await;
+    };
+    let name = tls::server_name(addr)?;
+    let stream = tokio::time::timeout(HANDSHAKE_TIMEOUT, tls.connect(name, stream))
+        .await
+        .map_err(|_| anyhow!("TLS handshake with {} timed out", addr))?
+        .map_err(|e| anyhow!("TLS handshake with {} failed: {}", addr, e))?;
+    open_websocket(addr, "wss", stream, timeouts, transport.websocket).await
+}
+
+/// The last step of `open`: the WebSocket handshake, if `websocket` is set.
+async fn open_websocket<S>(
+    addr: &str,
+    scheme: &str,
+    stream: S,
+    timeouts: &Timeouts,
+    websocket: bool,
+) -> Result<(FrameReader, TimedWriter)>
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    if !websocket {
+        return Ok(split_stream(stream, timeouts));
+    }
+    let url = format!("{}://{}/", scheme, addr);
+    let frames = tokio::time::timeout(HANDSHAKE_TIMEOUT, websocket::connect(&url, stream))
+        .await
+        .map_err(|_| anyhow!("WebSocket handshake with {} timed out", addr))?
+        .map_err(|e| anyhow!("WebSocket handshake with {} failed: {}", addr, e))?;
+    Ok(split_stream(frames, timeouts))
+}
+
+/// What a server's connections are carried over: plain TCP unless told
+/// otherwise, optionally wrapped in TLS, WebSocket framing, or both.
+#[derive(Clone, Default)]
+pub struct Transport {
+    /// TLS with the server's certificate (`--tls-cert`).
+    pub tls: Option<TlsAcceptor>,
+    /// Expect WebSocket clients instead of raw lines (`--websocket`).
+    pub websocket: bool,
+}
+
+impl Transport {
+    /// Takes a freshly accepted connection through whatever handshakes the
+    /// transport needs, and splits it.
+    pub async fn open(
+        &self,
+        stream: TcpStream,
+        timeouts: &Timeouts,
+    ) -> io::Result<(FrameReader, TimedWriter)> {
+        let Some(tls) = &self.tls else {
+            if !self.websocket {
+                return Ok(split(stream, timeouts));
+            }
+            return self.open_websocket(stream, timeouts).await;
+        };
+        let stream = tokio::time::timeout(HANDSHAKE_TIMEOUT, tls.accept(stream))
+            .await
+            .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??;
+        self.open_websocket(stream, timeouts).await
+    }
+
+    async fn open_websocket<S>(
+        &self,
+        stream: S,
+        timeouts: &Timeouts,
+    ) -> io::Result<(FrameReader, TimedWriter)>
+    where
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        if !self.websocket {
+            return Ok(split_stream(stream, timeouts));
+        }
+        let frames = tokio::time::timeout(HANDSHAKE_TIMEOUT, websocket::accept(stream))
+            .await
+            .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??;
+        Ok(split_stream(frames, timeouts))
+    }
+
+    /// Describes the transport for startup banners.
+    pub fn describe(&self) -> &'static str {
+        match (self.tls.is_some(), self.websocket) {
+            (false, false) => "TCP",
+            (true, false) => "TLS",
+            (false, true) => "WebSocket",
+            (true, true) => "secure WebSocket",
+        }
+    }
+}
+
+/// The longest frame, in bytes, either end accepts: a line of JSON or a
+/// MessagePack payload. Real messages are a few kilobytes at most; a peer
+/// that sends more is cut off instead of being buffered without end.
+pub const MAX_FRAME_LEN: usize = 64 * 1024;
+
+/// One message as it arrived, not yet decoded.
+pub struct Frame {
+    codec: &'static dyn Codec,
+    payload: Vec<u8>,
+}
+
+impl Frame {
+    pub fn decode(&self) -> Result<Message> {
+        self.codec.decode(&self.payload)
+    }
+}
+
+/// Incoming side of a connection: one protocol message per frame, each at
+/// most `MAX_FRAME_LEN` bytes, in whichever codec the peer sends.
+pub struct FrameReader {
+    reader: BufReader<Box<dyn AsyncRead + Send + Unpin>>,
+    /// The frame read so far, kept if `next_frame` is cancelled midway.
+    frame: Vec<u8>,
+}
+
+impl FrameReader {
+    fn new(reader: impl AsyncRead + Send + Unpin + 'static) -> Self {
+        Self {
+            reader: BufReader::new(Box::new(reader)),
+            frame: Vec::new(),
+        }
+    }
+
+    /// Reads the next frame, or `None` at EOF. A frame longer than
+    /// `MAX_FRAME_LEN` fails with `InvalidData`. Cancel safe, so it can race
+    /// other futures in `select!`.
+    pub async fn next_frame(&mut self) -> io::Result<Option<Frame>> {
+        std::future::poll_fn(|cx| self.poll_next_frame(cx)).await
+    }
+
+    fn poll_next_frame(&mut self, cx: &mut TaskContext<'_>) -> Poll<io::Result<Option<Frame>>> {
+        loop {
+            let available = ready!(Pin::new(&mut self.reader).poll_fill_buf(cx))?;
+            let Some(&next) = self.frame.first().or(available.first()) else {
+                return Poll::Ready(Ok(None));
+            };
+            let codec = codec::for_frame(next);
+            if available.is_empty() {
+                // A last line without its newline still counts
+                return Poll::Ready(match codec.framing() {
+                    Framing::Line => self.take_line().map(Some),
+                    Framing::LengthPrefixed => Err(io::ErrorKind::UnexpectedEof.into()),
+                });
+            }
+            match codec.framing() {
+                Framing::Line => {
+                    let newline = available.iter().position(|&b| b == b'\n');
+                    let used = newline.map_or(available.len(), |i| i + 1);
+                    self.frame.extend_from_slice(&available[..used]);
+                    Pin::new(&mut self.reader).consume(used);
+                    if newline.is_some() {
+                        return Poll::Ready(self.take_line().map(Some));
+                    }
+                    if self.frame.len() > MAX_FRAME_LEN {
+                        self.frame.clear();
+                        return Poll::Ready(Err(too_long()));
+                    }
+                }
+                Framing::LengthPrefixed => {
+                    let wanted = prefixed_len(&self.frame)?.unwrap_or(codec::LENGTH_PREFIX);
+                    let used = available.len().min(wanted - self.frame.len());
+                    self.frame.extend_from_slice(&available[..used]);
+                    Pin::new(&mut self.reader).consume(used);
+                    if prefixed_len(&self.frame)? == Some(self.frame.len()) {
+                        let payload = self.frame.split_off(codec::LENGTH_PREFIX);
+                        self.frame.clear();
+                        return Poll::Ready(Ok(Some(Frame { codec, payload })));
+                    }
+                }
+            }
+        }
+    }
+
+    fn take_line(&mut self) -> io::Result<Frame> {
+        let mut line = std::mem::take(&mut self.frame);
+        if line.last() == Some(&b'\n') {
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+        }
+        if line.len() > MAX_FRAME_LEN {
+            return Err(too_long());
+        }
+        Ok(Frame {
+            codec: &Json,
+            payload: line,
+        })
+    }
+}
+
+/// The full size of the length-prefixed `frame`, prefix included, once its
+/// prefix has arrived.
+fn prefixed_len(frame: &[u8]) -> io::Result<Option<usize>> {
+    let Some(prefix) = frame.first_chunk::<{ codec::LENGTH_PREFIX }>() else {
+        return Ok(None);
+    };
+    let len = u32::from_be_bytes(*prefix) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(too_long());
+    }
+    Ok(Some(codec::LENGTH_PREFIX + len))
+}
+
+fn too_long() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("frame longer than {} bytes", MAX_FRAME_LEN),
+    )
+}
+
+/// Splits a connection into its incoming frames and a writer for replies.
+pub fn split(stream: TcpStream, timeouts: &Timeouts) -> (FrameReader, TimedWriter) {
+    let (read_half, write_half) = stream.into_split();
+    (
+        FrameReader::new(read_half),
+        TimedWriter::new(write_half, timeouts),
+    )
+}
+
+/// Like `split`, for a connection wrapped in another layer such as TLS or
+/// WebSocket.
+pub fn split_stream<S>(stream: S, timeouts: &Timeouts) -> (FrameReader, TimedWriter)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (read_half, write_half) = tokio::io::split(stream);
+    (
+        FrameReader::new(read_half),
+        TimedWriter::new(write_half, timeouts),
+    )
+}
+
+/// Whether the peer has closed its end of the connection, checked without
+/// waiting. Anything it sent in the meantime stays buffered for `frames`.
+pub fn peer_closed(frames: &mut FrameReader) -> bool {
+    let mut cx = TaskContext::from_waker(Waker::noop());
+    match Pin::new(&mut frames.reader).poll_fill_buf(&mut cx) {
+        Poll::Ready(Ok(buf)) => buf.is_empty(),
+        Poll::Ready(Err(_)) => true,
+        Poll::Pending => false,
+    }
+}
+
+/// Opens a server's listening socket on `addr`. The IPv6 wildcard `[::]`
+/// also takes IPv4 connections where the system allows it.
+pub fn listen(addr: SocketAddr) -> Result<TcpListener> {
+    let bind = || -> io::Result<TcpListener> {
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, Some(Protocol::TCP))?;
+        if addr.is_ipv6() && addr.ip().is_unspecified() {
+            // Not every system allows dual-stack sockets; IPv6 alone will do
+            let _ = socket.set_only_v6(false);
+        }
+        // Restarting the server should not wait out the old connections
+        #[cfg(not(windows))]
+        socket.set_reuse_address(true)?;
+        socket.bind(&addr.into())?;
+        socket.listen(1024)?;
+        socket.set_nonblocking(true)?;
+        TcpListener::from_std(socket.into())
+    };
+    bind().with_context(|| format!("could not listen on {}", addr))
+}
+
+/// Counts a server's open connections against an optional maximum.
+pub struct ConnectionLimit {
+    open: Arc<AtomicUsize>,
+    max: Option<usize>,
+}
+
+impl ConnectionLimit {
+    pub fn new(max: Option<usize>) -> Self {
+        Self {
+            open: Arc::new(AtomicUsize::new(0)),
+            max,
+        }
+    }
+
+    /// Takes a place for a new connection, or returns `None` if the server is full.
+    pub fn admit(&self) -> Option<Slot> {
+        let open = self.open.fetch_add(1, Ordering::SeqCst);
+        if self.max.is_some_and(|max| open >= max) {
+            self.open.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+        Some(Slot(self.open.clone()))
+    }
+}
+
+/// One connection's place under a `ConnectionLimit`, given back when dropped.
+pub struct Slot(Arc<AtomicUsize>);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Messages a second a connection may keep sending before servers treat it
+/// as a flood.
+pub const MESSAGE_RATE: u32 = 20;
+/// Messages a connection may send at once above `MESSAGE_RATE`; enough for a
+/// bot to fire a whole game's shots as fast as the server answers.
+pub const MESSAGE_BURST: u32 = 100;
+
+/// Paces one connection's incoming messages: a bucket of `MESSAGE_BURST`
+/// tokens, refilled at `MESSAGE_RATE` a second, that each message draws from.
+pub struct RateLimit {
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        Self {
+            tokens: f64::from(MESSAGE_BURST),
+            refilled: Instant::now(),
+        }
+    }
+}
+
+impl RateLimit {
+    /// Counts a message, or returns false if the connection is over the limit.
+    pub fn allow(&mut self) -> bool {
+        let now = Instant::now();
+        let earned = now.duration_since(self.refilled).as_secs_f64() * f64::from(MESSAGE_RATE);
+        self.tokens = (self.tokens + earned).min(f64::from(MESSAGE_BURST));
+        self.refilled = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+}
+
+/// Invalid messages a player may send before being warned, throttled and
+/// finally disconnected.
+pub const WARN_AFTER: u32 = 3;
+pub const THROTTLE_AFTER: u32 = 5;
+pub const DISCONNECT_AFTER: u32 = 10;
+/// How long a server stops reading from a throttled player.
+pub const THROTTLE_DELAY: Duration = Duration::from_secs(2);
+
+/// How servers answer a player's invalid messages as they pile up.
+pub enum Penalty {
+    Warn,
+    Throttle,
+    Disconnect,
+}
+
+/// The penalty a player has earned with their `violations`th invalid
+/// message, if any, and the `Conduct` error that tells them so.
+pub fn penalty(violations: u32) -> Option<(Penalty, String)> {
+    if violations >= DISCONNECT_AFTER {
+        Some((
+            Penalty::Disconnect,
+            format!("disconnected after {} invalid messages", violations),
+        ))
+    } else if violations >= THROTTLE_AFTER {
+        Some((
+            Penalty::Throttle,
+            format!(
+                "throttled for {}s: {} invalid messages, disconnect at {}",
+                THROTTLE_DELAY.as_secs(),
+                violations,
+                DISCONNECT_AFTER
+            ),
+        ))
+    } else if violations == WARN_AFTER {
+        Some((
+            Penalty::Warn,
+            format!(
+                "warning: {} invalid messages; more will slow down and then end your connection",
+                violations
+            ),
+        ))
+    } else {
+        None
+    }
+}
+
+/// Write half of a connection. Writes are queued and sent by a background
+/// task, so they never block the caller; a write that stalls past the write
+/// timeout closes the connection, after which further writes fail.
+pub struct TimedWriter {
+    queue: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    task: JoinHandle<()>,
+    /// The connection's place under the server's limit, held while the writer lives.
+    slot: Option<Slot>,
+    /// How `send` encodes messages; JSON until the peer agrees to another.
+    codec: &'static dyn Codec,
+    /// Deflate large frames, once the peer has agreed to it.
+    deflate: bool,
+}
+
+impl TimedWriter {
+    pub fn new(mut stream: impl AsyncWrite + Send + Unpin + 'static, timeouts: &Timeouts) -> Self {
+        let (queue, mut pending) = mpsc::unbounded_channel::<Vec<u8>>();
+        let timeout = timeouts.write;
+        let task = tokio::spawn(async move {
+            while let Some(bytes) = pending.recv().await {
+                let write = async {
+                    stream.write_all(&bytes).await?;
+                    stream.flush().await
+                };
+                match tokio::time::timeout(timeout, write).await {
+                    Ok(Ok(())) => {}
+                    _ => break,
+                }
+            }
+            let _ = stream.shutdown().await;
+        });
+        Self {
+            queue: Some(queue),
+            task,
+            slot: None,
+            codec: &Json,
+            deflate: false,
+        }
+    }
+
+    /// Queues `msg`, encoded with the connection's codec.
+    pub fn send(&mut self, msg: &Message) -> io::Result<()> {
+        let mut frame = self.codec.encode(msg).map_err(io::Error::other)?;
+        if self.deflate {
+            frame = codec::deflate(frame);
+        }
+        self.write_all(&frame)
+    }
+
+    /// Encodes later messages with `codec`, once the peer has agreed to it.
+    pub fn set_codec(&mut self, codec: &'static dyn Codec) {
+        self.codec = codec;
+    }
+
+    /// Deflates later large messages, once the peer has agreed to it.
+    pub fn set_deflate(&mut self) {
+        self.deflate = true;
+    }
+
+    /// Counts this connection under a `ConnectionLimit` until the writer is dropped.
+    pub fn hold(&mut self, slot: Slot) {
+        self.slot = Some(slot);
+    }
+
+    /// Sends whatever is still queued, then closes our side of the connection
+    /// so the peer sees EOF.
+    pub fn shutdown(&mut self) {
+        self.queue = None;
+    }
+
+    /// Like `shutdown`, but waits until the queue has been sent (or the write
+    /// timed out). Call this before exiting so the last messages are not lost.
+    pub async fn close(mut self) {
+        self.shutdown();
+        let _ = self.task.await;
+    }
+}
+
+impl Write for TimedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let sent = self
+            .queue
+            .as_ref()
+            .is_some_and(|queue| queue.send(buf.to_vec()).is_ok());
+        if sent {
+            Ok(buf.len())
+        } else {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use tokio::io::ReadBuf;
+
+    /// A peer that sends its bytes in the given pieces, one per read, the way
+    /// a frame split across TCP segments arrives.
+    struct Pieces(VecDeque<Vec<u8>>);
+
+    impl AsyncRead for Pieces {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut TaskContext<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            if let Some(mut piece) = self.0.pop_front() {
+                if piece.len() > buf.remaining() {
+                    let rest = piece.split_off(buf.remaining());
+                    self.0.push_front(rest);
+                }
+                buf.put_slice(&piece);
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn reader(pieces: &[&[u8]]) -> FrameReader {
+        FrameReader::new(Pieces(pieces.iter().map(|p| p.to_vec()).collect()))
+    }
+
+    /// `payload` as a MessagePack frame, behind its length prefix.
+    fn prefixed(payload: &[u8]) -> Vec<u8> {
+        let mut frame = (payload.len() as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(payload);
+        frame
+    }
+
+    #[tokio::test]
+    async fn lines_arrive_whole_however_they_are_split() {
+        let mut frames = reader(&[b"{\"a\":", b"1}\r", b"\n{\"b\"", b":2}"]);
+        let first = frames.next_frame().await.unwrap().unwrap();
+        assert_eq!(first.payload, b"{\"a\":1}");
+        // The last line counts even without its newline
+        let second = frames.next_frame().await.unwrap().unwrap();
+        assert_eq!(second.payload, b"{\"b\":2}");
+        assert!(frames.next_frame().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn length_prefixed_frames_arrive_whole_however_they_are_split() {
+        let frame = prefixed(b"\x81\xa1a\x01");
+        let mut frames = reader(&[&frame[..2], &frame[2..5], &frame[5..]]);
+        let read = frames.next_frame().await.unwrap().unwrap();
+        assert_eq!(read.payload, b"\x81\xa1a\x01");
+        assert!(frames.next_frame().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn a_line_over_the_limit_is_refused() {
+        let piece = [b'x'; 4096];
+        let pieces = vec![&piece[..]; MAX_FRAME_LEN / piece.len() + 1];
+        let err = reader(&pieces).next_frame().await.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn a_line_at_the_limit_is_accepted() {
+        let mut line = vec![b'x'; MAX_FRAME_LEN];
+        line.push(b'\n');
+        let frame = reader(&[&line]).next_frame().await.unwrap().unwrap();
+        assert_eq!(frame.payload.len(), MAX_FRAME_LEN);
+    }
+
+    #[tokio::test]
+    async fn a_length_prefix_over_the_limit_is_refused() {
+        let prefix = (MAX_FRAME_LEN as u32 + 1).to_be_bytes();
+        // Refused on the prefix alone, without waiting for the payload
+        let err = reader(&[&prefix]).next_frame().await.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn a_length_prefixed_frame_cut_short_is_an_error() {
+        let frame = prefixed(b"\x81\xa1a\x01");
+        let err = reader(&[&frame[..6]]).next_frame().await.err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    /// How many messages `limit` lets through in a row.
+    fn allowed(limit: &mut RateLimit) -> u32 {
+        let mut count = 0;
+        while limit.allow() {
+            count += 1;
+        }
+        count
+    }
+
+    #[test]
+    fn the_rate_limit_allows_a_burst_then_refuses() {
+        let mut limit = RateLimit::default();
+        assert_eq!(allowed(&mut limit), MESSAGE_BURST);
+        assert!(!limit.allow());
+    }
+
+    #[test]
+    fn the_rate_limit_refills_at_the_message_rate() {
+        let mut limit = RateLimit::default();
+        allowed(&mut limit);
+        limit.refilled -= Duration::from_secs(1);
+        assert_eq!(allowed(&mut limit), MESSAGE_RATE);
+    }
+
+    #[test]
+    fn the_rate_limit_refills_no_further_than_the_burst() {
+        let mut limit = RateLimit::default();
+        allowed(&mut limit);
+        limit.refilled -= Duration::from_secs(3600);
+        assert_eq!(allowed(&mut limit), MESSAGE_BURST);
+    }
+}
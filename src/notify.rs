@@ -0,0 +1,121 @@
+use crossterm::{execute, terminal::SetTitle};
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+    time::{Duration, Instant},
+};
+
+use crate::settings::{Alerts, Notifications};
+
+const WINDOW_TITLE: &str = "Battleship";
+const TITLE_FLASH: Duration = Duration::from_secs(3);
+
+/// Game events the player can choose to be alerted about.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotifyEvent {
+    TurnStart,
+    HitReceived,
+    Sink,
+    Chat,
+    GameOver,
+}
+
+impl NotifyEvent {
+    pub const ALL: [NotifyEvent; 5] = [
+        NotifyEvent::TurnStart,
+        NotifyEvent::HitReceived,
+        NotifyEvent::Sink,
+        NotifyEvent::Chat,
+        NotifyEvent::GameOver,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NotifyEvent::TurnStart => "Your turn",
+            NotifyEvent::HitReceived => "Ship hit",
+            NotifyEvent::Sink => "Ship sunk",
+            NotifyEvent::Chat => "Message from opponent",
+            NotifyEvent::GameOver => "Game over",
+        }
+    }
+
+    pub fn alerts(self, notifications: &Notifications) -> Alerts {
+        match self {
+            NotifyEvent::TurnStart => notifications.turn_start,
+            NotifyEvent::HitReceived => notifications.hit_received,
+            NotifyEvent::Sink => notifications.sink,
+            NotifyEvent::Chat => notifications.chat,
+            NotifyEvent::GameOver => notifications.game_over,
+        }
+    }
+}
+
+/// Delivers alerts to the terminal and desktop, and restores the window title
+/// after a title flash.
+#[derive(Default)]
+pub struct Notifier {
+    title_reset_at: Option<Instant>,
+}
+
+impl Notifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fire(
+        &mut self,
+        event: NotifyEvent,
+        notifications: &Notifications,
+        out: &mut impl Write,
+    ) -> io::Result<()> {
+        let alerts = event.alerts(notifications);
+        if alerts.bell {
+            out.write_all(b"\x07")?;
+            out.flush()?;
+        }
+        if alerts.title {
+            execute!(
+                out,
+                SetTitle(format!("* {} - {}", event.label(), WINDOW_TITLE))
+            )?;
+            self.title_reset_at = Some(Instant::now() + TITLE_FLASH);
+        }
+        if alerts.desktop {
+            desktop_notification(event.label());
+        }
+        Ok(())
+    }
+
+    /// Restores the window title once a flash has run its course.
+    pub fn tick(&mut self, out: &mut impl Write) -> io::Result<()> {
+        if self
+            .title_reset_at
+            .is_some_and(|reset_at| Instant::now() >= reset_at)
+        {
+            self.title_reset_at = None;
+            execute!(out, SetTitle(WINDOW_TITLE))?;
+        }
+        Ok(())
+    }
+}
+
+/// Best effort: a missing notification tool is not worth interrupting the game for.
+fn desktop_notification(body: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification \"{}\" with title \"{}\"",
+            body, WINDOW_TITLE
+        ));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.arg(WINDOW_TITLE).arg(body);
+        command
+    };
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    std::thread::spawn(move || command.status());
+}
@@ -0,0 +1,100 @@
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+/// How often the server logs its loop statistics when `--perf` is on.
+pub const SERVER_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Turns on performance reporting for the whole process.
+pub fn enable() {
+    let _ = ENABLED.set(true);
+}
+
+pub fn enabled() -> bool {
+    ENABLED.get().copied().unwrap_or(false)
+}
+
+/// One-line summary for the server log.
+pub fn describe(rates: &Rates) -> String {
+    format!(
+        "[perf] loop {:.0}/s, messages {:.1}/s, iteration {:.2}ms avg / {:.2}ms max",
+        rates.iterations_per_sec, rates.messages_per_sec, rates.avg_work_ms, rates.max_work_ms
+    )
+}
+
+/// Rates measured over one reporting window.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Rates {
+    pub iterations_per_sec: f64,
+    pub messages_per_sec: f64,
+    /// Mean time spent in the timed section (e.g. drawing a frame).
+    pub avg_work_ms: f64,
+    pub max_work_ms: f64,
+}
+
+/// Counts loop iterations, messages and time spent working, and turns them
+/// into per-second rates once per window.
+pub struct LoopStats {
+    window: Duration,
+    window_start: Instant,
+    iterations: u32,
+    messages: u32,
+    work: Duration,
+    max_work: Duration,
+    pub latest: Rates,
+}
+
+impl LoopStats {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            window_start: Instant::now(),
+            iterations: 0,
+            messages: 0,
+            work: Duration::ZERO,
+            max_work: Duration::ZERO,
+            latest: Rates::default(),
+        }
+    }
+
+    pub fn iteration(&mut self) {
+        self.iterations += 1;
+    }
+
+    pub fn message(&mut self) {
+        self.messages += 1;
+    }
+
+    pub fn work(&mut self, took: Duration) {
+        self.work += took;
+        self.max_work = self.max_work.max(took);
+    }
+
+    /// Closes the window once it has run its length. Returns the new rates if it did.
+    pub fn roll(&mut self) -> Option<Rates> {
+        let elapsed = self.window_start.elapsed();
+        if elapsed < self.window {
+            return None;
+        }
+        let secs = elapsed.as_secs_f64();
+        self.latest = Rates {
+            iterations_per_sec: self.iterations as f64 / secs,
+            messages_per_sec: self.messages as f64 / secs,
+            avg_work_ms: if self.iterations == 0 {
+                0.0
+            } else {
+                self.work.as_secs_f64() * 1000.0 / self.iterations as f64
+            },
+            max_work_ms: self.max_work.as_secs_f64() * 1000.0,
+        };
+        self.window_start = Instant::now();
+        self.iterations = 0;
+        self.messages = 0;
+        self.work = Duration::ZERO;
+        self.max_work = Duration::ZERO;
+        Some(self.latest)
+    }
+}
@@ -0,0 +1,56 @@
+//! Practice mode against the AI: on their turn the player may ask for a hint,
+//! the cell the probability-density AI would fire at next from what the
+//! player knows of the enemy board, a limited number of times a game.
+
+use crate::ai::{AiStrategy, Heatmap};
+use crate::ai_audit::AiView;
+
+/// The player's hints and the heatmap that picks them, fed the result of
+/// each of the player's shots.
+pub struct Practice {
+    pub hints_per_game: usize,
+    pub hints_left: usize,
+    /// The cell last hinted at, until the player fires.
+    pub hint: Option<(usize, usize)>,
+    heatmap: Heatmap,
+    view: AiView,
+}
+
+impl Practice {
+    pub fn new(hints_per_game: usize) -> Self {
+        Self {
+            hints_per_game,
+            hints_left: hints_per_game,
+            hint: None,
+            heatmap: Heatmap::default(),
+            view: AiView::new(),
+        }
+    }
+
+    /// Starts over for a new game with a full set of hints.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.hints_per_game);
+    }
+
+    /// Learns how the player's shot at (x, y) went. `sunk` is the length of
+    /// the ship it sank, if it sank one.
+    pub fn record_shot(&mut self, x: usize, y: usize, hit: bool, sunk: Option<usize>) {
+        self.hint = None;
+        self.view.record_shot(x, y, hit);
+        let _ = self.heatmap.record_shot(x, y, hit, sunk);
+    }
+
+    /// Spends a hint on the likeliest cell, or `None` if none are left.
+    pub fn take_hint(&mut self) -> Option<(usize, usize)> {
+        if let Some(cell) = self.hint {
+            return Some(cell);
+        }
+        self.hints_left = self.hints_left.checked_sub(1)?;
+        let cell = self
+            .heatmap
+            .choose_shot(&self.view, &mut rand::rng())
+            .ok()?;
+        self.hint = Some(cell);
+        Some(cell)
+    }
+}
@@ -1,26 +1,883 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
+use rand::Rng;
 use std::{
-    io::{BufRead, BufReader, Write},
-    net::{TcpListener, TcpStream},
+    collections::{HashMap, VecDeque},
+    io::{self, Write},
+    net::SocketAddr,
+    str::FromStr,
     sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, oneshot, watch},
+    task::{JoinHandle, JoinSet},
+};
 
-use crate::game_state::GameState;
-use crate::types::{CellState, Message};
+use crate::backfill::{self, Backfill, Placement};
+use crate::chat;
+use crate::codec;
+use crate::coords;
+use crate::dashboard;
+use crate::directory;
+use crate::engine::{Board, Shot};
+use crate::net::{
+    self, Frame, FrameReader, Penalty, RateLimit, Slot, TimedWriter, Timeouts, Transport,
+};
+use crate::perf::{self, LoopStats};
+use crate::types::{
+    CellState, ErrorCode, GRID_SIZE, GameEvent, MAX_HINT_LEN, Message, PROTOCOL_VERSION,
+    ReviewShot, SeaEvent, SunkShip,
+};
+use crate::weather::{self, EventGenerator, Weather};
+use crate::webhook;
+
+/// A player's outgoing stream. Everything sent on it is also kept in a
+/// transcript, so coaches (even late ones) see exactly what the player saw.
+/// The transcript is JSON whatever codec the player uses.
+struct PlayerStream {
+    writer: TimedWriter,
+    transcript: Vec<u8>,
+}
+
+impl PlayerStream {
+    fn send(&mut self, msg: &Message) -> Result<()> {
+        self.record(msg)?;
+        let _ = self.writer.send(msg);
+        Ok(())
+    }
+
+    /// Adds `msg` to the transcript without sending it to the player.
+    fn record(&mut self, msg: &Message) -> Result<()> {
+        let json = serde_json::to_string(msg)?;
+        self.transcript.extend_from_slice(json.as_bytes());
+        self.transcript.push(b'\n');
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        self.writer.shutdown();
+    }
+}
 
 struct PlayerConnection {
-    stream: TcpStream,
-    grid: Option<Vec<Vec<CellState>>>,
+    stream: PlayerStream,
+    /// Numbers the connection in this seat; input from an earlier one is ignored.
+    connection: u64,
+    reader: JoinHandle<()>,
+    /// Paces the reader task: touched once each line has been handled, and
+    /// set to when a throttled player may be heard again.
+    throttle: watch::Sender<Option<Instant>>,
+    grid: Option<Board>,
     ready: bool,
+    conduct: Conduct,
+    rate: RateLimit,
+    /// Fog hides the result of this player's next shot until their next turn.
+    fogged: bool,
+    held_result: Option<Message>,
+    /// When the connection last sent a line; pongs keep an idle player fresh.
+    last_heard: Instant,
+    last_ping: Instant,
+    /// When the `Ping` still waiting for its `Pong` went out.
+    ping_sent: Option<Instant>,
+    /// The display name the player sent in `Hello`, if any.
+    name: Option<String>,
+    /// The name, or "Player 1" / "Player 2", for the log.
+    label: String,
+}
+
+/// Protocol violations (malformed JSON, messages sent in the wrong phase)
+/// counted against one connection.
+#[derive(Default)]
+struct Conduct {
+    violations: u32,
+    /// Why the connection is to be dropped, once it is.
+    kicked: Option<&'static str>,
+}
+
+/// What a player's reader task saw on their connection.
+enum Input {
+    Frame(Frame),
+    Closed,
+    Failed,
+    /// Nothing arrived within the read timeout.
+    Silent(Duration),
+}
+
+/// An `Input` from connection number `connection` in `player`'s seat.
+struct PlayerInput {
+    player: usize,
+    connection: u64,
+    input: Input,
+}
+
+/// Reads one player's frames and hands them to the session one at a time,
+/// pausing while the player is throttled. Stops after reporting EOF, an
+/// error or a silence longer than the read timeout.
+async fn read_player(
+    player: usize,
+    connection: u64,
+    mut frames: FrameReader,
+    mut pending: Option<Frame>,
+    mut throttle: watch::Receiver<Option<Instant>>,
+    read_timeout: Option<Duration>,
+    inputs: mpsc::UnboundedSender<PlayerInput>,
+) {
+    loop {
+        let throttled_until = *throttle.borrow_and_update();
+        if let Some(until) = throttled_until {
+            tokio::time::sleep_until(until.into()).await;
+        }
+        let next = match (pending.take(), read_timeout) {
+            (Some(frame), _) => Ok(Ok(Some(frame))),
+            (None, Some(timeout)) => tokio::time::timeout(timeout, frames.next_frame()).await,
+            (None, None) => Ok(frames.next_frame().await),
+        };
+        let input = match next {
+            Ok(Ok(Some(frame))) => Input::Frame(frame),
+            Ok(Ok(None)) => Input::Closed,
+            Ok(Err(_)) => Input::Failed,
+            Err(_) => Input::Silent(read_timeout.unwrap_or_default()),
+        };
+        let last = !matches!(input, Input::Frame(_));
+        let sent = inputs.send(PlayerInput {
+            player,
+            connection,
+            input,
+        });
+        if last || sent.is_err() {
+            return;
+        }
+        // Wait for the session to handle the frame, so a throttle it earns
+        // applies to the very next one
+        if throttle.changed().await.is_err() {
+            return;
+        }
+    }
+}
+
+impl PlayerConnection {
+    /// Seats `newcomer` as `player` and starts reading from it.
+    fn new(
+        player: usize,
+        connection: u64,
+        newcomer: Newcomer,
+        read_timeout: Option<Duration>,
+        inputs: &mpsc::UnboundedSender<PlayerInput>,
+    ) -> Self {
+        let Newcomer {
+            frames,
+            pending,
+            writer,
+            name,
+            ..
+        } = newcomer;
+        let (throttle, throttled) = watch::channel(None);
+        let reader = tokio::spawn(read_player(
+            player,
+            connection,
+            frames,
+            pending,
+            throttled,
+            read_timeout,
+            inputs.clone(),
+        ));
+        Self {
+            stream: PlayerStream {
+                writer,
+                transcript: Vec::new(),
+            },
+            connection,
+            reader,
+            throttle,
+            grid: None,
+            ready: false,
+            conduct: Conduct::default(),
+            rate: RateLimit::default(),
+            fogged: false,
+            held_result: None,
+            last_heard: Instant::now(),
+            last_ping: Instant::now(),
+            ping_sent: None,
+            label: name.clone().unwrap_or_else(|| format!("Player {}", player)),
+            name,
+        }
+    }
+
+    /// Moves `fresh`'s connection into this seat, keeping the seat's board and
+    /// transcript.
+    fn reseat(&mut self, fresh: PlayerConnection) {
+        self.disconnect();
+        self.stream.writer = fresh.stream.writer;
+        self.connection = fresh.connection;
+        self.reader = fresh.reader;
+        self.throttle = fresh.throttle;
+        self.conduct = Conduct::default();
+        self.rate = fresh.rate;
+        self.last_heard = fresh.last_heard;
+        self.last_ping = fresh.last_ping;
+        self.ping_sent = None;
+        self.name = fresh.name;
+        self.label = fresh.label;
+    }
+
+    /// Lets the reader task go on to the next line.
+    fn handled(&self) {
+        self.throttle.send_modify(|_| ());
+    }
+
+    /// Stops reading and closes the connection once queued messages are sent.
+    fn disconnect(&mut self) {
+        self.reader.abort();
+        self.stream.shutdown();
+    }
+
+    fn send(&mut self, msg: &Message) -> Result<()> {
+        self.stream.send(msg)
+    }
+
+    /// Sends `msg` to the player alone, leaving it out of the transcript
+    /// their coaches are shown.
+    fn send_private(&mut self, msg: &Message) -> Result<()> {
+        let _ = self.stream.writer.send(msg);
+        Ok(())
+    }
+
+    fn send_error(&mut self, code: ErrorCode, detail: String) -> Result<()> {
+        self.send(&Message::Error { code, detail })
+    }
+
+    /// Tells `player` why their message was rejected and escalates the penalty
+    /// as violations pile up.
+    fn reject(&mut self, player: usize, (code, detail): Rejection) -> Result<()> {
+        dashboard::log(format!("Rejected message from {}: {}", self.label, detail));
+        self.send_error(code, detail)?;
+
+        self.conduct.violations += 1;
+        let violations = self.conduct.violations;
+        dashboard::set_violations(player, violations);
+        let Some((penalty, detail)) = net::penalty(violations) else {
+            return Ok(());
+        };
+        match penalty {
+            Penalty::Warn => {}
+            Penalty::Throttle => {
+                self.throttle
+                    .send_replace(Some(Instant::now() + net::THROTTLE_DELAY));
+                dashboard::log(format!(
+                    "Throttling {} for {}s after {} invalid messages",
+                    self.label,
+                    net::THROTTLE_DELAY.as_secs(),
+                    violations
+                ));
+            }
+            Penalty::Disconnect => self.conduct.kicked = Some("repeated invalid messages"),
+        }
+        self.send_error(ErrorCode::Conduct, detail)
+    }
+}
+
+/// How long a new connection has after its `Hello` to ask to coach, observe
+/// or use a room before it is settled as a player.
+const ROLE_GRACE: Duration = Duration::from_millis(300);
+
+/// What a new connection turned out to be once it spoke, or stayed silent
+/// after its `Hello` for `ROLE_GRACE`.
+enum Role {
+    /// Said `Hello` and nothing more: wants to play whoever is next.
+    Player,
+    /// Asked for a private room.
+    Host,
+    /// Asked to join the room with this code.
+    Guest(String),
+    /// Asked for the seat this session token was issued for.
+    Resume(String),
+    /// Asked to watch the events of the game with this number or room
+    /// code, or the newest game.
+    Observer(Option<String>),
+    /// Asked to coach the player this coach code was issued to.
+    Coach(String),
+    Incompatible(String),
+    /// Did not give the server's password.
+    Unauthorized(&'static str),
+    Gone,
+}
+
+/// A new connection with its role worked out.
+struct Newcomer {
+    role: Role,
+    frames: FrameReader,
+    /// A frame read while working out the role that belongs to the game.
+    pending: Option<Frame>,
+    writer: TimedWriter,
+    addr: String,
+    /// The display name sent in `Hello`, cleaned up.
+    name: Option<String>,
+    /// When the connection's role was settled, e.g. when it joined the queue.
+    arrived: Instant,
+}
+
+/// What the server asks of every new connection, and tells it once greeted.
+#[derive(Clone)]
+struct Welcome {
+    /// Only a `Hello` carrying this lets the connection in.
+    password: Option<Arc<str>>,
+    /// Announced in a `Minefield` after the `Hello`, if not 0.
+    mines: usize,
+    /// Announced with a `RevealSunk` after the `Hello`.
+    reveal_sunk: bool,
+}
+
+/// Waits for a new connection to say what it wants, answering its `Hello`,
+/// then hands it back through `done`. The first frame must be the `Hello`;
+/// anything else is refused as incompatible. On a server with a `password`,
+/// only a `Hello` carrying it lets the connection in. Under `--mines` the answer is
+/// followed by a `Minefield`, and under `--reveal-sunk` by a `RevealSunk`, so
+/// the client knows before placing its fleet.
+async fn introduce(
+    stream: TcpStream,
+    addr: String,
+    slot: Slot,
+    transport: Transport,
+    timeouts: Timeouts,
+    Welcome {
+        password,
+        mines,
+        reveal_sunk,
+    }: Welcome,
+    done: mpsc::UnboundedSender<Newcomer>,
+) {
+    let (mut frames, mut writer) = match transport.open(stream, &timeouts).await {
+        Ok(halves) => halves,
+        Err(e) => {
+            dashboard::log(format!("Dropped {}: handshake failed: {}", addr, e));
+            return;
+        }
+    };
+    writer.hold(slot);
+    let mut pending = None;
+    let mut name = None;
+    let role = 'role: {
+        // However long it takes, the first frame has to be the Hello
+        let first = match timeouts.read {
+            Some(limit) => tokio::time::timeout(limit, frames.next_frame())
+                .await
+                .unwrap_or_else(|_| Err(io::ErrorKind::TimedOut.into())),
+            None => frames.next_frame().await,
+        };
+        let hello = match first {
+            Ok(Some(frame)) => frame.decode(),
+            Ok(None) => break 'role Role::Gone,
+            Err(e) => {
+                dashboard::log(format!("Dropped {}: {}", addr, e));
+                break 'role Role::Gone;
+            }
+        };
+        let hello = match hello {
+            Ok(msg) if matches!(msg, Message::Hello { .. }) => msg,
+            _ => {
+                break 'role Role::Incompatible(format!(
+                    "expected Hello (protocol version {}) first",
+                    PROTOCOL_VERSION
+                ));
+            }
+        };
+        if let Err(detail) = hello.check_hello() {
+            break 'role Role::Incompatible(detail);
+        }
+        if let Err(reason) = hello.check_password(password.as_deref()) {
+            break 'role Role::Unauthorized(reason);
+        }
+        let mut agreed = None;
+        let mut deflate = false;
+        if let Message::Hello {
+            player_name,
+            codec,
+            compression,
+            ..
+        } = &hello
+        {
+            name = player_name.as_deref().and_then(chat::clean_name);
+            agreed = codec.as_deref().and_then(codec::named);
+            deflate = compression.as_deref() == Some(codec::DEFLATE);
+        }
+        // The answer goes out in JSON; only later messages switch
+        let _ = writer.send(&Message::hello(
+            None,
+            agreed.map(|c| c.name()),
+            deflate.then_some(codec::DEFLATE),
+            None,
+        ));
+        if let Some(codec) = agreed {
+            writer.set_codec(codec);
+        }
+        if deflate {
+            writer.set_deflate();
+        }
+        if mines > 0 {
+            let _ = writer.send(&Message::Minefield { mines });
+        }
+        if reveal_sunk {
+            let _ = writer.send(&Message::RevealSunk);
+        }
+        match tokio::time::timeout(ROLE_GRACE, frames.next_frame()).await {
+            Err(_) => Role::Player,
+            Ok(Ok(Some(frame))) => match frame.decode() {
+                Ok(Message::Coach { code }) => Role::Coach(code.trim().to_uppercase()),
+                Ok(Message::Observe { game }) => Role::Observer(game),
+                Ok(Message::CreateRoom) => Role::Host,
+                Ok(Message::ResumeGame { token }) => Role::Resume(token),
+                Ok(Message::JoinRoom { code }) => Role::Guest(code.trim().to_uppercase()),
+                _ => {
+                    pending = Some(frame);
+                    Role::Player
+                }
+            },
+            Ok(Err(e)) => {
+                dashboard::log(format!("Dropped {}: {}", addr, e));
+                Role::Gone
+            }
+            Ok(Ok(None)) => Role::Gone,
+        }
+    };
+    let _ = done.send(Newcomer {
+        role,
+        frames,
+        pending,
+        writer,
+        addr,
+        name,
+        arrived: Instant::now(),
+    });
+}
+
+/// A third connection attached to one player: it is sent everything that
+/// player is sent and may whisper hints to them, but cannot act.
+struct Coach {
+    player: usize,
+    stream: TimedWriter,
+    /// Hints read from the coach; closed once the coach disconnects.
+    hints: mpsc::UnboundedReceiver<String>,
+    addr: String,
+    /// How much of the player's transcript has been forwarded.
+    sent: usize,
+}
+
+/// A read-only connection following the game's events.
+struct Observer {
+    stream: TimedWriter,
+    /// Closed once the observer disconnects.
+    gone: oneshot::Receiver<()>,
+    addr: String,
+}
+
+/// Connections beyond the two players. Observers are read-only and receive
+/// every engine event of the running game; late joiners are sent the full
+/// history first so the stream is loss-less. Coaches follow one player.
+#[derive(Default)]
+struct Observers {
+    streams: Vec<Observer>,
+    coaches: Vec<Coach>,
+    history: Vec<GameEvent>,
+}
+
+impl Observers {
+    /// Newcomers that asked to observe become observers. Anyone else has no
+    /// place in a running game.
+    fn settle(&mut self, newcomer: Newcomer) {
+        match newcomer.role {
+            Role::Observer(_) => self.add_observer(newcomer),
+            _ => refuse(
+                newcomer.writer,
+                &newcomer.addr,
+                ErrorCode::WrongPhase,
+                "this game has no seat free",
+            ),
+        }
+    }
+
+    fn add_observer(&mut self, newcomer: Newcomer) {
+        let Newcomer {
+            mut writer,
+            mut frames,
+            addr,
+            ..
+        } = newcomer;
+        dashboard::log(format!("Observer connected: {}", addr));
+        let delivered = self.history.iter().all(|event| {
+            serde_json::to_string(event)
+                .map(|json| writeln!(writer, "{}", json).is_ok())
+                .unwrap_or(false)
+        });
+        if !delivered {
+            return;
+        }
+        dashboard::observer_connected();
+        let (left, gone) = oneshot::channel();
+        let reader_addr = addr.clone();
+        tokio::spawn(async move {
+            // Observers have nothing to say; reading notices them leave
+            let mut rate = RateLimit::default();
+            while let Ok(Some(_)) = frames.next_frame().await {
+                if !rate.allow() {
+                    dashboard::log(format!(
+                        "Dropping observer {}: too many messages",
+                        reader_addr
+                    ));
+                    break;
+                }
+            }
+            drop(left);
+        });
+        self.streams.push(Observer {
+            stream: writer,
+            gone,
+            addr,
+        });
+    }
+
+    /// Lets go of observers whose connections have closed.
+    fn drop_departed(&mut self) {
+        self.streams.retain_mut(|observer| {
+            let here = matches!(
+                observer.gone.try_recv(),
+                Err(oneshot::error::TryRecvError::Empty)
+            );
+            if !here {
+                dashboard::log(format!("Observer {} disconnected", observer.addr));
+                dashboard::observer_disconnected();
+            }
+            here
+        });
+    }
+
+    fn add_coach(&mut self, newcomer: Newcomer, player: usize) {
+        dashboard::log(format!(
+            "Coach for Player {} connected: {}",
+            player, newcomer.addr
+        ));
+        let (forward, hints) = mpsc::unbounded_channel();
+        let mut frames = newcomer.frames;
+        let addr = newcomer.addr.clone();
+        tokio::spawn(async move {
+            let mut rate = RateLimit::default();
+            while let Ok(Some(frame)) = frames.next_frame().await {
+                if !rate.allow() {
+                    dashboard::log(format!("Dropping coach {}: too many messages", addr));
+                    break;
+                }
+                if let Ok(Message::Hint { text }) = frame.decode() {
+                    let text: String = text.chars().take(MAX_HINT_LEN).collect();
+                    if forward.send(text).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        self.coaches.push(Coach {
+            player,
+            stream: newcomer.writer,
+            hints,
+            addr: newcomer.addr,
+            sent: 0,
+        });
+    }
+
+    /// Forwards each player's new messages to their coaches and collects the
+    /// hints coaches sent, as `(player, text)`.
+    fn coach(&mut self, transcripts: [&[u8]; 2]) -> Vec<(usize, String)> {
+        let mut hints = Vec::new();
+        self.coaches.retain_mut(|coach| {
+            let transcript = transcripts[coach.player - 1];
+            let pending = &transcript[coach.sent.min(transcript.len())..];
+            if !pending.is_empty() {
+                if coach.stream.write_all(pending).is_err() {
+                    dashboard::log(format!("Coach {} dropped", coach.addr));
+                    return false;
+                }
+                coach.sent = transcript.len();
+            }
+
+            loop {
+                match coach.hints.try_recv() {
+                    Ok(text) => hints.push((coach.player, text)),
+                    Err(mpsc::error::TryRecvError::Empty) => return true,
+                    Err(mpsc::error::TryRecvError::Disconnected) => {
+                        dashboard::log(format!("Coach {} disconnected", coach.addr));
+                        return false;
+                    }
+                }
+            }
+        });
+        hints
+    }
+
+    fn emit(&mut self, event: GameEvent) {
+        if let Ok(json) = serde_json::to_string(&event) {
+            // A write fails once the observer's connection has gone
+            self.streams.retain_mut(|observer| {
+                let delivered = writeln!(observer.stream, "{}", json).is_ok();
+                if !delivered {
+                    dashboard::log(format!("Observer {} dropped", observer.addr));
+                    dashboard::observer_disconnected();
+                }
+                delivered
+            });
+        }
+        self.history.push(event);
+    }
+
+    /// Sends what is still queued for observers and coaches, then disconnects them.
+    async fn close(self) {
+        for _ in &self.streams {
+            dashboard::observer_disconnected();
+        }
+        let writers = self
+            .streams
+            .into_iter()
+            .map(|observer| observer.stream)
+            .chain(self.coaches.into_iter().map(|coach| coach.stream));
+        for writer in writers {
+            writer.close().await;
+        }
+    }
+}
+
+/// Turns a connection away with an `Error` explaining why, and counts it.
+fn refuse(mut stream: TimedWriter, addr: &str, code: ErrorCode, reason: &str) {
+    let _ = stream.send(&Message::Error {
+        code,
+        detail: reason.to_string(),
+    });
+    stream.shutdown();
+    dashboard::connection_refused();
+    dashboard::log(format!("Refused {}: {}", addr, reason));
+}
+
+/// How long a survivor may wait for a dropped opponent to come back.
+const RECONNECT_WINDOW: Duration = Duration::from_secs(60);
+
+/// A seat whose player dropped mid-game while the survivor decides
+/// whether to claim the win or wait for a reconnect.
+struct VacantSeat {
+    player: usize,
+    /// When the reconnect window opened, or was opened again by `WaitForReconnect`.
+    since: Instant,
+    /// When the player dropped. The turn clock does not run while the seat is empty.
+    vacated: Instant,
+}
+
+/// A battle on hold until either player carries on.
+struct Pause {
+    /// Who asked for it.
+    player: usize,
+    since: Instant,
+}
+
+/// `player`'s connection and their opponent's.
+fn pair(
+    players: &mut [PlayerConnection; 2],
+    player: usize,
+) -> (&mut PlayerConnection, &mut PlayerConnection) {
+    let [p1, p2] = players;
+    if player == 1 { (p1, p2) } else { (p2, p1) }
+}
+
+/// Applies a stormy-seas event to a random player and tells both what happened.
+fn stir(
+    weather: Weather,
+    players: &mut [PlayerConnection; 2],
+    observers: &mut Observers,
+) -> Result<()> {
+    let mut rng = rand::rng();
+    let player = rng.random_range(1..=2);
+    let (struck, other) = pair(players, player);
+    let Some(grid) = struck.grid.as_mut() else {
+        return Ok(());
+    };
+
+    let (event, seen_by_other) = match weather {
+        Weather::Fog => {
+            struck.fogged = true;
+            dashboard::log(format!("🌫 Fog hides {}'s next result", struck.label));
+            (
+                SeaEvent::Fog { yours: true },
+                SeaEvent::Fog { yours: false },
+            )
+        }
+        Weather::Current => {
+            let Some((from, to)) = weather::drift(grid, &mut rng) else {
+                return Ok(());
+            };
+            dashboard::update_board(player, Some(grid));
+            dashboard::log(format!(
+                "🌊 A current moves {}'s ship from {} to {}",
+                struck.label,
+                coords::format(from[0].0, from[0].1),
+                coords::format(to[0].0, to[0].1)
+            ));
+            (
+                SeaEvent::Current {
+                    yours: true,
+                    from,
+                    to,
+                },
+                SeaEvent::Current {
+                    yours: false,
+                    from: Vec::new(),
+                    to: Vec::new(),
+                },
+            )
+        }
+        Weather::Squall => {
+            let Some((x, y)) = weather::squall_cell(grid, &mut rng) else {
+                return Ok(());
+            };
+            let ship = grid[y][x] == CellState::Ship;
+            dashboard::log(format!(
+                "⛈ A squall reveals {} on {}'s board ({})",
+                coords::format(x, y),
+                struck.label,
+                if ship { "ship" } else { "water" }
+            ));
+            let squall = |yours| SeaEvent::Squall { yours, x, y, ship };
+            (squall(true), squall(false))
+        }
+    };
+
+    struck.send(&Message::SeaEvent(event))?;
+    other.send(&Message::SeaEvent(seen_by_other.clone()))?;
+    // Observers learn no more than the opponent, so a current gives away no ship
+    observers.emit(GameEvent::SeaEvent {
+        player,
+        event: seen_by_other,
+    });
+    Ok(())
+}
+
+/// Why a client message was turned away: the code and detail sent back in
+/// the `Error` reply.
+type Rejection = (ErrorCode, String);
+
+/// Where a session is in its lifecycle. Decides which client messages are accepted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SessionPhase {
+    Placement,
+    Battle,
+    Paused,
+    PlayAgain,
+    AwaitingReconnect,
+}
+
+impl SessionPhase {
+    /// Checks whether `player` (1 or 2) may send `msg` right now. `target` is
+    /// the opponent's board, which attacks must land on; a fleet must come
+    /// with `mines` mines.
+    fn validate(
+        self,
+        msg: &Message,
+        player: usize,
+        current_turn: usize,
+        placed: bool,
+        target: Option<&Board>,
+        mines: usize,
+    ) -> Result<(), Rejection> {
+        let on_turn = current_turn + 1 == player;
+        match (msg, self) {
+            (Message::Quit | Message::Emote(_) | Message::Chat { .. } | Message::Pong, _) => Ok(()),
+            (Message::Hello { .. }, _) => Err((
+                ErrorCode::WrongPhase,
+                "Hello was already received".to_string(),
+            )),
+            (Message::PlaceShips(board), SessionPhase::Placement) if !placed => board
+                .check_fleet(mines)
+                .map_err(|detail| (ErrorCode::InvalidPlacement, detail)),
+            (Message::PlaceShips(_), SessionPhase::Placement) => Err((
+                ErrorCode::WrongPhase,
+                "ships already placed; waiting for opponent".to_string(),
+            )),
+            (Message::Attack { x, y }, SessionPhase::Battle) if on_turn => target
+                .map_or(Ok(()), |board| board.check_target(*x, *y))
+                .map_err(|detail| (ErrorCode::InvalidTarget, detail)),
+            (Message::Attack { .. }, SessionPhase::Battle) => {
+                Err((ErrorCode::NotYourTurn, "it is not your turn".to_string()))
+            }
+            (
+                Message::DrawOffer | Message::DrawResponse { .. } | Message::Pause,
+                SessionPhase::Battle,
+            ) => Ok(()),
+            (Message::Unpause, SessionPhase::Paused) => Ok(()),
+            (Message::PlayAgainResponse { .. }, SessionPhase::PlayAgain) => Ok(()),
+            (
+                Message::ClaimVictory | Message::WaitForReconnect,
+                SessionPhase::AwaitingReconnect,
+            ) => Ok(()),
+            (
+                Message::PlaceShips(_)
+                | Message::Attack { .. }
+                | Message::DrawOffer
+                | Message::DrawResponse { .. }
+                | Message::Pause
+                | Message::Unpause
+                | Message::PlayAgainResponse { .. }
+                | Message::ClaimVictory
+                | Message::WaitForReconnect,
+                _,
+            ) => Err((
+                ErrorCode::WrongPhase,
+                format!("{} is not allowed during {}", msg.name(), self.describe()),
+            )),
+            _ => Err((
+                ErrorCode::UnexpectedMessage,
+                format!("{} is not a client message", msg.name()),
+            )),
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            SessionPhase::Placement => "ship placement",
+            SessionPhase::Battle => "battle",
+            SessionPhase::Paused => "a pause",
+            SessionPhase::PlayAgain => "the play-again vote",
+            SessionPhase::AwaitingReconnect => "an opponent reconnect",
+        }
+    }
+
+    /// Decodes a frame from `player` and passes the message through if it is
+    /// valid now; otherwise rejects it, counting the violation, and returns `None`.
+    fn admit(
+        self,
+        frame: &Frame,
+        player: usize,
+        current_turn: usize,
+        conn: &mut PlayerConnection,
+        target: Option<&Board>,
+        mines: usize,
+    ) -> Result<Option<Message>> {
+        let verdict = frame
+            .decode()
+            .map_err(|e| (ErrorCode::Malformed, format!("malformed message: {}", e)));
+        let verdict = verdict.and_then(|msg| {
+            self.validate(&msg, player, current_turn, conn.ready, target, mines)
+                .map(|()| msg)
+        });
+        match verdict {
+            Ok(msg) => Ok(Some(msg)),
+            Err(detail) => {
+                conn.reject(player, detail)?;
+                Ok(None)
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
 enum PlayAgainState {
     None,
     WaitingForResponses {
-        p1_response: Option<bool>,
-        p2_response: Option<bool>,
+        /// Each player's answer, Player 1 first.
+        responses: [Option<bool>; 2],
         timeout_start: Instant,
     },
     Timeout,
@@ -28,498 +885,1487 @@ enum PlayAgainState {
     OneDeclined,
 }
 
-pub async fn run_server(port: &str) -> Result<()> {
-    let listener = TcpListener::bind(format!("0.0.0.0:{}", port))?;
-    listener.set_nonblocking(true)?;
-    println!("🚢 Battleship Server listening on port {}", port);
-    println!("Waiting for 2 players to connect...\n");
+/// What happens to a player who lets the `--turn-timeout` clock run out.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum TurnExpiry {
+    /// The server fires at a random untouched cell for them.
+    #[default]
+    RandomShot,
+    /// They lose the game.
+    Forfeit,
+}
+
+impl FromStr for TurnExpiry {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "random-shot" => Ok(TurnExpiry::RandomShot),
+            "forfeit" => Ok(TurnExpiry::Forfeit),
+            _ => bail!(
+                "unknown turn timeout action '{}', expected 'random-shot' or 'forfeit'",
+                s
+            ),
+        }
+    }
+}
+
+/// How long a player may take over each shot, and what happens when they
+/// take longer.
+#[derive(Debug, Clone, Copy)]
+pub struct TurnLimit {
+    pub timeout: Duration,
+    pub expiry: TurnExpiry,
+}
+
+/// How long before the turn clock runs out the player on turn is warned.
+const TURN_WARNING: Duration = Duration::from_secs(10);
+
+/// How a server hosts its games.
+pub struct Hosting {
+    /// Games allowed to run at once; further pairs wait in the queue.
+    pub max_games: Option<usize>,
+    /// Players, observers and coaches allowed to be connected at once.
+    pub max_connections: Option<usize>,
+    /// Makes the weather for each new game.
+    pub sea: fn() -> Box<dyn EventGenerator>,
+    /// The per-turn clock, if turns are timed.
+    pub turn_limit: Option<TurnLimit>,
+    /// Mines each player lays with their fleet; 0 plays without them.
+    pub mines: usize,
+    /// Ships may not touch, and sinking one reveals the water around it.
+    pub reveal_sunk: bool,
+    /// Games in a series, if the pair plays one.
+    pub best_of: Option<u32>,
+    /// Seats the AI across from a player left waiting alone, if set.
+    pub backfill: Option<Backfill>,
+    /// Players, observers and coaches must give this in their `Hello`.
+    pub password: Option<String>,
+    pub transport: Transport,
+    pub timeouts: Timeouts,
+}
+
+pub async fn run_server(addr: SocketAddr, with_dashboard: bool, hosting: Hosting) -> Result<()> {
+    let listener = net::listen(addr)?;
 
     let shutdown = Arc::new(Mutex::new(false));
-    let shutdown_flag = shutdown.clone();
+    let dashboard_stop = Arc::new(Mutex::new(false));
+    let dashboard_task =
+        with_dashboard.then(|| dashboard::start(addr, shutdown.clone(), dashboard_stop.clone()));
+
+    dashboard::log(format!(
+        "🚢 Battleship Server listening on {} ({})",
+        addr,
+        hosting.transport.describe()
+    ));
+    dashboard::log("Players are paired in the order they connect\n");
 
+    let shutdown_flag = shutdown.clone();
     tokio::spawn(async move {
         let _ = tokio::signal::ctrl_c().await;
         *shutdown_flag.lock().unwrap() = true;
-        println!("\nShutting down server...");
+        dashboard::log("\nShutting down server...");
     });
 
-    // Wait for two players
-    let mut players: Vec<TcpStream> = Vec::new();
+    let result = serve(&listener, shutdown, hosting).await;
 
-    while players.len() < 2 {
-        if *shutdown.lock().unwrap() {
-            return Ok(());
-        }
-
-        match listener.accept() {
-            Ok((stream, addr)) => {
-                stream.set_nonblocking(true)?;
-                println!("Player {} connected: {}", players.len() + 1, addr);
-                players.push(stream);
-            }
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                tokio::time::sleep(Duration::from_millis(50)).await;
-            }
-            Err(e) => {
-                eprintln!("Accept error: {}", e);
-            }
-        }
+    if let Some(task) = dashboard_task {
+        *dashboard_stop.lock().unwrap() = true;
+        task.await??;
     }
+    result
+}
+
+/// Letters and digits for room codes, leaving out look-alikes such as O and 0.
+const ROOM_CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+const ROOM_CODE_LEN: usize = 5;
+/// Coach codes are longer: one lets a stranger see a player's fleet.
+const COACH_CODE_LEN: usize = 10;
+
+fn random_code(rng: &mut impl Rng, len: usize) -> String {
+    (0..len)
+        .map(|_| ROOM_CODE_CHARS[rng.random_range(0..ROOM_CODE_CHARS.len())] as char)
+        .collect()
+}
 
-    println!("\n2 players connected! Starting game...\n");
+/// A secret that lets a dropped player resume their seat, too long to guess.
+fn session_token(rng: &mut impl Rng) -> String {
+    format!("{:032x}", rng.random::<u128>())
+}
 
-    run_game_session(players.remove(0), players.remove(0), shutdown).await
+/// A game the match manager has started.
+struct RunningGame {
+    id: u64,
+    /// The code of the room the game was made in, if any.
+    room: Option<String>,
+    /// Each seat's session token, Player 1's first.
+    tokens: [String; 2],
+    /// Each seat's coach code, Player 1's first.
+    coach_codes: [String; 2],
+    arrivals: mpsc::UnboundedSender<Newcomer>,
 }
 
-pub async fn run_game_session(
-    stream1: TcpStream,
-    stream2: TcpStream,
+/// Accepts connections on `listener` until shut down, pairing players in
+/// the order they arrive and running each pair's game in its own task.
+/// Players who create a room wait for whoever joins with its code instead.
+/// Observers join the game they name, or the newest, and coaches the game
+/// their code was issued in; a player arriving while a game waits for a
+/// reconnect takes that seat instead of queueing.
+pub async fn serve(
+    listener: &TcpListener,
     shutdown: Arc<Mutex<bool>>,
+    hosting: Hosting,
 ) -> Result<()> {
-    // Create player connections
-    let mut p1 = PlayerConnection {
-        stream: stream1,
-        grid: None,
-        ready: false,
-    };
-    let mut p2 = PlayerConnection {
-        stream: stream2,
-        grid: None,
-        ready: false,
+    let Hosting {
+        max_games,
+        max_connections,
+        sea,
+        turn_limit,
+        mines,
+        reveal_sunk,
+        best_of,
+        backfill,
+        password,
+        transport,
+        timeouts,
+    } = hosting;
+    let welcome = Welcome {
+        password: password.map(Arc::from),
+        mines,
+        reveal_sunk,
     };
+    let limit = net::ConnectionLimit::new(max_connections);
+    let (introduced, mut newcomers) = mpsc::unbounded_channel();
+    let mut queue: Vec<Newcomer> = Vec::new();
+    // Hosts waiting in their room, by code
+    let mut rooms: HashMap<String, Newcomer> = HashMap::new();
+    // Rooms that filled up while `max_games` were running
+    let mut matched: VecDeque<(String, [Newcomer; 2])> = VecDeque::new();
+    // Observers that arrived before any game started
+    let mut spectators: Vec<Newcomer> = Vec::new();
+    let mut running: Vec<RunningGame> = Vec::new();
+    let mut games = JoinSet::new();
+    let mut next_id = 1;
+    let mut idle_since = Instant::now();
 
-    let mut p1_reader = BufReader::new(p1.stream.try_clone()?);
-    let mut p2_reader = BufReader::new(p2.stream.try_clone()?);
-
-    // Game loop
-    let mut current_turn = 0; // 0 = player 1, 1 = player 2
-    let mut game_over = false;
-    let mut play_again_state = PlayAgainState::None;
-
-    while !game_over && !*shutdown.lock().unwrap() {
-        // Read from both players
-        let mut line = String::new();
-
-        // Check player 1
-        match p1_reader.read_line(&mut line) {
-            Ok(0) => {
-                println!("Player 1 disconnected");
-                break;
-            }
-            Ok(_) => {
-                if let Ok(msg) = serde_json::from_str::<Message>(&line) {
-                    match msg {
-                        Message::PlaceShips(grid) => {
-                            p1.grid = Some(grid);
-                            p1.ready = true;
-                            println!("Player 1 placed ships");
-
-                            if p2.ready {
-                                // Both ready, start game
-                                writeln!(
-                                    p1.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::GameStart)?
-                                )?;
-                                p1.stream.flush()?;
-                                writeln!(
-                                    p2.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::GameStart)?
-                                )?;
-                                p2.stream.flush()?;
-                                writeln!(
-                                    p1.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::YourTurn)?
-                                )?;
-                                p1.stream.flush()?;
-                                writeln!(
-                                    p2.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::OpponentTurn)?
-                                )?;
-                                p2.stream.flush()?;
-                                println!("Game started! Player 1's turn\n");
-                            } else {
-                                writeln!(
-                                    p1.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::WaitingForOpponent)?
-                                )?;
-                                p1.stream.flush()?;
-                            }
-                        }
-                        Message::Attack { x, y } if current_turn == 0 && p1.ready && p2.ready => {
-                            // Player 1 attacks player 2
-                            if let Some(ref mut grid) = p2.grid {
-                                let hit = grid[y][x] == CellState::Ship;
-                                if hit {
-                                    grid[y][x] = CellState::Hit;
-                                }
-                                let sunk = if hit {
-                                    GameState::is_ship_sunk_at(grid, x, y)
-                                } else {
-                                    false
-                                };
-
-                                // Send result to player 1
-                                writeln!(
-                                    p1.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::AttackResult {
-                                        x,
-                                        y,
-                                        hit,
-                                        sunk
-                                    })?
-                                )?;
-                                p1.stream.flush()?;
-
-                                // Send attack to player 2
-                                writeln!(
-                                    p2.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::Attack { x, y })?
-                                )?;
-                                p2.stream.flush()?;
-
-                                println!(
-                                    "Player 1 attacked {} - {}",
-                                    crate::game_state::GameState::format_coordinate(x, y),
-                                    if hit { "HIT" } else { "MISS" }
-                                );
+    while !*shutdown.lock().unwrap() {
+        if running.is_empty() && timeouts.accept_expired(idle_since) {
+            anyhow::bail!(
+                "timed out after {}s waiting for players to connect ({} waiting)",
+                idle_since.elapsed().as_secs(),
+                queue.len()
+            );
+        }
 
-                                // Check if player 2 lost
-                                if GameState::all_ships_sunk(grid) {
-                                    writeln!(
-                                        p1.stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::GameOver { won: true })?
-                                    )?;
-                                    p1.stream.flush()?;
-                                    writeln!(
-                                        p2.stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::GameOver { won: false })?
-                                    )?;
-                                    p2.stream.flush()?;
-                                    println!("\n🎉 Player 1 wins!");
-
-                                    // Start play again process
-                                    play_again_state = PlayAgainState::WaitingForResponses {
-                                        p1_response: None,
-                                        p2_response: None,
-                                        timeout_start: Instant::now(),
-                                    };
-                                    writeln!(
-                                        p1.stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::PlayAgainRequest)?
-                                    )?;
-                                    p1.stream.flush()?;
-                                    writeln!(
-                                        p2.stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::PlayAgainRequest)?
-                                    )?;
-                                    p2.stream.flush()?;
-                                    println!("Asking both players if they want to play again...");
-                                } else {
-                                    // Switch turn
-                                    current_turn = 1;
-                                    writeln!(
-                                        p1.stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::OpponentTurn)?
-                                    )?;
-                                    p1.stream.flush()?;
-                                    writeln!(
-                                        p2.stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::YourTurn)?
-                                    )?;
-                                    p2.stream.flush()?;
-                                    println!("Player 2's turn\n");
-                                }
-                            }
-                        }
-                        Message::PlayAgainResponse { wants_to_play } => {
-                            if let PlayAgainState::WaitingForResponses {
-                                p1_response,
-                                p2_response,
-                                ..
-                            } = &mut play_again_state
-                            {
-                                *p1_response = Some(wants_to_play);
-                                println!("Player 1 play again response: {}", wants_to_play);
-
-                                // Check if both players responded
-                                if let (Some(p1_resp), Some(p2_resp)) = (p1_response, p2_response) {
-                                    if *p1_resp && *p2_resp {
-                                        play_again_state = PlayAgainState::BothAgreed;
-                                    } else {
-                                        play_again_state = PlayAgainState::OneDeclined;
-                                    }
-                                }
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, addr)) => match limit.admit() {
+                    Some(slot) => {
+                        tokio::spawn(introduce(
+                            stream,
+                            addr.to_string(),
+                            slot,
+                            transport.clone(),
+                            timeouts,
+                            welcome.clone(),
+                            introduced.clone(),
+                        ));
+                    }
+                    None => {
+                        let transport = transport.clone();
+                        tokio::spawn(async move {
+                            if let Ok((_, writer)) = transport.open(stream, &timeouts).await {
+                                refuse(
+                                    writer,
+                                    &addr.to_string(),
+                                    ErrorCode::ServerFull,
+                                    "server full: connection limit reached",
+                                );
                             }
+                        });
+                    }
+                },
+                Err(e) => dashboard::log(format!("Accept error: {}", e)),
+            },
+            Some(newcomer) = newcomers.recv() => match newcomer.role {
+                Role::Player => {
+                    dashboard::log(format!("Player connected: {}", newcomer.addr));
+                    directory::player_joined();
+                    queue.push(newcomer);
+                }
+                Role::Host => {
+                    let mut rng = rand::rng();
+                    let code = loop {
+                        let code = random_code(&mut rng, ROOM_CODE_LEN);
+                        if !rooms.contains_key(&code) && !running.iter().any(|game| game.room.as_ref() == Some(&code)) {
+                            break code;
                         }
-                        Message::Quit => {
-                            println!("Player 1 quit the game");
-                            let _ = writeln!(
-                                p2.stream,
-                                "{}",
-                                serde_json::to_string(&Message::OpponentQuit)?
-                            );
-                            let _ = p2.stream.flush();
-                            game_over = true;
+                    };
+                    let mut host = newcomer;
+                    let _ = host.writer.send(&Message::RoomCreated { code: code.clone() });
+                    dashboard::log(format!("{} opened room {}", host.addr, code));
+                    directory::player_joined();
+                    rooms.insert(code, host);
+                }
+                Role::Guest(ref code) => {
+                    if let Some(host) = rooms.remove(code) {
+                        dashboard::log(format!("{} joined room {}", newcomer.addr, code));
+                        directory::player_joined();
+                        matched.push_back((code.clone(), [host, newcomer]));
+                    } else {
+                        let reason = format!("there is no room {} waiting for a player", code);
+                        refuse(newcomer.writer, &newcomer.addr, ErrorCode::RoomNotFound, &reason);
+                    }
+                }
+                Role::Resume(ref token) => {
+                    if let Some(game) = running.iter().find(|game| game.tokens.contains(token)) {
+                        let _ = game.arrivals.send(newcomer);
+                    } else {
+                        refuse(
+                            newcomer.writer,
+                            &newcomer.addr,
+                            ErrorCode::SessionExpired,
+                            "that game is over",
+                        );
+                    }
+                }
+                Role::Coach(ref code) => {
+                    if let Some(game) = running.iter().find(|game| game.coach_codes.contains(code)) {
+                        let _ = game.arrivals.send(newcomer);
+                    } else {
+                        let reason = format!("no running game has the coach code {}", code);
+                        refuse(newcomer.writer, &newcomer.addr, ErrorCode::RoomNotFound, &reason);
+                    }
+                }
+                Role::Observer(None) => match running.last() {
+                    Some(game) => {
+                        let _ = game.arrivals.send(newcomer);
+                    }
+                    None => spectators.push(newcomer),
+                },
+                Role::Observer(Some(ref wanted)) => {
+                    let wanted = wanted.trim().to_uppercase();
+                    if let Some(game) = running.iter().find(|game| {
+                        game.id.to_string() == wanted || game.room.as_ref() == Some(&wanted)
+                    }) {
+                        let _ = game.arrivals.send(newcomer);
+                    } else {
+                        let reason = format!("no game {} is running", wanted);
+                        refuse(newcomer.writer, &newcomer.addr, ErrorCode::RoomNotFound, &reason);
+                    }
+                }
+                Role::Incompatible(ref detail) => refuse(
+                    newcomer.writer,
+                    &newcomer.addr,
+                    ErrorCode::IncompatibleVersion,
+                    detail,
+                ),
+                Role::Unauthorized(reason) => {
+                    refuse(newcomer.writer, &newcomer.addr, ErrorCode::Unauthorized, reason);
+                }
+                Role::Gone => {}
+            },
+            Some(finished) = games.join_next() => {
+                match finished {
+                    Ok((id, result)) => {
+                        running.retain(|game| game.id != id);
+                        if let Err(e) = result {
+                            dashboard::log(format!("Game {} failed: {}", id, e));
                         }
-                        _ => {}
                     }
+                    Err(e) => dashboard::log(format!("A game task failed: {}", e)),
+                }
+                if running.is_empty() {
+                    idle_since = Instant::now();
                 }
             }
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
-            Err(_) => {
-                println!("Player 1 connection error");
-                break;
-            }
-        }
-
-        // Check player 2
-        line.clear();
-        match p2_reader.read_line(&mut line) {
-            Ok(0) => {
-                println!("Player 2 disconnected");
-                break;
-            }
-            Ok(_) => {
-                if let Ok(msg) = serde_json::from_str::<Message>(&line) {
-                    match msg {
-                        Message::PlaceShips(grid) => {
-                            p2.grid = Some(grid);
-                            p2.ready = true;
-                            println!("Player 2 placed ships");
-
-                            if p1.ready {
-                                // Both ready, start game
-                                writeln!(
-                                    p1.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::GameStart)?
-                                )?;
-                                p1.stream.flush()?;
-                                writeln!(
-                                    p2.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::GameStart)?
-                                )?;
-                                p2.stream.flush()?;
-                                writeln!(
-                                    p1.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::YourTurn)?
-                                )?;
-                                p1.stream.flush()?;
-                                writeln!(
-                                    p2.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::OpponentTurn)?
-                                )?;
-                                p2.stream.flush()?;
-                                println!("Game started! Player 1's turn\n");
-                            } else {
-                                writeln!(
-                                    p2.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::WaitingForOpponent)?
-                                )?;
-                                p2.stream.flush()?;
-                            }
-                        }
-                        Message::Attack { x, y } if current_turn == 1 && p1.ready && p2.ready => {
-                            // Player 2 attacks player 1
-                            if let Some(ref mut grid) = p1.grid {
-                                let hit = grid[y][x] == CellState::Ship;
-                                if hit {
-                                    grid[y][x] = CellState::Hit;
-                                }
-                                let sunk = if hit {
-                                    GameState::is_ship_sunk_at(grid, x, y)
-                                } else {
-                                    false
-                                };
-
-                                // Send result to player 2
-                                writeln!(
-                                    p2.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::AttackResult {
-                                        x,
-                                        y,
-                                        hit,
-                                        sunk
-                                    })?
-                                )?;
-                                p2.stream.flush()?;
-
-                                // Send attack to player 1
-                                writeln!(
-                                    p1.stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::Attack { x, y })?
-                                )?;
-                                p1.stream.flush()?;
-
-                                println!(
-                                    "Player 2 attacked {} - {}",
-                                    crate::game_state::GameState::format_coordinate(x, y),
-                                    if hit { "HIT" } else { "MISS" }
-                                );
+            _ = tokio::time::sleep(net::POLL_INTERVAL) => {}
+        }
 
-                                // Check if player 1 lost
-                                if GameState::all_ships_sunk(grid) {
-                                    writeln!(
-                                        p1.stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::GameOver { won: false })?
-                                    )?;
-                                    p1.stream.flush()?;
-                                    writeln!(
-                                        p2.stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::GameOver { won: true })?
-                                    )?;
-                                    p2.stream.flush()?;
-                                    println!("\n🎉 Player 2 wins!");
-
-                                    // Start play again process
-                                    play_again_state = PlayAgainState::WaitingForResponses {
-                                        p1_response: None,
-                                        p2_response: None,
-                                        timeout_start: Instant::now(),
-                                    };
-                                    writeln!(
-                                        p1.stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::PlayAgainRequest)?
-                                    )?;
-                                    p1.stream.flush()?;
-                                    writeln!(
-                                        p2.stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::PlayAgainRequest)?
-                                    )?;
-                                    p2.stream.flush()?;
-                                    println!("Asking both players if they want to play again...");
-                                } else {
-                                    // Switch turn
-                                    current_turn = 0;
-                                    writeln!(
-                                        p1.stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::YourTurn)?
-                                    )?;
-                                    p1.stream.flush()?;
-                                    writeln!(
-                                        p2.stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::OpponentTurn)?
-                                    )?;
-                                    p2.stream.flush()?;
-                                    println!("Player 1's turn\n");
-                                }
-                            }
-                        }
-                        Message::PlayAgainResponse { wants_to_play } => {
-                            if let PlayAgainState::WaitingForResponses {
-                                p1_response,
-                                p2_response,
-                                ..
-                            } = &mut play_again_state
-                            {
-                                *p2_response = Some(wants_to_play);
-                                println!("Player 2 play again response: {}", wants_to_play);
-
-                                // Check if both players responded
-                                if let (Some(p1_resp), Some(p2_resp)) = (p1_response, p2_response) {
-                                    if *p1_resp && *p2_resp {
-                                        play_again_state = PlayAgainState::BothAgreed;
-                                    } else {
-                                        play_again_state = PlayAgainState::OneDeclined;
-                                    }
-                                }
-                            }
-                        }
-                        Message::Quit => {
-                            println!("Player 2 quit the game");
-                            let _ = writeln!(
-                                p1.stream,
-                                "{}",
-                                serde_json::to_string(&Message::OpponentQuit)?
-                            );
-                            let _ = p1.stream.flush();
-                            game_over = true;
-                        }
-                        _ => {}
+        // Someone who gives up while waiting must not be paired with the next arrival
+        queue.retain_mut(|waiting| {
+            let left = net::peer_closed(&mut waiting.frames);
+            if left {
+                dashboard::log("A waiting player left before the game started");
+                directory::player_left();
+            }
+            !left
+        });
+        rooms.retain(|code, host| {
+            let left = net::peer_closed(&mut host.frames);
+            if left {
+                dashboard::log(format!("Room {} closed: its host left", code));
+                directory::player_left();
+            }
+            !left
+        });
+
+        // Nobody came for the only player waiting: the AI takes the seat
+        if let Some(Backfill { after, difficulty }) = backfill
+            && let [waiting] = queue.as_slice()
+            && waiting.arrived.elapsed() >= after
+            && max_games.is_none_or(|max| running.len() < max)
+        {
+            dashboard::log(format!(
+                "No opponent for {} after {}s: seating the AI ({})",
+                waiting.addr,
+                after.as_secs(),
+                difficulty.name()
+            ));
+            let placement = Placement {
+                mines,
+                apart: reveal_sunk,
+            };
+            let (frames, writer) = backfill::connect(difficulty, placement, &timeouts);
+            // Counted like any player, since the game counts both seats out
+            directory::player_joined();
+            queue.push(Newcomer {
+                role: Role::Player,
+                frames,
+                pending: None,
+                writer,
+                addr: "AI".to_string(),
+                name: Some("AI".to_string()),
+                arrived: Instant::now(),
+            });
+        }
+
+        while max_games.is_none_or(|max| running.len() < max) {
+            let (room, players) = match matched.pop_front() {
+                Some((code, players)) => (Some(code), players),
+                None if queue.len() >= 2 => (None, [queue.remove(0), queue.remove(0)]),
+                None => break,
+            };
+            let id = next_id;
+            next_id += 1;
+            dashboard::log(format!(
+                "Game {} started{}: {} vs {}",
+                id,
+                room.as_ref()
+                    .map(|code| format!(" in room {}", code))
+                    .unwrap_or_default(),
+                players[0].addr,
+                players[1].addr
+            ));
+            let (arrivals, arriving) = mpsc::unbounded_channel();
+            for spectator in spectators.drain(..) {
+                let _ = arrivals.send(spectator);
+            }
+            let mut rng = rand::rng();
+            let tokens = [session_token(&mut rng), session_token(&mut rng)];
+            let coach_codes = [
+                random_code(&mut rng, COACH_CODE_LEN),
+                random_code(&mut rng, COACH_CODE_LEN),
+            ];
+            running.push(RunningGame {
+                id,
+                room,
+                tokens: tokens.clone(),
+                coach_codes: coach_codes.clone(),
+                arrivals,
+            });
+            let [first, second] = players;
+            let [first_token, second_token] = tokens;
+            let session = run_game_session(
+                id,
+                [(first, first_token), (second, second_token)],
+                coach_codes,
+                arriving,
+                shutdown.clone(),
+                Rules {
+                    sea: sea(),
+                    turn_limit,
+                    mines,
+                    reveal_sunk,
+                    best_of,
+                },
+                timeouts,
+            );
+            games.spawn(async move { (id, dashboard::in_game(id, session).await) });
+        }
+        dashboard::set_queue(queue.len() + rooms.len() + 2 * matched.len());
+    }
+
+    while games.join_next().await.is_some() {}
+    Ok(())
+}
+
+/// What woke the session loop.
+enum Wake {
+    Input(PlayerInput),
+    Arrival(Box<Newcomer>),
+    /// Time to check the clocks: reconnect window, play-again vote, shutdown.
+    Tick,
+}
+
+/// One running game: both seats and everyone watching. Owned by a single
+/// task; each player's reader task feeds it through `inputs`, so everything
+/// below handles either player the same way.
+struct Session {
+    id: u64,
+    /// Player 1's seat first.
+    players: [PlayerConnection; 2],
+    addrs: [String; 2],
+    /// What a player sends in `ResumeGame` to take back their seat.
+    tokens: [String; 2],
+    /// What a coach sends in `Coach` to follow a seat. Only that seat's
+    /// player is told it.
+    coach_codes: [String; 2],
+    /// Seats with a player connected, as counted in the directory listing.
+    seated: usize,
+    /// 0 = player 1, 1 = player 2
+    current_turn: usize,
+    /// The player who fires first this game.
+    first: usize,
+    /// Games in a series, if the pair plays one.
+    best_of: Option<u32>,
+    /// Games each player has won in the series so far, Player 1's first.
+    wins: [u32; 2],
+    /// The player whose draw offer awaits an answer.
+    draw_offer: Option<usize>,
+    /// Set while the battle is paused.
+    pause: Option<Pause>,
+    phase: SessionPhase,
+    play_again: PlayAgainState,
+    observers: Observers,
+    vacant_seat: Option<VacantSeat>,
+    sea: Box<dyn EventGenerator>,
+    turn_limit: Option<TurnLimit>,
+    /// Mines each player lays with their fleet.
+    mines: usize,
+    /// Ships may not touch, and sinking one reveals the water around it.
+    reveal_sunk: bool,
+    /// When the player on turn started their turn; only read under a turn limit.
+    turn_started: Instant,
+    /// Whether the player on turn has been warned their time is running out.
+    turn_warned: bool,
+    game_over: bool,
+    inputs: mpsc::UnboundedSender<PlayerInput>,
+    /// How many connections have taken a seat, used to number them.
+    connections: u64,
+    timeouts: Timeouts,
+}
+
+impl Session {
+    /// How the log refers to `player`: their display name, or "Player N".
+    fn name(&self, player: usize) -> &str {
+        &self.players[player - 1].label
+    }
+
+    /// Both players' names as the log shows them, Player 1's first.
+    fn names(&self) -> [String; 2] {
+        [self.name(1).to_string(), self.name(2).to_string()]
+    }
+
+    fn handle_input(&mut self, input: PlayerInput, stats: &mut LoopStats) -> Result<()> {
+        let PlayerInput {
+            player,
+            connection,
+            input,
+        } = input;
+        let vacant = self
+            .vacant_seat
+            .as_ref()
+            .is_some_and(|seat| seat.player == player);
+        if vacant || connection != self.players[player - 1].connection {
+            return Ok(());
+        }
+
+        match input {
+            Input::Frame(frame) => {
+                stats.message();
+                self.players[player - 1].last_heard = Instant::now();
+                let (phase, current_turn, mines) = (self.phase, self.current_turn, self.mines);
+                let (conn, opponent) = pair(&mut self.players, player);
+                if !conn.rate.allow() {
+                    // Floods escalate like any other misbehaviour
+                    let detail =
+                        format!("too many messages: keep to {} a second", net::MESSAGE_RATE);
+                    conn.reject(player, (ErrorCode::Conduct, detail))?;
+                } else if let Some(msg) = phase.admit(
+                    &frame,
+                    player,
+                    current_turn,
+                    conn,
+                    opponent.grid.as_ref(),
+                    mines,
+                )? {
+                    self.handle_message(player, msg)?;
+                }
+                self.players[player - 1].handled();
+            }
+            Input::Closed => {
+                dashboard::log(format!("{} disconnected", self.name(player)));
+                return self.lose(player);
+            }
+            Input::Failed => {
+                dashboard::log(format!("{} connection error", self.name(player)));
+                return self.lose(player);
+            }
+            Input::Silent(timeout) => {
+                dashboard::log(format!(
+                    "{} timed out: no data for {}s",
+                    self.name(player),
+                    timeout.as_secs()
+                ));
+                return self.lose(player);
+            }
+        }
+
+        let conn = &mut self.players[player - 1];
+        if let Some(reason) = conn.conduct.kicked {
+            dashboard::log(format!("{} disconnected for {}", conn.label, reason));
+            dashboard::player_kicked();
+            conn.disconnect();
+            return self.lose(player);
+        }
+        Ok(())
+    }
+
+    /// Acts on a message from `player` that `SessionPhase::admit` let through.
+    fn handle_message(&mut self, player: usize, msg: Message) -> Result<()> {
+        let opponent = 3 - player;
+        match msg {
+            Message::PlaceShips(grid) => {
+                if self.reveal_sunk
+                    && let Err(detail) = grid.check_apart()
+                {
+                    let conn = &mut self.players[player - 1];
+                    return conn.reject(player, (ErrorCode::InvalidPlacement, detail));
+                }
+                self.observers.emit(GameEvent::ShipsPlaced { player });
+                let (conn, other) = pair(&mut self.players, player);
+                conn.stream.record(&Message::PlaceShips(grid.clone()))?;
+                conn.grid = Some(grid);
+                conn.ready = true;
+                dashboard::update_board(player, conn.grid.as_ref());
+                dashboard::log(format!("{} placed ships", conn.label));
+
+                if other.ready {
+                    self.start_battle()?;
+                } else {
+                    conn.send(&Message::WaitingForOpponent)?;
+                }
+            }
+            Message::Attack { x, y } => {
+                let (conn, other) = pair(&mut self.players, player);
+                let Some(grid) = other.grid.as_mut() else {
+                    return Ok(());
+                };
+                let shot = grid.fire(x, y);
+                let Shot {
+                    hit, sunk, mine, ..
+                } = shot;
+                let fleet_sunk = grid.all_sunk();
+                dashboard::update_board(opponent, Some(grid));
+
+                // Send the result to the shooter, unless fog holds it back a turn
+                let revealed = if self.reveal_sunk {
+                    grid.water_around_sunk(x, y)
+                } else {
+                    Vec::new()
+                };
+                let result = Message::AttackResult {
+                    x,
+                    y,
+                    hit,
+                    sunk,
+                    ship: SunkShip::from_shot(&shot),
+                    revealed,
+                };
+                if conn.fogged && !fleet_sunk {
+                    conn.fogged = false;
+                    conn.held_result = Some(result);
+                } else {
+                    conn.send(&result)?;
+                }
+                other.send(&Message::Attack { x, y })?;
+
+                self.observers.emit(GameEvent::Shot {
+                    player,
+                    x,
+                    y,
+                    hit,
+                    sunk,
+                });
+                dashboard::log(format!(
+                    "{} attacked {} - {}",
+                    conn.label,
+                    coords::format(x, y),
+                    if hit { "HIT" } else { "MISS" }
+                ));
+
+                if fleet_sunk {
+                    self.finish(player, "fleet_sunk")?;
+                } else if mine {
+                    self.detonate(player)?;
+                } else {
+                    self.pass_turn(opponent)?;
+                }
+            }
+            Message::DrawOffer => {
+                let (conn, other) = pair(&mut self.players, player);
+                if self.draw_offer.is_some() {
+                    let detail = "a draw offer is already waiting for an answer".to_string();
+                    return conn.reject(player, (ErrorCode::WrongPhase, detail));
+                }
+                self.draw_offer = Some(player);
+                dashboard::log(format!("{} offers a draw", conn.label));
+                other.send(&Message::DrawOffer)?;
+            }
+            Message::DrawResponse { accept } => {
+                let (conn, other) = pair(&mut self.players, player);
+                if self.draw_offer != Some(opponent) {
+                    let detail = "there is no draw offer to answer".to_string();
+                    return conn.reject(player, (ErrorCode::WrongPhase, detail));
+                }
+                self.draw_offer = None;
+                if accept {
+                    return self.draw();
+                }
+                dashboard::log(format!("{} declines the draw", conn.label));
+                other.send(&Message::DrawResponse { accept })?;
+            }
+            Message::Pause => self.pause(player)?,
+            Message::Unpause => self.unpause(player)?,
+            Message::PlayAgainResponse { wants_to_play } => {
+                if let PlayAgainState::WaitingForResponses { responses, .. } = &mut self.play_again
+                {
+                    responses[player - 1] = Some(wants_to_play);
+                    dashboard::log(format!(
+                        "{} play again response: {}",
+                        self.players[player - 1].label,
+                        wants_to_play
+                    ));
+                    self.observers.emit(GameEvent::PlayAgainResponse {
+                        player,
+                        wants_to_play,
+                    });
+
+                    // Check if both players responded
+                    if let [Some(first), Some(second)] = *responses {
+                        self.play_again = if first && second {
+                            PlayAgainState::BothAgreed
+                        } else {
+                            PlayAgainState::OneDeclined
+                        };
                     }
                 }
             }
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
-            Err(_) => {
-                println!("Player 2 connection error");
-                break;
+            Message::ClaimVictory => {
+                self.forfeit(player)?;
+            }
+            Message::WaitForReconnect => {
+                if let Some(seat) = self.vacant_seat.as_mut() {
+                    seat.since = Instant::now();
+                    dashboard::log(format!(
+                        "{} is waiting up to {}s for {} to reconnect",
+                        self.players[player - 1].label,
+                        RECONNECT_WINDOW.as_secs(),
+                        self.players[opponent - 1].label
+                    ));
+                }
+            }
+            Message::Emote(emote) => {
+                self.players[opponent - 1].send(&Message::Emote(emote))?;
+            }
+            Message::Chat { text } => {
+                if let Some(text) = chat::clean(&text) {
+                    self.players[opponent - 1].send(&Message::Chat { text })?;
+                }
+            }
+            Message::Pong => {
+                // Tell the player their round trip, for the client's ping display
+                let conn = &mut self.players[player - 1];
+                if let Some(sent) = conn.ping_sent.take() {
+                    let rtt_ms = sent.elapsed().as_millis() as u64;
+                    conn.send_private(&Message::Latency { rtt_ms })?;
+                }
+            }
+            Message::Quit => {
+                dashboard::log(format!("{} quit the game", self.name(player)));
+                self.observers.emit(GameEvent::PlayerLeft { player });
+                self.players[opponent - 1].send(&Message::OpponentQuit)?;
+                self.game_over = true;
             }
+            _ => {}
         }
+        Ok(())
+    }
+
+    /// Both fleets are placed: `first` fires first.
+    fn start_battle(&mut self) -> Result<()> {
+        self.phase = SessionPhase::Battle;
+        self.current_turn = self.first - 1;
+        let (first, second) = pair(&mut self.players, self.first);
+        first.send(&Message::GameStart)?;
+        second.send(&Message::GameStart)?;
+        first.send(&Message::YourTurn)?;
+        second.send(&Message::OpponentTurn)?;
+        dashboard::log(format!("Game started! {}'s turn\n", first.label));
+        self.observers.emit(GameEvent::GameStarted {
+            first_player: self.first,
+        });
+        self.start_clock()
+    }
 
-        // Handle play again state transitions
-        match &mut play_again_state {
+    /// `player`'s shot set off a mine: the blast strikes a random cell of
+    /// their own fleet, which can cost them the game, before the turn passes.
+    fn detonate(&mut self, player: usize) -> Result<()> {
+        let opponent = 3 - player;
+        let (conn, other) = pair(&mut self.players, player);
+        let Some(grid) = conn.grid.as_mut() else {
+            return Ok(());
+        };
+        let Some((x, y)) = grid.random_ship_cell(&mut rand::rng()) else {
+            return self.pass_turn(opponent);
+        };
+        let shot = grid.fire(x, y);
+        let Shot { sunk, .. } = shot;
+        let ship = SunkShip::from_shot(&shot);
+        let fleet_sunk = grid.all_sunk();
+        dashboard::update_board(player, Some(grid));
+        dashboard::log(format!(
+            "💥 {} hit a mine; the blast struck their ship at {}",
+            conn.label,
+            coords::format(x, y)
+        ));
+        conn.send(&Message::MineDetonated {
+            yours: true,
+            x,
+            y,
+            sunk,
+            ship: ship.clone(),
+        })?;
+        other.send(&Message::MineDetonated {
+            yours: false,
+            x,
+            y,
+            sunk,
+            ship,
+        })?;
+        self.observers
+            .emit(GameEvent::MineDetonated { player, x, y, sunk });
+
+        if fleet_sunk {
+            self.finish(opponent, "mine")
+        } else {
+            self.pass_turn(opponent)
+        }
+    }
+
+    /// Hands the turn to `next`. Under stormy seas the weather may strike in between.
+    fn pass_turn(&mut self, next: usize) -> Result<()> {
+        self.current_turn = next - 1;
+        let (conn, other) = pair(&mut self.players, next);
+        if let Some(result) = conn.held_result.take() {
+            conn.send(&result)?;
+        }
+        other.send(&Message::OpponentTurn)?;
+        conn.send(&Message::YourTurn)?;
+        dashboard::log(format!("{}'s turn\n", conn.label));
+        self.observers.emit(GameEvent::TurnChanged { player: next });
+        self.start_clock()?;
+
+        if let Some(weather) = self.sea.next_event() {
+            stir(weather, &mut self.players, &mut self.observers)?;
+        }
+        Ok(())
+    }
+
+    /// Restarts the turn clock, if turns are timed, and tells both players
+    /// how long the player on turn has.
+    fn start_clock(&mut self) -> Result<()> {
+        let Some(limit) = self.turn_limit else {
+            return Ok(());
+        };
+        self.turn_started = Instant::now();
+        self.turn_warned = false;
+        self.send_clock(limit.timeout.as_secs(), false)
+    }
+
+    /// Tells both players how long the player on turn still has, e.g. after a
+    /// pause or a resume.
+    fn send_time_left(&mut self) -> Result<()> {
+        let Some(limit) = self.turn_limit else {
+            return Ok(());
+        };
+        let left = limit.timeout.saturating_sub(self.turn_started.elapsed());
+        self.send_clock((left.as_secs_f64().ceil() as u64).max(1), false)
+    }
+
+    fn send_clock(&mut self, seconds_left: u64, warning: bool) -> Result<()> {
+        for conn in &mut self.players {
+            conn.send(&Message::TurnClock {
+                seconds_left,
+                warning,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Warns the player on turn as their time runs short, and acts for them
+    /// once it has run out: a random shot or a forfeit, as the server was told.
+    fn check_turn_clock(&mut self) -> Result<()> {
+        let Some(limit) = self.turn_limit else {
+            return Ok(());
+        };
+        if self.phase != SessionPhase::Battle {
+            return Ok(());
+        }
+        let player = self.current_turn + 1;
+        let left = limit.timeout.saturating_sub(self.turn_started.elapsed());
+        if !left.is_zero() {
+            if !self.turn_warned && left <= TURN_WARNING.min(limit.timeout / 2) {
+                self.turn_warned = true;
+                let seconds_left = left.as_secs_f64().ceil() as u64;
+                dashboard::log(format!(
+                    "{} has {}s left to fire",
+                    self.name(player),
+                    seconds_left
+                ));
+                self.send_clock(seconds_left, true)?;
+            }
+            return Ok(());
+        }
+
+        self.send_clock(0, false)?;
+        match limit.expiry {
+            TurnExpiry::RandomShot => {
+                let Some(target) = self.players[2 - player].grid.as_ref() else {
+                    return Ok(());
+                };
+                let open: Vec<(usize, usize)> = (0..GRID_SIZE)
+                    .flat_map(|y| (0..GRID_SIZE).map(move |x| (x, y)))
+                    .filter(|&(x, y)| target.check_target(x, y).is_ok())
+                    .collect();
+                if open.is_empty() {
+                    return Ok(());
+                }
+                let (x, y) = open[rand::rng().random_range(0..open.len())];
+                dashboard::log(format!(
+                    "{} ran out of time; firing at {} for them",
+                    self.name(player),
+                    coords::format(x, y)
+                ));
+                self.handle_message(player, Message::Attack { x, y })
+            }
+            TurnExpiry::Forfeit => {
+                dashboard::log(format!(
+                    "{} ran out of time and forfeits",
+                    self.name(player)
+                ));
+                self.finish(3 - player, "turn_timeout")
+            }
+        }
+    }
+
+    /// `winner` won the game, by sinking the last ship or as `reason` says:
+    /// report the result and ask for a rematch.
+    fn finish(&mut self, winner: usize, reason: &str) -> Result<()> {
+        for (i, conn) in self.players.iter_mut().enumerate() {
+            conn.send(&Message::GameOver {
+                won: i + 1 == winner,
+            })?;
+        }
+        self.send_review()?;
+        dashboard::log(format!("\n🎉 {} wins!", self.name(winner)));
+        self.observers.emit(GameEvent::GameOver { winner });
+        self.reveal_fleets();
+        webhook::report(
+            self.id,
+            &self.names(),
+            Some(winner),
+            reason,
+            &self.observers.history,
+        );
+        self.score_series(winner)?;
+        self.ask_play_again()
+    }
+
+    /// Ends the game in `winner`'s favour because the opponent left. It
+    /// counts towards the series like any win, but with the opponent gone
+    /// the rematch offer closes at once and the session ends.
+    fn forfeit(&mut self, winner: usize) -> Result<()> {
+        self.vacant_seat = None;
+        dashboard::log(format!(
+            "{} forfeits the game by leaving",
+            self.name(3 - winner)
+        ));
+        self.finish(winner, "forfeit")?;
+        self.players[winner - 1].send(&Message::OpponentQuit)?;
+        self.game_over = true;
+        Ok(())
+    }
+
+    /// Shows observers both fleets once the game is over, never before.
+    fn reveal_fleets(&mut self) {
+        for (i, conn) in self.players.iter().enumerate() {
+            if let Some(grid) = &conn.grid {
+                self.observers.emit(GameEvent::FleetRevealed {
+                    player: i + 1,
+                    grid: grid.clone(),
+                });
+            }
+        }
+    }
+
+    /// Sends each player both boards and the shots of the game that just
+    /// ended, read back from the observers' event history.
+    fn send_review(&mut self) -> Result<()> {
+        // Only the latest game when several were played on one connection
+        let start = self
+            .observers
+            .history
+            .iter()
+            .rposition(|event| matches!(event, GameEvent::NewGame))
+            .map_or(0, |i| i + 1);
+        let shots: Vec<(usize, ReviewShot)> = self.observers.history[start..]
+            .iter()
+            .filter_map(|event| match *event {
+                GameEvent::Shot {
+                    player,
+                    x,
+                    y,
+                    hit,
+                    sunk,
+                } => Some((
+                    player,
+                    ReviewShot {
+                        yours: false,
+                        x,
+                        y,
+                        hit,
+                        sunk,
+                    },
+                )),
+                _ => None,
+            })
+            .collect();
+        let boards = [0, 1].map(|i| self.players[i].grid.clone());
+        for (i, conn) in self.players.iter_mut().enumerate() {
+            let (Some(yours), Some(theirs)) = (&boards[i], &boards[1 - i]) else {
+                continue;
+            };
+            conn.send(&Message::GameReview {
+                your_board: yours.clone(),
+                opponent_board: theirs.clone(),
+                shots: shots
+                    .iter()
+                    .map(|&(player, shot)| ReviewShot {
+                        yours: player == i + 1,
+                        ..shot
+                    })
+                    .collect(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Holds the battle, turn clock included, until either player carries on.
+    fn pause(&mut self, player: usize) -> Result<()> {
+        self.phase = SessionPhase::Paused;
+        self.pause = Some(Pause {
+            player,
+            since: Instant::now(),
+        });
+        dashboard::log(format!("⏸ {} paused the game", self.name(player)));
+        for (i, conn) in self.players.iter_mut().enumerate() {
+            conn.send(&Message::GamePaused {
+                by_you: i + 1 == player,
+            })?;
+        }
+        self.observers.emit(GameEvent::Paused { player });
+        Ok(())
+    }
+
+    /// Carries on after a pause, with the turn clock where it stopped.
+    fn unpause(&mut self, player: usize) -> Result<()> {
+        let Some(pause) = self.pause.take() else {
+            return Ok(());
+        };
+        self.phase = SessionPhase::Battle;
+        self.turn_started += pause.since.elapsed();
+        dashboard::log(format!("▶ {} carried on with the game", self.name(player)));
+        for (i, conn) in self.players.iter_mut().enumerate() {
+            conn.send(&Message::GameUnpaused {
+                your_turn: i == self.current_turn,
+            })?;
+        }
+        self.observers.emit(GameEvent::Unpaused { player });
+        self.send_time_left()
+    }
+
+    /// Both players agreed to a draw: the game ends with no winner, and
+    /// counts for neither in a series.
+    fn draw(&mut self) -> Result<()> {
+        for conn in &mut self.players {
+            conn.send(&Message::GameDrawn)?;
+        }
+        dashboard::log("\n🤝 The game is drawn by agreement");
+        self.observers.emit(GameEvent::Draw);
+        self.reveal_fleets();
+        webhook::report(
+            self.id,
+            &self.names(),
+            None,
+            "draw",
+            &self.observers.history,
+        );
+        self.ask_play_again()
+    }
+
+    /// Closes the game, dropping any open draw offer, and asks both players
+    /// for a rematch.
+    fn ask_play_again(&mut self) -> Result<()> {
+        self.draw_offer = None;
+        self.phase = SessionPhase::PlayAgain;
+        self.play_again = PlayAgainState::WaitingForResponses {
+            responses: [None, None],
+            timeout_start: Instant::now(),
+        };
+        for conn in &mut self.players {
+            conn.send(&Message::PlayAgainRequest)?;
+        }
+        dashboard::log("Asking both players if they want to play again...");
+        Ok(())
+    }
+
+    /// Counts a game won by `winner` towards the series, if the pair plays
+    /// one, and tells both players the score.
+    fn score_series(&mut self, winner: usize) -> Result<()> {
+        let Some(best_of) = self.best_of else {
+            return Ok(());
+        };
+        self.wins[winner - 1] += 1;
+        let decided = self.series_decided();
+        for (i, conn) in self.players.iter_mut().enumerate() {
+            conn.send(&Message::SeriesScore {
+                wins: self.wins[i],
+                losses: self.wins[1 - i],
+                best_of,
+                won: decided.then_some(i + 1 == winner),
+            })?;
+        }
+        dashboard::log(if decided {
+            format!(
+                "🏆 {} wins the best-of-{} series {}-{}",
+                self.name(winner),
+                best_of,
+                self.wins[winner - 1],
+                self.wins[2 - winner]
+            )
+        } else {
+            format!(
+                "Series (best of {}): {} {} - {} {}",
+                best_of,
+                self.name(1),
+                self.wins[0],
+                self.wins[1],
+                self.name(2)
+            )
+        });
+        Ok(())
+    }
+
+    /// Whether a player has won most of the series' games.
+    fn series_decided(&self) -> bool {
+        self.best_of
+            .is_some_and(|best_of| self.wins.iter().any(|&wins| wins * 2 > best_of))
+    }
+
+    /// Handles `player`'s connection going away. Mid-battle the survivor may
+    /// wait for a reconnect; otherwise the session ends.
+    fn lose(&mut self, player: usize) -> Result<()> {
+        self.observers.emit(GameEvent::PlayerLeft { player });
+        self.seated -= 1;
+        directory::player_left();
+        let (gone, survivor) = pair(&mut self.players, player);
+        if matches!(self.phase, SessionPhase::Battle | SessionPhase::Paused) {
+            self.phase = SessionPhase::AwaitingReconnect;
+            self.pause = None;
+            survivor.send(&Message::OpponentDisconnected {
+                reconnect_window_secs: RECONNECT_WINDOW.as_secs(),
+            })?;
+            dashboard::log(format!(
+                "{} dropped mid-game. Offering {} the win",
+                gone.label, survivor.label
+            ));
+            self.vacant_seat = Some(VacantSeat {
+                player,
+                since: Instant::now(),
+                vacated: Instant::now(),
+            });
+        } else {
+            survivor.send(&Message::OpponentQuit)?;
+            self.game_over = true;
+        }
+        Ok(())
+    }
+
+    /// A new connection resumes its own seat with a session token, the only
+    /// way into a seat once the game has started, or coaches the seat its
+    /// coach code was issued for; otherwise it joins the observers.
+    fn arrive(&mut self, newcomer: Newcomer) -> Result<()> {
+        if let Role::Resume(token) = &newcomer.role
+            && let Some(seat) = self.tokens.iter().position(|t| t == token)
+        {
+            return self.resume(seat + 1, newcomer);
+        }
+        if let Role::Coach(code) = &newcomer.role
+            && let Some(seat) = self.coach_codes.iter().position(|c| c == code)
+        {
+            self.observers.add_coach(newcomer, seat + 1);
+            return Ok(());
+        }
+        self.observers.settle(newcomer);
+        Ok(())
+    }
+
+    /// Puts `player` back in their seat mid-battle and replays the game to
+    /// them. Their old connection is dropped if the server had not noticed
+    /// it die yet.
+    fn resume(&mut self, player: usize, newcomer: Newcomer) -> Result<()> {
+        let vacant = self
+            .vacant_seat
+            .as_ref()
+            .is_some_and(|seat| seat.player == player);
+        if !vacant && !matches!(self.phase, SessionPhase::Battle | SessionPhase::Paused) {
+            refuse(
+                newcomer.writer,
+                &newcomer.addr,
+                ErrorCode::SessionExpired,
+                &format!(
+                    "the game cannot be resumed during {}",
+                    self.phase.describe()
+                ),
+            );
+            return Ok(());
+        }
+
+        let addr = newcomer.addr.clone();
+        dashboard::log(format!(
+            "{} resumed the game from {}",
+            self.name(player),
+            addr
+        ));
+        dashboard::player_reconnected(player, addr.clone());
+        if vacant {
+            self.seated += 1;
+            directory::player_joined();
+            if let Some(seat) = self.vacant_seat.take() {
+                self.turn_started += seat.vacated.elapsed();
+            }
+            self.phase = SessionPhase::Battle;
+        }
+        self.addrs[player - 1] = addr;
+        self.connections += 1;
+        let fresh = PlayerConnection::new(
+            player,
+            self.connections,
+            newcomer,
+            self.timeouts.read,
+            &self.inputs,
+        );
+        let on_turn = self.current_turn + 1 == player;
+        let (conn, opponent) = pair(&mut self.players, player);
+        conn.reseat(fresh);
+        let (Some(own), Some(enemy)) = (conn.grid.clone(), opponent.grid.as_ref()) else {
+            return Ok(());
+        };
+        // Only the shots show on the enemy board, never the ships or mines
+        let enemy: Vec<Vec<CellState>> = enemy
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        CellState::Ship | CellState::Mine => CellState::Empty,
+                        other => *other,
+                    })
+                    .collect()
+            })
+            .collect();
+        conn.send(&Message::GameResumed {
+            own,
+            enemy: enemy.into(),
+            your_turn: on_turn,
+        })?;
+        if vacant {
+            opponent.send(&Message::OpponentReconnected {
+                your_turn: !on_turn,
+            })?;
+        }
+        // The pause outlasts the reconnect, and the time paused so far stays off the clock
+        if let Some(pause) = &mut self.pause {
+            self.turn_started += pause.since.elapsed();
+            pause.since = Instant::now();
+            conn.send(&Message::GamePaused {
+                by_you: pause.player == player,
+            })?;
+        }
+        self.observers.emit(GameEvent::PlayerResumed { player });
+        self.exchange_names()?;
+        // A resume keeps the turn's clock running, so reconnecting never buys time
+        self.send_time_left()
+    }
+
+    /// Tells each player whether the coin toss has them firing first.
+    fn toss_coin(&mut self) -> Result<()> {
+        dashboard::log(format!(
+            "🪙 {} wins the coin toss and fires first",
+            self.name(self.first)
+        ));
+        for (i, conn) in self.players.iter_mut().enumerate() {
+            conn.send(&Message::CoinToss {
+                you_first: i + 1 == self.first,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Tells each player who they are playing against.
+    fn exchange_names(&mut self) -> Result<()> {
+        let [p1, p2] = &mut self.players;
+        p1.send(&Message::OpponentName {
+            name: p2.name.clone(),
+        })?;
+        p2.send(&Message::OpponentName {
+            name: p1.name.clone(),
+        })
+    }
+
+    /// Forwards what each player saw to their coaches, and passes on hints.
+    fn coach(&mut self) -> Result<()> {
+        let [p1, p2] = &self.players;
+        for (player, text) in self
+            .observers
+            .coach([&p1.stream.transcript, &p2.stream.transcript])
+        {
+            self.players[player - 1].send(&Message::Hint { text })?;
+        }
+        Ok(())
+    }
+
+    /// Pings each seated player every `HEARTBEAT_INTERVAL`, and drops one
+    /// that has not been heard from within `HEARTBEAT_TIMEOUT`: its connection
+    /// is half-open or its client is stuck.
+    fn heartbeat(&mut self) -> Result<()> {
+        for player in 1..=2 {
+            let vacant = self
+                .vacant_seat
+                .as_ref()
+                .is_some_and(|seat| seat.player == player);
+            let conn = &mut self.players[player - 1];
+            if vacant || self.game_over {
+                continue;
+            }
+            if conn.last_heard.elapsed() > net::HEARTBEAT_TIMEOUT {
+                dashboard::log(format!(
+                    "{}'s connection was lost: no reply to pings for {}s",
+                    conn.label,
+                    net::HEARTBEAT_TIMEOUT.as_secs()
+                ));
+                conn.disconnect();
+                self.lose(player)?;
+            } else if conn.last_ping.elapsed() >= net::HEARTBEAT_INTERVAL {
+                conn.send_private(&Message::Ping)?;
+                conn.last_ping = Instant::now();
+                conn.ping_sent = Some(conn.last_ping);
+            }
+        }
+        Ok(())
+    }
+
+    /// Acts on whatever has come due: a turn running out, a reconnect window
+    /// closing or the play-again vote settling.
+    fn check_clocks(&mut self) -> Result<()> {
+        self.check_turn_clock()?;
+
+        if let Some(seat) = self.vacant_seat.as_ref()
+            && seat.since.elapsed() > RECONNECT_WINDOW
+        {
+            let player = seat.player;
+            dashboard::log(format!("{} did not reconnect in time", self.name(player)));
+            self.forfeit(3 - player)?;
+        }
+
+        match &self.play_again {
             PlayAgainState::WaitingForResponses { timeout_start, .. } => {
                 if timeout_start.elapsed() > Duration::from_secs(30) {
-                    println!("Play again timeout - no response from one or both players");
-                    play_again_state = PlayAgainState::Timeout;
+                    dashboard::log("Play again timeout - no response from one or both players");
+                    self.play_again = PlayAgainState::Timeout;
                 }
             }
             PlayAgainState::BothAgreed => {
-                println!("Both players want to play again! Starting new game...");
+                dashboard::log("Both players want to play again! Starting new game...");
 
                 // Reset game state
-                p1.grid = None;
-                p1.ready = false;
-                p2.grid = None;
-                p2.ready = false;
-                current_turn = 0;
-                play_again_state = PlayAgainState::None;
+                for conn in &mut self.players {
+                    conn.grid = None;
+                    conn.ready = false;
+                    conn.fogged = false;
+                    conn.held_result = None;
+                }
+                // Rematches alternate who fires first; a decided series starts over
+                self.first = 3 - self.first;
+                if self.series_decided() {
+                    self.wins = [0, 0];
+                }
+                self.current_turn = self.first - 1;
+                self.play_again = PlayAgainState::None;
+                self.phase = SessionPhase::Placement;
+                dashboard::update_board(1, None);
+                dashboard::update_board(2, None);
 
                 // Notify both players that new game is starting
-                let _ = writeln!(
-                    p1.stream,
-                    "{}",
-                    serde_json::to_string(&Message::NewGameStart)?
-                );
-                let _ = p1.stream.flush();
-                let _ = writeln!(
-                    p2.stream,
-                    "{}",
-                    serde_json::to_string(&Message::NewGameStart)?
-                );
-                let _ = p2.stream.flush();
-
-                println!("New game ready! Waiting for players to place ships...");
+                for conn in &mut self.players {
+                    conn.send(&Message::NewGameStart)?;
+                }
+
+                dashboard::log("New game ready! Waiting for players to place ships...");
+                self.observers.emit(GameEvent::NewGame);
             }
             PlayAgainState::OneDeclined => {
-                println!("One player declined to play again. Ending session.");
-                game_over = true;
+                dashboard::log("One player declined to play again. Ending session.");
+                self.game_over = true;
             }
             PlayAgainState::Timeout => {
-                println!("Play again timeout reached. Ending session.");
-                game_over = true;
+                dashboard::log("Play again timeout reached. Ending session.");
+                self.game_over = true;
             }
             PlayAgainState::None => {}
         }
+        Ok(())
+    }
+
+    /// Lets the last messages reach everyone, then closes every connection.
+    async fn close(self) {
+        for _ in 0..self.seated {
+            directory::player_left();
+        }
+        for conn in self.players {
+            conn.reader.abort();
+            conn.stream.writer.close().await;
+        }
+        self.observers.close().await;
+    }
+}
+
+/// The optional rules a game is played under.
+struct Rules {
+    /// Decides when stormy-seas events strike.
+    sea: Box<dyn EventGenerator>,
+    turn_limit: Option<TurnLimit>,
+    /// Mines each player lays with their fleet.
+    mines: usize,
+    /// Ships may not touch, and sinking one reveals the water around it.
+    reveal_sunk: bool,
+    /// Games in a series, if the pair plays one.
+    best_of: Option<u32>,
+}
+
+/// Runs game `id` between two players, each with their seat's session token,
+/// until it ends or the server shuts down. Observers, coaches and reconnecting
+/// players are handed over through `arrivals`.
+async fn run_game_session(
+    id: u64,
+    [(first, first_token), (second, second_token)]: [(Newcomer, String); 2],
+    coach_codes: [String; 2],
+    mut arrivals: mpsc::UnboundedReceiver<Newcomer>,
+    shutdown: Arc<Mutex<bool>>,
+    Rules {
+        sea,
+        turn_limit,
+        mines,
+        reveal_sunk,
+        best_of,
+    }: Rules,
+    timeouts: Timeouts,
+) -> Result<()> {
+    let addrs = [first.addr.clone(), second.addr.clone()];
+    let (inputs, mut incoming) = mpsc::unbounded_channel();
+    let mut session = Session {
+        id,
+        players: [
+            PlayerConnection::new(1, 1, first, timeouts.read, &inputs),
+            PlayerConnection::new(2, 2, second, timeouts.read, &inputs),
+        ],
+        addrs: addrs.clone(),
+        tokens: [first_token, second_token],
+        coach_codes,
+        seated: 2,
+        current_turn: 0,
+        first: rand::rng().random_range(1..=2),
+        best_of,
+        wins: [0, 0],
+        draw_offer: None,
+        pause: None,
+        phase: SessionPhase::Placement,
+        play_again: PlayAgainState::None,
+        observers: Observers::default(),
+        vacant_seat: None,
+        sea,
+        turn_limit,
+        mines,
+        reveal_sunk,
+        turn_started: Instant::now(),
+        turn_warned: false,
+        game_over: false,
+        inputs,
+        connections: 2,
+        timeouts,
+    };
+    dashboard::game_started(addrs);
+    for ((conn, token), code) in session
+        .players
+        .iter_mut()
+        .zip(&session.tokens)
+        .zip(&session.coach_codes)
+    {
+        conn.send_private(&Message::SessionToken {
+            token: token.clone(),
+        })?;
+        conn.send_private(&Message::CoachCode { code: code.clone() })?;
+    }
+    session.exchange_names()?;
+    session.toss_coin()?;
+
+    let mut stats = LoopStats::new(perf::SERVER_REPORT_INTERVAL);
+
+    while !session.game_over && !*shutdown.lock().unwrap() {
+        let wake = tokio::select! {
+            Some(input) = incoming.recv() => Wake::Input(input),
+            Some(newcomer) = arrivals.recv() => Wake::Arrival(Box::new(newcomer)),
+            _ = tokio::time::sleep(net::POLL_INTERVAL) => Wake::Tick,
+        };
+
+        let iteration_start = Instant::now();
+        match wake {
+            Wake::Input(input) => session.handle_input(input, &mut stats)?,
+            Wake::Arrival(newcomer) => session.arrive(*newcomer)?,
+            Wake::Tick => {}
+        }
+        session.coach()?;
+        session.observers.drop_departed();
+        if !session.game_over {
+            session.heartbeat()?;
+        }
+        if !session.game_over {
+            session.check_clocks()?;
+        }
 
-        tokio::time::sleep(Duration::from_millis(10)).await;
+        stats.work(iteration_start.elapsed());
+        stats.iteration();
+        if let Some(rates) = stats.roll()
+            && perf::enabled()
+        {
+            dashboard::log(perf::describe(&rates));
+        }
     }
 
-    println!("Game ended");
+    session.close().await;
+
+    dashboard::game_ended();
+    dashboard::log("Game ended");
     Ok(())
 }
@@ -1,272 +1,585 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use rand::Rng;
 use std::{
-    io::{BufRead, BufReader, Write},
-    net::TcpListener,
+    net::SocketAddr,
+    str::FromStr,
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use crate::game_state::GameState;
-use crate::types::{CellState, GRID_SIZE, Message, SHIPS};
+use crate::ai::AiStrategy;
+use crate::ai_audit::{AiView, AuditLog};
+use crate::codec;
+use crate::engine::Board;
+use crate::net::{self, Frame, FrameReader, Penalty, RateLimit, TimedWriter, Timeouts, Transport};
+use crate::perf::{self, LoopStats};
+use crate::types::{CellState, ErrorCode, Message, ReviewShot, SHIPS, SunkShip};
 
-pub async fn run_server_ai(port: &str) -> Result<()> {
-    let listener = TcpListener::bind(format!("0.0.0.0:{}", port))?;
-    listener.set_nonblocking(true)?;
-    println!("🤖 AI Battleship Server listening on port {}", port);
-
-    let shutdown = Arc::new(Mutex::new(false));
-    let shutdown_flag = shutdown.clone();
-    tokio::spawn(async move {
-        let _ = tokio::signal::ctrl_c().await;
-        *shutdown_flag.lock().unwrap() = true;
-        println!("\nShutting down AI server...");
-    });
-
-    // Accept one client and play against it
-    let (mut stream, addr) = loop {
-        if *shutdown.lock().unwrap() {
-            return Ok(());
-        }
-        match listener.accept() {
-            Ok((s, a)) => {
-                s.set_nonblocking(true)?;
-                break (s, a);
-            }
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                tokio::time::sleep(Duration::from_millis(50)).await;
-            }
-            Err(e) => {
-                eprintln!("Accept error: {}", e);
-                tokio::time::sleep(Duration::from_millis(200)).await;
+/// Runs the fairness check for a finished game and reports the outcome.
+fn finish_audit(audit: &mut Option<AuditLog>, player_grid: &[Vec<CellState>]) {
+    if let Some(audit) = audit.as_mut() {
+        let violations = audit.finish_game(player_grid);
+        if violations.is_empty() {
+            println!("AI audit verified: no hidden ship cells were consulted");
+        } else {
+            println!("AI audit FAILED with {} violation(s):", violations.len());
+            for violation in violations {
+                println!("  {}", violation);
             }
         }
-    };
-    println!("Client connected: {}", addr);
-
-    let mut reader = BufReader::new(stream.try_clone()?);
+    }
+}
 
-    // Generate AI's board
-    let mut ai_grid = vec![vec![CellState::Empty; GRID_SIZE]; GRID_SIZE];
-    let mut rng = rand::rng();
+/// How long the AI takes over each shot: a random time between `min` and
+/// `max`, so its answer does not land the instant the player fires.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThinkTime {
+    pub min: Duration,
+    pub max: Duration,
+}
 
-    for (len, _name) in SHIPS {
-        'place: loop {
-            let x = rng.random_range(0..GRID_SIZE);
-            let y = rng.random_range(0..GRID_SIZE);
-            let horiz = rng.random_bool(0.5);
+impl ThinkTime {
+    fn pick(self) -> Duration {
+        rand::rng().random_range(self.min..=self.max)
+    }
+}
 
-            let can = if horiz {
-                if x + len > GRID_SIZE {
-                    false
-                } else {
-                    (0..len).all(|i| ai_grid[y][x + i] == CellState::Empty)
-                }
-            } else if y + len > GRID_SIZE {
-                false
-            } else {
-                (0..len).all(|i| ai_grid[y + i][x] == CellState::Empty)
-            };
+impl FromStr for ThinkTime {
+    type Err = anyhow::Error;
 
-            if can {
-                if horiz {
-                    for i in 0..len {
-                        ai_grid[y][x + i] = CellState::Ship;
-                    }
-                } else {
-                    for i in 0..len {
-                        ai_grid[y + i][x] = CellState::Ship;
-                    }
-                }
-                break 'place;
+    /// Milliseconds, either a range like "500-1500" or one number.
+    fn from_str(s: &str) -> Result<Self> {
+        let (min, max) = s.split_once('-').unwrap_or((s, s));
+        let millis = |n: &str| -> Result<Duration> {
+            match n.trim().parse() {
+                Ok(ms) => Ok(Duration::from_millis(ms)),
+                Err(_) => bail!("'{}' is not a time in milliseconds, e.g. 500-1500", s),
             }
+        };
+        let (min, max) = (millis(min)?, millis(max)?);
+        if min > max {
+            bail!("thinking time {} runs backwards", s);
         }
+        Ok(Self { min, max })
     }
+}
 
-    let mut player_grid: Option<Vec<Vec<CellState>>> = None;
-    let mut ai_fired = vec![vec![false; GRID_SIZE]; GRID_SIZE];
+/// What the AI does with one message from its player.
+pub enum Reply {
+    /// Messages for the player, in order.
+    Send(Vec<Message>),
+    /// Messages for the player now, and more after `delay`: the AI's shot
+    /// and what follows it, held back while the AI "thinks".
+    Think {
+        now: Vec<Message>,
+        delay: Duration,
+        then: Vec<Message>,
+    },
+    /// Turn the message away with this error; the game goes on.
+    Reject(ErrorCode, String),
+    /// The player is done and the session is over.
+    End,
+}
 
-    let mut line = String::new();
-    loop {
-        if *shutdown.lock().unwrap() {
-            break;
-        }
+/// The AI's side of a match against one player, game after game. The AI
+/// server plays it over the network, `play-ai` inside the client itself.
+pub struct AiGame {
+    ai_grid: Board,
+    player_grid: Option<Board>,
+    ai_view: AiView,
+    strategy: Box<dyn AiStrategy>,
+    think_time: Option<ThinkTime>,
+    /// Every shot of this game so far, for the `GameReview` at its end.
+    shots: Vec<ReviewShot>,
+    game_over: bool,
+    audit: Option<AuditLog>,
+    /// Whether to log progress to stdout, as the AI server does.
+    verbose: bool,
+}
 
-        line.clear();
-        match reader.read_line(&mut line) {
-            Ok(0) => break,
-            Ok(_) => {
-                if let Ok(msg) = serde_json::from_str::<Message>(&line) {
-                    match msg {
-                        Message::Attack { x, y } => {
-                            // Player fired at AI
-                            let hit = ai_grid[y][x] == CellState::Ship;
-                            if hit {
-                                ai_grid[y][x] = CellState::Hit;
-                            }
-                            let sunk = if hit {
-                                GameState::is_ship_sunk_at(&ai_grid, x, y)
-                            } else {
-                                false
-                            };
+impl AiGame {
+    pub fn new(
+        mut strategy: Box<dyn AiStrategy>,
+        think_time: Option<ThinkTime>,
+        audit: Option<AuditLog>,
+        verbose: bool,
+    ) -> Result<Self> {
+        Ok(Self {
+            ai_grid: strategy.place_ships(&mut rand::rng())?,
+            player_grid: None,
+            ai_view: AiView::new(),
+            strategy,
+            think_time,
+            shots: Vec::new(),
+            game_over: false,
+            audit,
+            verbose,
+        })
+    }
 
-                            let reply = Message::AttackResult { x, y, hit, sunk };
-                            writeln!(stream, "{}", serde_json::to_string(&reply)?)?;
+    fn log(&self, line: &str) {
+        if self.verbose {
+            println!("{}", line);
+        }
+    }
 
-                            // Check if all AI ships are sunk
-                            if GameState::all_ships_sunk(&ai_grid) {
-                                writeln!(
-                                    stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::GameOver { won: true })?
-                                )?;
-                                println!("Player wins!");
+    /// Plays the AI's answer to `msg`. Fails only if the audit log cannot be
+    /// written or the strategy fails, e.g. an external bot that has exited.
+    pub fn handle(&mut self, msg: Message) -> Result<Reply> {
+        let reply = match msg {
+            Message::Attack { .. } if self.player_grid.is_none() || self.game_over => {
+                Reply::Reject(
+                    ErrorCode::WrongPhase,
+                    "Attack is only allowed during battle".to_string(),
+                )
+            }
+            Message::Attack { x, y } => return self.attack(x, y),
+            Message::PlaceShips(_) if self.player_grid.is_some() => {
+                Reply::Reject(ErrorCode::WrongPhase, "ships already placed".to_string())
+            }
+            Message::PlaceShips(client_grid) => {
+                if let Err(detail) = client_grid.check_fleet(0) {
+                    return Ok(Reply::Reject(ErrorCode::InvalidPlacement, detail));
+                }
+                self.player_grid = Some(client_grid);
+                self.log("Game started!");
+                Reply::Send(vec![Message::GameStart, Message::YourTurn])
+            }
+            Message::PlayAgainResponse { .. } if !self.game_over => Reply::Reject(
+                ErrorCode::WrongPhase,
+                "PlayAgainResponse is only allowed after a game".to_string(),
+            ),
+            Message::PlayAgainResponse {
+                wants_to_play: true,
+            } => {
+                self.log("Player wants to play again! Starting new game...");
+                // A fresh fleet, and no memory of the player's last board
+                self.ai_grid = self.strategy.place_ships(&mut rand::rng())?;
+                self.ai_view = AiView::new();
+                self.player_grid = None;
+                self.shots.clear();
+                self.game_over = false;
+                self.log("New game ready! Waiting for player to place ships...");
+                Reply::Send(vec![Message::NewGameStart])
+            }
+            Message::PlayAgainResponse {
+                wants_to_play: false,
+            } => {
+                self.log("Player doesn't want to play again. Ending session.");
+                Reply::End
+            }
+            Message::Quit => {
+                self.log("Player quit the game");
+                Reply::End
+            }
+            Message::Emote(_) | Message::Chat { .. } => Reply::Send(Vec::new()),
+            Message::Hello { .. } => Reply::Reject(
+                ErrorCode::WrongPhase,
+                "Hello was already received".to_string(),
+            ),
+            other => Reply::Reject(
+                ErrorCode::UnexpectedMessage,
+                format!("{} is not supported by the AI server", other.name()),
+            ),
+        };
+        Ok(reply)
+    }
 
-                                // Ask if player wants to play again
-                                writeln!(
-                                    stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::PlayAgainRequest)?
-                                )?;
-                                println!("Asking player if they want to play again...");
-                                continue;
-                            }
+    /// The player fires at (x, y); unless that wins them the game, the AI
+    /// fires straight back.
+    fn attack(&mut self, x: usize, y: usize) -> Result<Reply> {
+        if let Err(detail) = self.ai_grid.check_target(x, y) {
+            return Ok(Reply::Reject(ErrorCode::InvalidTarget, detail));
+        }
+        let shot = self.ai_grid.fire(x, y);
+        self.shots.push(ReviewShot {
+            yours: true,
+            x,
+            y,
+            hit: shot.hit,
+            sunk: shot.sunk,
+        });
+        let mut replies = vec![Message::AttackResult {
+            x,
+            y,
+            hit: shot.hit,
+            sunk: shot.sunk,
+            ship: SunkShip::from_shot(&shot),
+            revealed: Vec::new(),
+        }];
+        let won = self.ai_grid.all_sunk();
+        // What follows the AI's own shot waits out its thinking time
+        let mut then = Vec::new();
+        if !won && let Some(grid) = self.player_grid.as_mut() {
+            replies.push(Message::OpponentTurn);
+            let (sx, sy) = self.strategy.choose_shot(&self.ai_view, &mut rand::rng())?;
+            if let Some(audit) = self.audit.as_mut() {
+                audit.record(&self.ai_view, (sx, sy))?;
+            }
+            let shot = grid.fire(sx, sy);
+            self.ai_view.record_shot(sx, sy, shot.hit);
+            self.shots.push(ReviewShot {
+                yours: false,
+                x: sx,
+                y: sy,
+                hit: shot.hit,
+                sunk: shot.sunk,
+            });
+            let sunk = shot.ship.filter(|_| shot.sunk).map(|kind| SHIPS[kind].0);
+            self.strategy.record_shot(sx, sy, shot.hit, sunk)?;
+            then.push(Message::Attack { x: sx, y: sy });
+            if !grid.all_sunk() {
+                then.push(Message::YourTurn);
+                return Ok(self.after_thinking(replies, then));
+            }
+        }
 
-                            // AI's turn
-                            if let Some(grid) = player_grid.as_mut() {
-                                writeln!(
-                                    stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::OpponentTurn)?
-                                )?;
+        self.game_over = true;
+        let end = if won { &mut replies } else { &mut then };
+        end.push(Message::GameOver { won });
+        if let Some(grid) = self.player_grid.as_ref() {
+            end.push(Message::GameReview {
+                your_board: grid.clone(),
+                opponent_board: self.ai_grid.clone(),
+                shots: std::mem::take(&mut self.shots),
+            });
+        }
+        end.push(Message::PlayAgainRequest);
+        self.log(if won { "Player wins!" } else { "AI wins!" });
+        if let Some(grid) = self.player_grid.as_ref() {
+            finish_audit(&mut self.audit, grid);
+        }
+        self.log("Asking player if they want to play again...");
+        Ok(self.after_thinking(replies, then))
+    }
 
-                                // Find untargeted cell
-                                let (sx, sy) = loop {
-                                    let sx = rng.random_range(0..GRID_SIZE);
-                                    let sy = rng.random_range(0..GRID_SIZE);
-                                    if !ai_fired[sy][sx] {
-                                        break (sx, sy);
-                                    }
-                                };
-                                ai_fired[sy][sx] = true;
+    /// Sends `now`, then `then` once the AI has taken its thinking time, if
+    /// it has one and `then` holds its shot.
+    fn after_thinking(&self, mut now: Vec<Message>, then: Vec<Message>) -> Reply {
+        match self.think_time {
+            Some(think_time) if !then.is_empty() => {
+                now.push(Message::OpponentThinking);
+                Reply::Think {
+                    now,
+                    delay: think_time.pick(),
+                    then,
+                }
+            }
+            _ => {
+                now.extend(then);
+                Reply::Send(now)
+            }
+        }
+    }
+}
 
-                                let ai_hit = grid[sy][sx] == CellState::Ship;
-                                if ai_hit {
-                                    grid[sy][sx] = CellState::Hit;
-                                } else {
-                                    grid[sy][sx] = CellState::Miss;
-                                }
+/// Tells the player why their message was turned away.
+fn reject(stream: &mut TimedWriter, code: ErrorCode, detail: String) -> Result<()> {
+    println!("Rejected message: {}", detail);
+    stream.send(&Message::Error { code, detail })?;
+    Ok(())
+}
 
-                                // Send attack to client
-                                writeln!(
-                                    stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::Attack { x: sx, y: sy })?
-                                )?;
+/// Turns away an invalid message and counts it against the player, with the
+/// two-player server's penalties: a warning, then a pause in reading their
+/// messages, and finally a disconnect. Returns false once they are to be
+/// disconnected.
+async fn reject_invalid(
+    stream: &mut TimedWriter,
+    violations: &mut u32,
+    code: ErrorCode,
+    detail: String,
+) -> Result<bool> {
+    reject(stream, code, detail)?;
+    *violations += 1;
+    let Some((penalty, detail)) = net::penalty(*violations) else {
+        return Ok(true);
+    };
+    stream.send(&Message::Error {
+        code: ErrorCode::Conduct,
+        detail,
+    })?;
+    match penalty {
+        Penalty::Warn => {}
+        Penalty::Throttle => {
+            println!(
+                "Throttling the player for {}s after {} invalid messages",
+                net::THROTTLE_DELAY.as_secs(),
+                violations
+            );
+            tokio::time::sleep(net::THROTTLE_DELAY).await;
+        }
+        Penalty::Disconnect => {
+            println!(
+                "Disconnecting the player after {} invalid messages",
+                violations
+            );
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
 
-                                // Check if player lost
-                                if GameState::all_ships_sunk(grid) {
-                                    writeln!(
-                                        stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::GameOver { won: false })?
-                                    )?;
-                                    println!("AI wins!");
+/// Runs `work` on `game` on a blocking thread: an external bot can take
+/// seconds to answer, and must not hold up the async runtime meanwhile.
+async fn off_runtime<T: Send + 'static>(
+    mut game: AiGame,
+    work: impl FnOnce(&mut AiGame) -> T + Send + 'static,
+) -> Result<(AiGame, T)> {
+    Ok(tokio::task::spawn_blocking(move || {
+        let out = work(&mut game);
+        (game, out)
+    })
+    .await?)
+}
 
-                                    // Ask if player wants to play again
-                                    writeln!(
-                                        stream,
-                                        "{}",
-                                        serde_json::to_string(&Message::PlayAgainRequest)?
-                                    )?;
-                                    println!("Asking player if they want to play again...");
-                                    continue;
-                                }
+/// How long a new connection has to send its `Hello` on a server with a password.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
 
-                                // Back to player's turn
-                                writeln!(stream, "{}", serde_json::to_string(&Message::YourTurn)?)?;
-                            }
-                        }
-                        Message::PlaceShips(client_grid) => {
-                            player_grid = Some(client_grid);
-                            writeln!(stream, "{}", serde_json::to_string(&Message::GameStart)?)?;
-                            writeln!(stream, "{}", serde_json::to_string(&Message::YourTurn)?)?;
-                            println!("Game started!");
-                        }
-                        Message::PlayAgainResponse { wants_to_play } => {
-                            if wants_to_play {
-                                println!("Player wants to play again! Starting new game...");
+/// Reads the `Hello` of a new connection and checks its password, so a
+/// stranger cannot take the game. Returns the `Hello` for the game loop to
+/// answer, or turns the connection away with the reason.
+async fn authenticate(
+    frames: &mut FrameReader,
+    writer: &mut TimedWriter,
+    password: &str,
+) -> Result<Frame, &'static str> {
+    let reason = match tokio::time::timeout(HELLO_TIMEOUT, frames.next_frame()).await {
+        Ok(Ok(Some(frame))) => match frame.decode() {
+            Ok(msg) => match msg.check_password(Some(password)) {
+                Ok(()) => return Ok(frame),
+                Err(reason) => reason,
+            },
+            Err(_) => "this server needs a password",
+        },
+        _ => "this server needs a password",
+    };
+    let _ = writer.send(&Message::Error {
+        code: ErrorCode::Unauthorized,
+        detail: reason.to_string(),
+    });
+    writer.shutdown();
+    Err(reason)
+}
 
-                                // Reset AI's board
-                                ai_grid = vec![vec![CellState::Empty; GRID_SIZE]; GRID_SIZE];
-                                for (len, _name) in SHIPS {
-                                    'place: loop {
-                                        let x = rng.random_range(0..GRID_SIZE);
-                                        let y = rng.random_range(0..GRID_SIZE);
-                                        let horiz = rng.random_bool(0.5);
+pub async fn run_server_ai(
+    addr: SocketAddr,
+    strategy: Box<dyn AiStrategy>,
+    think_time: Option<ThinkTime>,
+    audit_log: Option<&str>,
+    password: Option<&str>,
+    transport: Transport,
+    timeouts: Timeouts,
+) -> Result<()> {
+    let listener = net::listen(addr)?;
+    println!(
+        "🤖 AI Battleship Server listening on {} ({})",
+        addr,
+        transport.describe()
+    );
 
-                                        let can = if horiz {
-                                            if x + len > GRID_SIZE {
-                                                false
-                                            } else {
-                                                (0..len)
-                                                    .all(|i| ai_grid[y][x + i] == CellState::Empty)
-                                            }
-                                        } else if y + len > GRID_SIZE {
-                                            false
-                                        } else {
-                                            (0..len).all(|i| ai_grid[y + i][x] == CellState::Empty)
-                                        };
+    let audit = audit_log.map(AuditLog::open).transpose()?;
+    if let Some(path) = audit_log {
+        println!("Recording AI decisions to {}", path);
+    }
 
-                                        if can {
-                                            if horiz {
-                                                for i in 0..len {
-                                                    ai_grid[y][x + i] = CellState::Ship;
-                                                }
-                                            } else {
-                                                for i in 0..len {
-                                                    ai_grid[y + i][x] = CellState::Ship;
-                                                }
-                                            }
-                                            break 'place;
-                                        }
-                                    }
-                                }
+    // The AI places its first fleet now, so a bot that cannot stops the
+    // server before anyone connects
+    let mut game =
+        tokio::task::spawn_blocking(move || AiGame::new(strategy, think_time, audit, true))
+            .await??;
 
-                                // Reset AI's firing grid
-                                ai_fired = vec![vec![false; GRID_SIZE]; GRID_SIZE];
+    let shutdown = Arc::new(Mutex::new(false));
+    let shutdown_flag = shutdown.clone();
+    tokio::spawn(async move {
+        let _ = tokio::signal::ctrl_c().await;
+        *shutdown_flag.lock().unwrap() = true;
+        println!("\nShutting down AI server...");
+    });
 
-                                // Reset player grid
-                                player_grid = None;
+    // Accept one client and play against it
+    let waiting_since = Instant::now();
+    // A Hello already read to check the password
+    let mut pending = None;
+    let (mut frames, mut stream) = loop {
+        if *shutdown.lock().unwrap() {
+            return Ok(());
+        }
+        if timeouts.accept_expired(waiting_since) {
+            anyhow::bail!(
+                "timed out after {}s waiting for a player to connect",
+                waiting_since.elapsed().as_secs()
+            );
+        }
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    eprintln!("Accept error: {}", e);
+                    continue;
+                }
+            },
+            _ = tokio::time::sleep(net::POLL_INTERVAL) => continue,
+        };
+        let (mut frames, mut writer) = match transport.open(stream, &timeouts).await {
+            Ok(halves) => halves,
+            Err(e) => {
+                eprintln!("Dropped {}: handshake failed: {}", addr, e);
+                continue;
+            }
+        };
+        if let Some(password) = password {
+            match authenticate(&mut frames, &mut writer, password).await {
+                Ok(hello) => pending = Some(hello),
+                Err(reason) => {
+                    println!("Refused {}: {}", addr, reason);
+                    continue;
+                }
+            }
+        }
+        println!("Client connected: {}", addr);
+        break (frames, writer);
+    };
+    let mut last_seen = Instant::now();
+    let mut rate = RateLimit::default();
+    let mut violations = 0;
+    // Why the AI gave up mid-game, e.g. an external bot that exited
+    let mut failure = None;
+    let mut greeted = false;
 
-                                // Notify client that new game is starting
-                                let _ = writeln!(
-                                    stream,
-                                    "{}",
-                                    serde_json::to_string(&Message::NewGameStart)?
-                                );
+    let mut stats = LoopStats::new(perf::SERVER_REPORT_INTERVAL);
+    loop {
+        if *shutdown.lock().unwrap() {
+            break;
+        }
+        stats.iteration();
+        if let Some(rates) = stats.roll()
+            && perf::enabled()
+        {
+            println!("{}", perf::describe(&rates));
+        }
 
-                                println!("New game ready! Waiting for player to place ships...");
-                            } else {
-                                println!("Player doesn't want to play again. Ending session.");
-                                break;
-                            }
+        let read = match pending.take() {
+            Some(hello) => Some(Ok(Some(hello))),
+            None => tokio::select! {
+                frame = frames.next_frame() => Some(frame),
+                _ = tokio::time::sleep(net::POLL_INTERVAL) => None,
+            },
+        };
+        let iteration_start = Instant::now();
+        match read {
+            Some(Ok(None)) => break,
+            Some(Ok(Some(frame))) => {
+                last_seen = Instant::now();
+                stats.message();
+                if !rate.allow() {
+                    // Nobody else is waiting on the game, so a flood simply ends it
+                    let detail = format!(
+                        "disconnected for sending more than {} messages a second",
+                        net::MESSAGE_RATE
+                    );
+                    reject(&mut stream, ErrorCode::Conduct, detail)?;
+                    break;
+                }
+                match frame.decode() {
+                    Err(e) => {
+                        let detail = format!("malformed message: {}", e);
+                        if !reject_invalid(
+                            &mut stream,
+                            &mut violations,
+                            ErrorCode::Malformed,
+                            detail,
+                        )
+                        .await?
+                        {
+                            break;
                         }
-                        Message::Quit => {
-                            println!("Player quit the game");
+                    }
+                    Ok(msg) if !greeted => {
+                        if let Err(detail) = msg.check_hello() {
+                            reject(&mut stream, ErrorCode::IncompatibleVersion, detail)?;
                             break;
                         }
-                        _ => {}
+                        greeted = true;
+                        let (agreed, deflate) = match &msg {
+                            Message::Hello {
+                                codec, compression, ..
+                            } => (
+                                codec.as_deref().and_then(codec::named),
+                                compression.as_deref() == Some(codec::DEFLATE),
+                            ),
+                            _ => (None, false),
+                        };
+                        stream.send(&Message::hello(
+                            None,
+                            agreed.map(|c| c.name()),
+                            deflate.then_some(codec::DEFLATE),
+                            None,
+                        ))?;
+                        if let Some(codec) = agreed {
+                            stream.set_codec(codec);
+                        }
+                        if deflate {
+                            stream.set_deflate();
+                        }
+                    }
+                    Ok(msg) => {
+                        let (returned, reply) =
+                            off_runtime(game, move |game| game.handle(msg)).await?;
+                        game = returned;
+                        let reply = match reply {
+                            Ok(reply) => reply,
+                            Err(e) => {
+                                println!("The AI failed: {:#}", e);
+                                // The player sees their opponent leave, not a dead connection
+                                stream.send(&Message::OpponentQuit)?;
+                                failure = Some(e);
+                                break;
+                            }
+                        };
+                        match reply {
+                            Reply::Send(replies) => {
+                                for reply in &replies {
+                                    stream.send(reply)?;
+                                }
+                            }
+                            Reply::Think { now, delay, then } => {
+                                for reply in &now {
+                                    stream.send(reply)?;
+                                }
+                                tokio::time::sleep(delay).await;
+                                for reply in &then {
+                                    stream.send(reply)?;
+                                }
+                            }
+                            Reply::Reject(code, detail) => {
+                                if !reject_invalid(&mut stream, &mut violations, code, detail)
+                                    .await?
+                                {
+                                    break;
+                                }
+                            }
+                            Reply::End => break,
+                        }
                     }
                 }
             }
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                tokio::time::sleep(Duration::from_millis(25)).await;
+            None => {
+                if timeouts.read_expired(last_seen) {
+                    println!(
+                        "Player timed out: no data for {}s",
+                        last_seen.elapsed().as_secs()
+                    );
+                    break;
+                }
             }
-            Err(_) => break,
+            Some(Err(_)) => break,
         }
+        stats.work(iteration_start.elapsed());
     }
 
+    // Let the last replies reach the player before the connection closes
+    stream.close().await;
     println!("Game ended");
-    Ok(())
+    failure.map_or(Ok(()), Err)
 }
@@ -1,15 +1,25 @@
 use anyhow::Result;
-use std::{
-    net::TcpListener,
-    sync::{Arc, Mutex},
-    time::Duration,
-};
-
-pub async fn run_server_relay(port: &str) -> Result<()> {
-    let listener = TcpListener::bind(format!("0.0.0.0:{}", port))?;
-    listener.set_nonblocking(true)?;
-    println!("🔀 Relay Battleship Server listening on port {}", port);
-    println!("This server hosts games between two remote players.\n");
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use crate::dashboard;
+use crate::net::{self, Timeouts, Transport};
+use crate::server::{self, Hosting};
+use crate::weather::CalmSeas;
+
+pub async fn run_server_relay(
+    addr: SocketAddr,
+    password: Option<String>,
+    transport: Transport,
+    timeouts: Timeouts,
+) -> Result<()> {
+    let listener = net::listen(addr)?;
+    dashboard::log(format!(
+        "🔀 Relay Battleship Server listening on {} ({})",
+        addr,
+        transport.describe()
+    ));
+    dashboard::log("This server hosts games between pairs of remote players.\n");
 
     let shutdown = Arc::new(Mutex::new(false));
     let shutdown_flag = shutdown.clone();
@@ -17,34 +27,22 @@ pub async fn run_server_relay(port: &str) -> Result<()> {
     tokio::spawn(async move {
         let _ = tokio::signal::ctrl_c().await;
         *shutdown_flag.lock().unwrap() = true;
-        println!("\nShutting down relay server...");
+        dashboard::log("\nShutting down relay server...");
     });
 
-    // Wait for two players
-    let mut players = Vec::new();
-
-    while players.len() < 2 {
-        if *shutdown.lock().unwrap() {
-            return Ok(());
-        }
-
-        match listener.accept() {
-            Ok((stream, addr)) => {
-                stream.set_nonblocking(true)?;
-                println!("Player {} connected: {}", players.len() + 1, addr);
-                players.push(stream);
-            }
-            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                tokio::time::sleep(Duration::from_millis(50)).await;
-            }
-            Err(e) => {
-                eprintln!("Accept error: {}", e);
-            }
-        }
-    }
-
-    println!("\n2 players connected! Starting game...\n");
-
-    // Just use the regular server logic
-    crate::server::run_game_session(players.remove(0), players.remove(0), shutdown).await
+    // Every pair gets a session of its own, as many as connect
+    let hosting = Hosting {
+        max_games: None,
+        max_connections: None,
+        sea: || Box::new(CalmSeas),
+        turn_limit: None,
+        mines: 0,
+        reveal_sunk: false,
+        best_of: None,
+        backfill: None,
+        password,
+        transport,
+        timeouts,
+    };
+    server::serve(&listener, shutdown, hosting).await
 }
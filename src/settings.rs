@@ -0,0 +1,87 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Client-side preferences that change how the game is played or shown.
+/// Loaded from a JSON settings file; command-line flags override it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Display name sent to the server and shown to the opponent.
+    pub name: Option<String>,
+    /// Require a second Enter on the same cell before firing.
+    pub confirm_fire: bool,
+    /// Custom ship names in fleet order; missing or blank entries keep the default name.
+    pub ship_names: Vec<String>,
+    /// Turn off decorative animation such as the moving water.
+    pub reduced_motion: bool,
+    /// Which events alert the player, and how.
+    pub notifications: Notifications,
+}
+
+/// Per-event alert preferences.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Notifications {
+    pub turn_start: Alerts,
+    pub hit_received: Alerts,
+    /// A ship sunk on either side.
+    pub sink: Alerts,
+    /// Chat messages and emotes from the opponent.
+    pub chat: Alerts,
+    pub game_over: Alerts,
+}
+
+/// The ways a single event can get the player's attention.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Alerts {
+    /// Ring the terminal bell.
+    pub bell: bool,
+    /// Show a desktop notification (notify-send on Linux, osascript on macOS).
+    pub desktop: bool,
+    /// Flash the event in the terminal window title.
+    pub title: bool,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        let bell = Alerts {
+            bell: true,
+            ..Alerts::default()
+        };
+        Self {
+            turn_start: bell,
+            hit_received: Alerts::default(),
+            sink: Alerts::default(),
+            chat: Alerts::default(),
+            game_over: bell,
+        }
+    }
+}
+
+impl Settings {
+    /// `$XDG_CONFIG_HOME/battleship-rs/settings.json`, falling back to `~/.config`.
+    pub fn default_path() -> Option<PathBuf> {
+        let config = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config.join("battleship-rs").join("settings.json"))
+    }
+
+    /// Reads `path`, or the default path if none is given. A missing default
+    /// file yields the default settings; a missing explicit file is an error.
+    pub fn load(path: Option<&str>) -> Result<Self> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("could not read settings file {}", path.display()))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("invalid settings file {}", path.display()))
+    }
+}
@@ -0,0 +1,88 @@
+use anyhow::Result;
+// ratatui's own crossterm, so its colours convert directly
+use ratatui::crossterm::{
+    queue,
+    style::{Attribute, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor},
+};
+use ratatui::{
+    buffer::{Buffer, Cell},
+    style::Modifier,
+};
+use std::{
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use unicode_width::UnicodeWidthStr;
+
+/// File format for a screen snapshot.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SnapshotFormat {
+    /// Plain text, for bug reports.
+    Text,
+    /// Text with ANSI colour codes; `cat` it in a terminal to see the screen.
+    Ansi,
+}
+
+/// Writes the rendered frame to `battleship-<timestamp>.txt` (or `.ans`) in the
+/// current directory and returns the path.
+pub fn save(buffer: &Buffer, format: SnapshotFormat) -> Result<PathBuf> {
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let extension = match format {
+        SnapshotFormat::Text => "txt",
+        SnapshotFormat::Ansi => "ans",
+    };
+    let path = PathBuf::from(format!("battleship-{}.{}", timestamp, extension));
+    std::fs::write(&path, render(buffer, format)?)?;
+    Ok(path)
+}
+
+fn render(buffer: &Buffer, format: SnapshotFormat) -> Result<Vec<u8>> {
+    let area = buffer.area;
+    let mut out = Vec::new();
+    for y in area.top()..area.bottom() {
+        let mut line = Vec::new();
+        let mut skip = 0;
+        let mut last_style = None;
+        for x in area.left()..area.right() {
+            // Cells covered by the previous wide character hold filler
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            let cell = &buffer[(x, y)];
+            if format == SnapshotFormat::Ansi {
+                let style = (cell.fg, cell.bg, cell.modifier);
+                if last_style != Some(style) {
+                    write_style(&mut line, cell)?;
+                    last_style = Some(style);
+                }
+            }
+            line.extend_from_slice(cell.symbol().as_bytes());
+            skip = cell.symbol().width().saturating_sub(1);
+        }
+        if format == SnapshotFormat::Ansi {
+            queue!(line, ResetColor, SetAttribute(Attribute::Reset))?;
+        } else {
+            line.truncate(line.trim_ascii_end().len());
+        }
+        out.extend_from_slice(&line);
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+fn write_style(out: &mut Vec<u8>, cell: &Cell) -> Result<()> {
+    queue!(
+        out,
+        SetAttribute(Attribute::Reset),
+        SetForegroundColor(cell.fg.into()),
+        SetBackgroundColor(cell.bg.into())
+    )?;
+    if cell.modifier.contains(Modifier::BOLD) {
+        queue!(out, SetAttribute(Attribute::Bold))?;
+    }
+    if cell.modifier.contains(Modifier::CROSSED_OUT) {
+        queue!(out, SetAttribute(Attribute::CrossedOut))?;
+    }
+    Ok(())
+}
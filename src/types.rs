@@ -1,6 +1,20 @@
+use crate::engine::{Board, Shot};
 use serde::{Deserialize, Serialize};
 
+/// Version of the message protocol, exchanged in `Hello` when a client
+/// connects. Bump it whenever old and new builds could misread each other:
+/// a message or event added, removed or reshaped, or a new rule about what
+/// may be sent when. A new optional field that the other side can ignore
+/// does not need it. Version 3 added `Observe`, moved fleets from
+/// `ShipsPlaced` to `FleetRevealed` and made `Hello` the required first frame.
+pub const PROTOCOL_VERSION: u32 = 3;
 pub const GRID_SIZE: usize = 10;
+/// Longest coaching hint the server forwards, in characters.
+pub const MAX_HINT_LEN: usize = 200;
+/// Longest chat line the server relays, in characters.
+pub const MAX_CHAT_LEN: usize = 120;
+/// Longest display name the server accepts, in characters.
+pub const MAX_NAME_LEN: usize = 16;
 pub const SHIPS: [(usize, &str); 5] = [
     (5, "Carrier"),
     (4, "Battleship"),
@@ -8,6 +22,8 @@ pub const SHIPS: [(usize, &str); 5] = [
     (3, "Submarine"),
     (2, "Destroyer"),
 ];
+/// Mines each player lays with their fleet on a server started with `--mines`.
+pub const MINES: usize = 2;
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum CellState {
@@ -15,11 +31,63 @@ pub enum CellState {
     Ship,
     Hit,
     Miss,
+    /// A hidden mine under `--mines`. Only ever on the owner's own board.
+    Mine,
+}
+
+/// Why the server rejected a message, so clients can react without parsing
+/// the human-readable detail.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ErrorCode {
+    /// The line was not a valid JSON message.
+    Malformed,
+    /// A message only the server sends.
+    UnexpectedMessage,
+    /// A valid message sent in a phase that does not accept it.
+    WrongPhase,
+    /// An attack sent during the opponent's turn.
+    NotYourTurn,
+    /// A fleet that breaks the placement rules.
+    InvalidPlacement,
+    /// An attack off the board or at a cell already fired at.
+    InvalidTarget,
+    /// A warning, throttle or disconnect after repeated invalid messages.
+    Conduct,
+    /// The server has no room for another connection.
+    ServerFull,
+    /// The client did not open with a `Hello` in the server's protocol version.
+    IncompatibleVersion,
+    /// No open room or running game has the code given in `JoinRoom`, no
+    /// running game is the one named in `Observe`, or none issued the code
+    /// given in `Coach`.
+    RoomNotFound,
+    /// The game behind a `ResumeGame` token is over or cannot be resumed now.
+    SessionExpired,
+    /// The server has a password and the `Hello` did not carry it.
+    Unauthorized,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Message {
-    PlaceShips(Vec<Vec<CellState>>),
+    /// The first message on every client connection, answered by the server
+    /// with its own. Observers and coaches send it too.
+    Hello {
+        protocol_version: u32,
+        player_name: Option<String>,
+        /// From a client, the codec it would rather use than JSON; from the
+        /// server, the codec it agreed to. See `codec`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        codec: Option<String>,
+        /// From a client, the compression it would like, `deflate`; from the
+        /// server, the compression it agreed to. See `codec`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        compression: Option<String>,
+        /// The server's password, from a client joining a server started
+        /// with `--password`. Sent in the clear unless the connection is TLS.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        password: Option<String>,
+    },
+    PlaceShips(Board),
     Attack {
         x: usize,
         y: usize,
@@ -29,6 +97,13 @@ pub enum Message {
         y: usize,
         hit: bool,
         sunk: bool,
+        /// The ship the shot sank, when `sunk`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ship: Option<SunkShip>,
+        /// Under `--reveal-sunk`, the untouched water around the ship the
+        /// shot sank, to be marked as misses.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        revealed: Vec<(usize, usize)>,
     },
     YourTurn,
     OpponentTurn,
@@ -43,8 +118,364 @@ pub enum Message {
     },
     PlayAgainTimeout,
     OpponentQuit,
+    /// The opponent's connection dropped mid-game. Answer with
+    /// `ClaimVictory` or `WaitForReconnect`.
+    OpponentDisconnected {
+        reconnect_window_secs: u64,
+    },
+    ClaimVictory,
+    WaitForReconnect,
     NewGameStart,
     Quit,
+    /// The server rejected the previous message.
+    Error {
+        code: ErrorCode,
+        detail: String,
+    },
+    /// A canned reaction, relayed to the opponent as-is.
+    Emote(Emote),
+    /// Something the weather did under the stormy-seas rule.
+    SeaEvent(SeaEvent),
+    /// Sent after `Hello` by a connection that wants to coach a player
+    /// instead of playing. `code` is the one that player got in `CoachCode`,
+    /// and picks both the game and the seat.
+    Coach {
+        code: String,
+    },
+    /// The code someone needs to coach this player, sent to them alone when
+    /// the game starts. Only share it with a coach you trust: they see your
+    /// fleet.
+    CoachCode {
+        code: String,
+    },
+    /// Sent after `Hello` by a connection that wants to watch the game's
+    /// events instead of playing. See `GameEvent`. `game` picks the game by
+    /// its number or room code; without it the observer joins the newest game.
+    Observe {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        game: Option<String>,
+    },
+    /// A private tip from a coach to the player they coach.
+    Hint {
+        text: String,
+    },
+    /// Sent after `Hello` instead of queueing for a stranger: asks for a
+    /// private room. Answered with `RoomCreated`.
+    CreateRoom,
+    /// The code another player can join the new room with.
+    RoomCreated {
+        code: String,
+    },
+    /// Sent after `Hello` to join the room with `code` while its host waits.
+    /// A player who dropped out of the room's game resumes with `ResumeGame`.
+    JoinRoom {
+        code: String,
+    },
+    /// Identifies the player's seat in the game that just started. Keep it to
+    /// send in `ResumeGame` if the connection drops.
+    SessionToken {
+        token: String,
+    },
+    /// Sent after `Hello` on a new connection to take back the seat `token`
+    /// was issued for. Answered with `GameResumed`, or a `SessionExpired` error.
+    ResumeGame {
+        token: String,
+    },
+    /// The state of a resumed game: the player's own board with the shots
+    /// taken at it, what they have seen of the enemy's board, and whose turn it is.
+    GameResumed {
+        own: Board,
+        enemy: Board,
+        your_turn: bool,
+    },
+    /// The disconnected opponent resumed the game, which carries on.
+    OpponentReconnected {
+        your_turn: bool,
+    },
+    /// A line of chat, relayed to the opponent after the server has
+    /// cleaned it up.
+    Chat {
+        text: String,
+    },
+    /// The opponent's display name, or `None` if they gave none. Sent when
+    /// a game starts and whenever a player takes a seat again.
+    OpponentName {
+        name: Option<String>,
+    },
+    /// Time left for the player on turn under `--turn-timeout`. Sent to both
+    /// players when a turn starts, again with `warning` set as time runs
+    /// short, and with 0 seconds left once it has run out.
+    TurnClock {
+        seconds_left: u64,
+        warning: bool,
+    },
+    /// Sent by the server every few seconds; answer with `Pong`.
+    Ping,
+    Pong,
+    /// How long the last `Ping` took to come back as a `Pong`, in
+    /// milliseconds, as the server measured it.
+    Latency {
+        rtt_ms: u64,
+    },
+    /// Sent by a server started with `--mines` when a player connects:
+    /// `PlaceShips` must also lay this many `Mine` cells.
+    Minefield {
+        mines: usize,
+    },
+    /// Sent by a server started with `--reveal-sunk` when a player connects:
+    /// ships may not touch, and sinking one reveals the water around it.
+    RevealSunk,
+    /// Sent by an AI opponent started with a thinking time when it starts
+    /// choosing its shot. Its `Attack` follows once it has made up its mind.
+    OpponentThinking,
+    /// A shot landed on a mine, and the blast struck a random ship cell of
+    /// the player who fired it. `yours` is true for that player; (x, y) is
+    /// the cell of their fleet that was hit.
+    MineDetonated {
+        yours: bool,
+        x: usize,
+        y: usize,
+        sunk: bool,
+        /// The ship the blast sank, when `sunk`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ship: Option<SunkShip>,
+    },
+    /// Offers the opponent a draw mid-battle; forwarded to them as-is.
+    /// Answer with `DrawResponse`.
+    DrawOffer,
+    /// The answer to the opponent's `DrawOffer`. A decline is passed back to
+    /// whoever offered; an acceptance ends the game with `GameDrawn`.
+    DrawResponse {
+        accept: bool,
+    },
+    /// Both players agreed to a draw. Sent in place of `GameOver`.
+    GameDrawn,
+    /// Asks to pause the battle, e.g. to take a phone call. Turn clocks stop
+    /// until either player sends `Unpause`.
+    Pause,
+    /// Sent to both players when the battle is paused; `by_you` is true for
+    /// whoever asked.
+    GamePaused {
+        by_you: bool,
+    },
+    /// Asks to carry on after a pause. Either player may send it.
+    Unpause,
+    /// Sent to both players when a paused battle carries on, followed by a
+    /// `TurnClock` with the time that was left under `--turn-timeout`.
+    GameUnpaused {
+        your_turn: bool,
+    },
+    /// Sent to both players when they are paired: who won the coin toss
+    /// and fires first. Rematches alternate from there.
+    CoinToss {
+        you_first: bool,
+    },
+    /// The score of a `--best-of` series from the receiving player's side,
+    /// sent after each game. Once either player has won most of `best_of`
+    /// games the series is decided, and `won` says whether it was theirs.
+    SeriesScore {
+        wins: u32,
+        losses: u32,
+        best_of: u32,
+        won: Option<bool>,
+    },
+    /// Sent to each player after `GameOver`: both fleets as they finished and
+    /// every shot of the game in order, for the client's post-game analysis.
+    GameReview {
+        your_board: Board,
+        opponent_board: Board,
+        shots: Vec<ReviewShot>,
+    },
+}
+
+impl Message {
+    /// Checks the first message on a connection: it must be a `Hello` in
+    /// this build's protocol version. Describes the mismatch otherwise.
+    pub fn check_hello(&self) -> Result<(), String> {
+        match self {
+            Message::Hello {
+                protocol_version, ..
+            } if *protocol_version == PROTOCOL_VERSION => Ok(()),
+            Message::Hello {
+                protocol_version, ..
+            } => Err(format!(
+                "server speaks protocol version {}, client speaks {}",
+                PROTOCOL_VERSION, protocol_version
+            )),
+            _ => Err(format!(
+                "expected Hello (protocol version {}) first, got {}; the client is older than the server",
+                PROTOCOL_VERSION,
+                self.name()
+            )),
+        }
+    }
+
+    /// Checks the password a `Hello` carries against the server's, if it
+    /// has one. Describes the mismatch otherwise.
+    pub fn check_password(&self, expected: Option<&str>) -> Result<(), &'static str> {
+        let given = match self {
+            Message::Hello { password, .. } => password.as_deref(),
+            _ => None,
+        };
+        match (expected, given) {
+            (None, _) => Ok(()),
+            (Some(expected), Some(given)) if given == expected => Ok(()),
+            (Some(_), Some(_)) => Err("wrong password"),
+            (Some(_), None) => Err("this server needs a password"),
+        }
+    }
+
+    /// A `Hello` in this build's protocol version.
+    pub fn hello(
+        player_name: Option<String>,
+        codec: Option<&str>,
+        compression: Option<&str>,
+        password: Option<String>,
+    ) -> Self {
+        Message::Hello {
+            protocol_version: PROTOCOL_VERSION,
+            player_name,
+            codec: codec.map(str::to_string),
+            compression: compression.map(str::to_string),
+            password,
+        }
+    }
+
+    /// The variant name, for logs and error details.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Message::Hello { .. } => "Hello",
+            Message::PlaceShips(_) => "PlaceShips",
+            Message::Attack { .. } => "Attack",
+            Message::AttackResult { .. } => "AttackResult",
+            Message::YourTurn => "YourTurn",
+            Message::OpponentTurn => "OpponentTurn",
+            Message::GameOver { .. } => "GameOver",
+            Message::WaitingForOpponent => "WaitingForOpponent",
+            Message::GameStart => "GameStart",
+            Message::PlayAgainRequest => "PlayAgainRequest",
+            Message::PlayAgainResponse { .. } => "PlayAgainResponse",
+            Message::PlayAgainTimeout => "PlayAgainTimeout",
+            Message::OpponentQuit => "OpponentQuit",
+            Message::OpponentDisconnected { .. } => "OpponentDisconnected",
+            Message::ClaimVictory => "ClaimVictory",
+            Message::WaitForReconnect => "WaitForReconnect",
+            Message::NewGameStart => "NewGameStart",
+            Message::Quit => "Quit",
+            Message::Error { .. } => "Error",
+            Message::Emote(_) => "Emote",
+            Message::SeaEvent(_) => "SeaEvent",
+            Message::Coach { .. } => "Coach",
+            Message::CoachCode { .. } => "CoachCode",
+            Message::Observe { .. } => "Observe",
+            Message::Hint { .. } => "Hint",
+            Message::CreateRoom => "CreateRoom",
+            Message::RoomCreated { .. } => "RoomCreated",
+            Message::JoinRoom { .. } => "JoinRoom",
+            Message::SessionToken { .. } => "SessionToken",
+            Message::ResumeGame { .. } => "ResumeGame",
+            Message::GameResumed { .. } => "GameResumed",
+            Message::OpponentReconnected { .. } => "OpponentReconnected",
+            Message::Chat { .. } => "Chat",
+            Message::OpponentName { .. } => "OpponentName",
+            Message::TurnClock { .. } => "TurnClock",
+            Message::Ping => "Ping",
+            Message::Pong => "Pong",
+            Message::Latency { .. } => "Latency",
+            Message::Minefield { .. } => "Minefield",
+            Message::RevealSunk => "RevealSunk",
+            Message::OpponentThinking => "OpponentThinking",
+            Message::MineDetonated { .. } => "MineDetonated",
+            Message::DrawOffer => "DrawOffer",
+            Message::DrawResponse { .. } => "DrawResponse",
+            Message::GameDrawn => "GameDrawn",
+            Message::Pause => "Pause",
+            Message::GamePaused { .. } => "GamePaused",
+            Message::Unpause => "Unpause",
+            Message::GameUnpaused { .. } => "GameUnpaused",
+            Message::CoinToss { .. } => "CoinToss",
+            Message::SeriesScore { .. } => "SeriesScore",
+            Message::GameReview { .. } => "GameReview",
+        }
+    }
+}
+
+/// One shot of a finished game in a `GameReview`, from the receiving
+/// player's side: `yours` is true for their own shots.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ReviewShot {
+    pub yours: bool,
+    pub x: usize,
+    pub y: usize,
+    pub hit: bool,
+    pub sunk: bool,
+}
+
+/// A ship named in the `AttackResult` that sank it, as listed in `SHIPS`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SunkShip {
+    pub name: String,
+    pub length: usize,
+}
+
+impl SunkShip {
+    /// What a sinking shot reports, from the engine's `Shot`.
+    pub fn from_shot(shot: &Shot) -> Option<Self> {
+        let (length, name) = SHIPS[shot.ship.filter(|_| shot.sunk)?];
+        Some(Self {
+            name: name.to_string(),
+            length,
+        })
+    }
+}
+
+/// Quick reactions sent with F1-F4, lighter-weight than chat.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Emote {
+    GoodShot,
+    Ouch,
+    GoodGame,
+    HurryUp,
+}
+
+impl Emote {
+    pub const ALL: [Emote; 4] = [
+        Emote::GoodShot,
+        Emote::Ouch,
+        Emote::GoodGame,
+        Emote::HurryUp,
+    ];
+
+    pub fn text(self) -> &'static str {
+        match self {
+            Emote::GoodShot => "Good shot!",
+            Emote::Ouch => "Ouch",
+            Emote::GoodGame => "GG",
+            Emote::HurryUp => "Hurry up",
+        }
+    }
+}
+
+/// An event from the optional stormy-seas rule, told from the receiving
+/// player's side: `yours` is true when it struck their own fleet or guns.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SeaEvent {
+    /// The result of the struck player's next shot arrives a turn late.
+    Fog { yours: bool },
+    /// One unhit ship drifted a cell. Only its owner learns which cells it
+    /// moved `from` and `to`; the opponent gets empty lists.
+    Current {
+        yours: bool,
+        from: Vec<(usize, usize)>,
+        to: Vec<(usize, usize)>,
+    },
+    /// A cell of the struck player's board was revealed to both players.
+    Squall {
+        yours: bool,
+        x: usize,
+        y: usize,
+        ship: bool,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -53,6 +484,76 @@ pub enum GamePhase {
     WaitingForOpponent,
     YourTurn,
     OpponentTurn,
+    Paused,
     GameOver,
     PlayAgainPrompt,
+    OpponentDisconnected,
+}
+
+/// Engine events streamed to observer connections, one JSON object per line.
+/// Players are numbered 1 and 2, matching the server logs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GameEvent {
+    /// `player` confirmed their fleet. Where it lies stays secret until the
+    /// game ends, when `FleetRevealed` shows it.
+    ShipsPlaced {
+        player: usize,
+    },
+    /// `player`'s fleet as the game that just ended left it, sent after
+    /// `GameOver` or `Draw`.
+    FleetRevealed {
+        player: usize,
+        grid: Board,
+    },
+    GameStarted {
+        first_player: usize,
+    },
+    Shot {
+        player: usize,
+        x: usize,
+        y: usize,
+        hit: bool,
+        sunk: bool,
+    },
+    TurnChanged {
+        player: usize,
+    },
+    GameOver {
+        winner: usize,
+    },
+    /// The players agreed to a draw.
+    Draw,
+    /// `player` paused the battle.
+    Paused {
+        player: usize,
+    },
+    /// `player` carried on after a pause.
+    Unpaused {
+        player: usize,
+    },
+    PlayAgainResponse {
+        player: usize,
+        wants_to_play: bool,
+    },
+    NewGame,
+    PlayerLeft {
+        player: usize,
+    },
+    /// `player` took back their seat with a session token after dropping.
+    PlayerResumed {
+        player: usize,
+    },
+    /// A stormy-seas event that struck `player`, as their opponent was told
+    /// it: a current's cells stay hidden.
+    SeaEvent {
+        player: usize,
+        event: SeaEvent,
+    },
+    /// A shot hit a mine and the blast struck `player`'s own fleet at (x, y).
+    MineDetonated {
+        player: usize,
+        x: usize,
+        y: usize,
+        sunk: bool,
+    },
 }
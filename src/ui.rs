@@ -2,51 +2,177 @@ use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
 };
+use std::time::Duration;
 
-use crate::game_state::GameState;
+use crate::analysis::Analysis;
+use crate::coords;
+use crate::game_state::{GameState, SidePanelMode};
+use crate::intel;
+use crate::notify::NotifyEvent;
 use crate::types::{CellState, GRID_SIZE, GamePhase, SHIPS};
 
+/// How long an opponent's emote stays on screen.
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+/// How often the ocean animation advances.
+const WAVE_STEP: Duration = Duration::from_millis(700);
+
+/// The title box, with the round trip to the server in its corner once the
+/// server has measured one.
+fn title_block(state: &GameState) -> Block<'static> {
+    let mut block = Block::default().borders(Borders::ALL);
+    if let Some(series) = state.series {
+        block = block.title(format!(
+            " 🏆 {}-{} · best of {} ",
+            series.wins, series.losses, series.best_of
+        ));
+    }
+    let Some(latency) = state.latency else {
+        return block;
+    };
+    let ms = latency.as_millis();
+    let color = match ms {
+        0..100 => Color::Green,
+        100..250 => Color::Yellow,
+        _ => Color::Red,
+    };
+    block.title(
+        Line::from(Span::styled(
+            format!(" 📶 {} ms ", ms),
+            Style::default().fg(color),
+        ))
+        .right_aligned(),
+    )
+}
+
 pub fn draw_ui(f: &mut Frame, state: &GameState) {
+    if state.show_result_screen
+        && let Some(won) = state.winner
+    {
+        draw_result_screen(f, state, won);
+        return;
+    }
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3),
+            Constraint::Length(4), // Title + status line
             Constraint::Min(0),
             Constraint::Length(8),
         ])
         .split(f.area());
 
     // Title + status line
-    let status_text = match state.phase {
-        GamePhase::Placing if state.placing_ship_idx < SHIPS.len() => {
-            let (len, name) = SHIPS[state.placing_ship_idx];
-            format!(
-                "Placing: {} (len {}) | Ships left: {}",
-                name,
-                len,
-                SHIPS.len() - state.placing_ship_idx
-            )
-        }
-        GamePhase::PlayAgainPrompt => "Do you want to play again? (Y/N)".to_string(),
-        GamePhase::GameOver => {
-            if let Some(won) = state.winner {
-                if won {
-                    "🎉 YOU WIN! 🎉".to_string()
+    let status_text = if let Some(password) = &state.password_draft {
+        format!(
+            "Password: {}_ (Enter = connect, Esc = quit)",
+            "*".repeat(password.chars().count())
+        )
+    } else if let Some(status) = &state.connection_status {
+        status.clone()
+    } else if let Some(hint) = &state.hint_draft {
+        format!("Hint: {}_ (Enter = send, Esc = cancel)", hint)
+    } else if let Some(line) = &state.chat_draft {
+        format!("Chat: {}_ (Enter = send, Esc = cancel)", line)
+    } else if state.coaching.is_some() {
+        let doing = match state.phase {
+            GamePhase::Placing => "placing ships",
+            GamePhase::WaitingForOpponent => "waiting for the opponent",
+            GamePhase::YourTurn => "their turn",
+            GamePhase::OpponentTurn => "opponent's turn",
+            GamePhase::Paused => "paused",
+            GamePhase::GameOver => match state.winner {
+                Some(true) => "they won",
+                Some(false) => "they lost",
+                None => "game over",
+            },
+            GamePhase::PlayAgainPrompt => "play again?",
+            GamePhase::OpponentDisconnected => "opponent disconnected",
+        };
+        format!("Coaching - {} | H: send a hint", doing)
+    } else {
+        match state.phase {
+            GamePhase::Placing if state.naming_ship.is_some() => format!(
+                "Ship name: {}_ (Enter = save, Esc = cancel)",
+                state.naming_ship.as_deref().unwrap_or_default()
+            ),
+            GamePhase::Placing if state.placing_ship_idx < SHIPS.len() => {
+                let ship = &state.ship_status[state.placing_ship_idx];
+                format!(
+                    "Placing: {} (len {}) | Ships left: {} | N: rename",
+                    ship.name,
+                    ship.length,
+                    SHIPS.len() - state.placing_ship_idx
+                )
+            }
+            GamePhase::Placing if state.mines_left() > 0 => format!(
+                "Laying mines: {} left | Enter: lay a mine on open water",
+                state.mines_left()
+            ),
+            GamePhase::Placing => {
+                "Fleet ready - Enter: confirm | H/V: mirror | Shift+arrows: shift".to_string()
+            }
+            GamePhase::YourTurn | GamePhase::OpponentTurn if state.draw_offered => {
+                format!("{} offers a draw - Y: accept, N: decline", state.opponent())
+            }
+            _ if state.viewing_turn.is_some() => format!(
+                "Viewing turn {} of {} - [ / ] to scrub, Esc for live",
+                state.viewing_turn.unwrap_or_default() + 1,
+                state.history.len()
+            ),
+            GamePhase::YourTurn if state.pending_shot.is_some() => {
+                let (x, y) = state.pending_shot.unwrap();
+                format!(
+                    "Fire at {}? Enter = fire, Esc = cancel",
+                    coords::format(x, y)
+                )
+            }
+            GamePhase::YourTurn if state.practice.is_some() => {
+                let hints_left = state.practice.as_ref().map_or(0, |p| p.hints_left);
+                format!("Your turn - H: hint ({} left)", hints_left)
+            }
+            GamePhase::YourTurn | GamePhase::OpponentTurn if state.turn_deadline.is_some() => {
+                format!(
+                    "{} - ⏱ {}s left",
+                    if state.phase == GamePhase::YourTurn {
+                        "Your turn".to_string()
+                    } else {
+                        format!("{}'s turn", state.opponent())
+                    },
+                    state.turn_seconds_left().unwrap_or_default()
+                )
+            }
+            GamePhase::Paused => "Paused - P: carry on".to_string(),
+            GamePhase::PlayAgainPrompt => state.play_again_question().to_string(),
+            GamePhase::OpponentDisconnected => {
+                format!(
+                    "{} disconnected - C: claim victory, W: wait for reconnect",
+                    state.opponent()
+                )
+            }
+            GamePhase::GameOver => {
+                if let Some(won) = state.winner {
+                    if won {
+                        "🎉 YOU WIN! 🎉".to_string()
+                    } else {
+                        "💀 YOU LOSE! 💀".to_string()
+                    }
+                } else if state.drawn {
+                    "🤝 Drawn by agreement".to_string()
                 } else {
-                    "💀 YOU LOSE! 💀".to_string()
+                    "Game Over".to_string()
                 }
-            } else {
-                "Game Over".to_string()
             }
+            _ => format!(
+                "Ships placed: {} / {}",
+                state.placing_ship_idx.min(SHIPS.len()),
+                SHIPS.len()
+            ),
         }
-        _ => format!(
-            "Ships placed: {} / {}",
-            state.placing_ship_idx.min(SHIPS.len()),
-            SHIPS.len()
-        ),
     };
+
     let title = Paragraph::new(format!("🚢 BATTLESHIP 🚢\n{}", status_text))
         .style(
             Style::default()
@@ -54,11 +180,17 @@ pub fn draw_ui(f: &mut Frame, state: &GameState) {
                 .add_modifier(Modifier::BOLD),
         )
         .alignment(Alignment::Center)
-        .block(Block::default().borders(Borders::ALL));
+        .block(title_block(state));
     f.render_widget(title, chunks[0]);
 
+    let (own_grid, enemy_grid) = state.displayed_grids();
+    let enemy_title = match &state.opponent_name {
+        Some(name) => format!("{}'s Waters", name),
+        None => "Enemy Waters".to_string(),
+    };
+
     // Game area - adjust layout based on side panel visibility
-    let game_area = if state.show_side_panel {
+    let game_area = if state.side_panel != SidePanelMode::Hidden {
         let main_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
@@ -76,23 +208,9 @@ pub fn draw_ui(f: &mut Frame, state: &GameState) {
         draw_side_panel(f, main_chunks[0], state);
 
         // Own grid
-        draw_grid(
-            f,
-            game_chunks[0],
-            &state.own_grid,
-            "Your Fleet",
-            state,
-            true,
-        );
+        draw_grid(f, game_chunks[0], own_grid, "Your Fleet", state, true);
         // Enemy grid
-        draw_grid(
-            f,
-            game_chunks[1],
-            &state.enemy_grid,
-            "Enemy Waters",
-            state,
-            false,
-        );
+        draw_grid(f, game_chunks[1], enemy_grid, &enemy_title, state, false);
 
         chunks[2] // Return messages area
     } else {
@@ -102,23 +220,9 @@ pub fn draw_ui(f: &mut Frame, state: &GameState) {
             .split(chunks[1]);
 
         // Own grid
-        draw_grid(
-            f,
-            game_chunks[0],
-            &state.own_grid,
-            "Your Fleet",
-            state,
-            true,
-        );
+        draw_grid(f, game_chunks[0], own_grid, "Your Fleet", state, true);
         // Enemy grid
-        draw_grid(
-            f,
-            game_chunks[1],
-            &state.enemy_grid,
-            "Enemy Waters",
-            state,
-            false,
-        );
+        draw_grid(f, game_chunks[1], enemy_grid, &enemy_title, state, false);
 
         chunks[2] // Return messages area
     };
@@ -133,6 +237,470 @@ pub fn draw_ui(f: &mut Frame, state: &GameState) {
         .collect();
     let msgs = List::new(msg_items).block(Block::default().borders(Borders::ALL).title("Messages"));
     f.render_widget(msgs, game_area);
+
+    if let Some((text, shown_at)) = &state.toast
+        && shown_at.elapsed() < TOAST_DURATION
+    {
+        draw_toast(f, chunks[1], text);
+    }
+
+    if state.phase == GamePhase::Paused {
+        draw_pause(f, state);
+    }
+
+    if state.show_readout {
+        draw_readout(f, state);
+    }
+
+    if state.show_analysis
+        && let Some(analysis) = &state.analysis
+    {
+        draw_analysis(f, analysis);
+    }
+
+    if state.show_help {
+        draw_help(f, state);
+    }
+
+    if state.show_perf {
+        draw_perf(f, state);
+    }
+}
+
+/// Hotseat privacy screen: hides both boards until `name` has the keyboard.
+pub fn draw_handover(f: &mut Frame, name: &str, note: Option<&str>) {
+    let mut lines = vec!["🚢 BATTLESHIP 🚢".to_string(), String::new()];
+    if let Some(note) = note {
+        lines.push(note.to_string());
+        lines.push(String::new());
+    }
+    lines.push(format!("Pass the keyboard to {}", name));
+    lines.push(String::new());
+    lines.push("Press Enter when the other player is not looking (Q quits)".to_string());
+
+    let area = f.area();
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let handover_area = Rect {
+        x: area.x,
+        y: area.y + (area.height - height) / 2,
+        width: area.width,
+        height,
+    };
+    let handover = Paragraph::new(lines.join("\n"))
+        .style(
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(handover, handover_area);
+}
+
+/// Box over the boards while the battle is paused.
+fn draw_pause(f: &mut Frame, state: &GameState) {
+    let by = if state.paused_by_you {
+        "You paused the game".to_string()
+    } else {
+        format!("{} paused the game", state.opponent())
+    };
+    let text = format!(
+        "⏸  PAUSED\n\n{}\nTurn clocks are stopped\n\nP: carry on   Q: quit",
+        by
+    );
+    let area = f.area();
+    let width = 40.min(area.width);
+    let height = 8.min(area.height);
+    let pause_area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let pause = Paragraph::new(text)
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(Clear, pause_area);
+    f.render_widget(pause, pause_area);
+}
+
+/// Full-screen plain-text description of both boards.
+fn draw_readout(f: &mut Frame, state: &GameState) {
+    let (own_grid, enemy_grid) = state.displayed_grids();
+    let mut lines = vec!["Your fleet:".to_string()];
+    lines.extend(GameState::describe_board(own_grid, true));
+    lines.push(String::new());
+    lines.push("Enemy waters:".to_string());
+    lines.extend(GameState::describe_board(enemy_grid, false));
+
+    let area = f.area();
+    let readout = Paragraph::new(lines.join("\n"))
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Board readout (D or Esc to close)"),
+        );
+    f.render_widget(Clear, area);
+    f.render_widget(readout, area);
+}
+
+/// Full-screen post-game analysis: the player's stats against a perfect game
+/// and the heatmap AI, and their shots on the enemy board coloured by how
+/// likely each cell was when they fired at it.
+fn draw_analysis(f: &mut Frame, analysis: &Analysis) {
+    let mut lines = vec![
+        Line::from(format!(
+            "Your shots: {}   Hits: {}   Accuracy: {:.0}%{}",
+            analysis.shots,
+            analysis.hits,
+            analysis.accuracy(),
+            if analysis.sank_fleet {
+                ""
+            } else {
+                "   (fleet not sunk)"
+            }
+        )),
+        Line::from(format!(
+            "A perfect game: {} shots   The heatmap AI on this fleet: {:.1} shots",
+            analysis.optimum, analysis.par
+        )),
+        Line::from(format!(
+            "Likeliest-cell shots: {}   Wasted shots: {}",
+            analysis.best_shots,
+            analysis.wasted.len()
+        )),
+        Line::from(format!(
+            "Opponent: {} shots, {} hits, {:.0}% accuracy",
+            analysis.opponent_shots,
+            analysis.opponent_hits,
+            analysis.opponent_accuracy()
+        )),
+        Line::from(""),
+    ];
+
+    let mut header = "   ".to_string();
+    for x in 0..GRID_SIZE {
+        header.push_str(&format!(" {} ", coords::column_label(x)));
+    }
+    lines.push(Line::from(header));
+    for y in 0..GRID_SIZE {
+        let mut row = vec![Span::raw(format!("{:>2} ", coords::row_label(y)))];
+        for x in 0..GRID_SIZE {
+            let (symbol, fg) = match analysis.board[y][x] {
+                CellState::Hit => ("X", Color::White),
+                CellState::Miss => ("·", Color::White),
+                CellState::Ship => ("■", Color::DarkGray),
+                CellState::Mine => ("✹", Color::Magenta),
+                CellState::Empty => (" ", Color::DarkGray),
+            };
+            let mut style = Style::default().fg(fg);
+            if let Some(share) = analysis.targeting[y][x] {
+                style = style.bg(targeting_color(share));
+            }
+            row.push(Span::styled(format!(" {} ", symbol), style));
+        }
+        lines.push(Line::from(row));
+    }
+    lines.push(Line::from(""));
+    let mut legend = vec![Span::raw("Likeliest ")];
+    for share in [1.0, 0.5, 0.2, 0.1, 0.0] {
+        legend.push(Span::styled(
+            "   ",
+            Style::default().bg(targeting_color(share)),
+        ));
+    }
+    legend.push(Span::raw(" wasted   ■ ship you never found"));
+    lines.push(Line::from(legend));
+
+    let area = f.area();
+    let screen = Paragraph::new(lines).alignment(Alignment::Center).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Post-game analysis (A or Esc to close)"),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(screen, area);
+}
+
+/// Background for a shot at a cell that was `share` as likely as the
+/// likeliest cell at the time.
+fn targeting_color(share: f64) -> Color {
+    match share {
+        s if s >= 1.0 => Color::Green,
+        s if s >= 0.5 => Color::LightGreen,
+        s if s >= 0.2 => Color::Yellow,
+        s if s > 0.0 => Color::LightRed,
+        _ => Color::Red,
+    }
+}
+
+/// Debug overlay in the bottom-right corner with the client loop timings.
+fn draw_perf(f: &mut Frame, state: &GameState) {
+    let rates = state.perf.latest;
+    let text = format!(
+        "Frame {:.1}ms avg / {:.1}ms max\nLoop {:.0}/s | Msgs {:.1}/s",
+        rates.avg_work_ms, rates.max_work_ms, rates.iterations_per_sec, rates.messages_per_sec
+    );
+    let area = f.area();
+    let width = 34.min(area.width);
+    let height = 4.min(area.height);
+    let perf_area = Rect {
+        x: area.x + area.width - width,
+        y: area.y + area.height - height,
+        width,
+        height,
+    };
+    let perf = Paragraph::new(text)
+        .style(Style::default().fg(Color::DarkGray))
+        .block(Block::default().borders(Borders::ALL).title("Perf (F12)"));
+    f.render_widget(Clear, perf_area);
+    f.render_widget(perf, perf_area);
+}
+
+const VICTORY_ART: &[&str] = &[
+    r"__     _____ ____ _____ ___  ______   __",
+    r"\ \   / /_ _/ ___|_   _/ _ \|  _ \ \ / /",
+    r" \ \ / / | | |     | || | | | |_) \ V / ",
+    r"  \ V /  | | |___  | || |_| |  _ < | |  ",
+    r"   \_/  |___\____| |_| \___/|_| \_\|_|  ",
+    r"",
+    r"         |    |    |                    ",
+    r"        )_)  )_)  )_)                   ",
+    r"       )___))___))___)\                 ",
+    r"      )____)____)_____)\\               ",
+    r"    _____|____|____|____\\\__           ",
+    r"    \                   /               ",
+    r"  ~~~~~~~~~~~~~~~~~~~~~~~~~~~~          ",
+];
+
+const DEFEAT_ART: &[&str] = &[
+    r" ____  _____ _____ _____    _  _____ ",
+    r"|  _ \| ____|  ___| ____|  / \|_   _|",
+    r"| | | |  _| | |_  |  _|   / _ \ | |  ",
+    r"| |_| | |___|  _| | |___ / ___ \| |  ",
+    r"|____/|_____|_|   |_____/_/   \_\_|  ",
+    r"",
+    r"             |                        ",
+    r"            )_)                       ",
+    r"        ___/___\___                   ",
+    r"        \   ~~~   /  ~   ~            ",
+    r"  ~~~~~~~\~~~~~~~/~~~~~~~~~~~         ",
+    r"      ~~~  ~ ~~~~   ~~  ~             ",
+];
+
+/// Full-screen art shown when a game ends, with the final stats, until a key is pressed.
+fn draw_result_screen(f: &mut Frame, state: &GameState, won: bool) {
+    let (art, color) = if won {
+        (VICTORY_ART, Color::Green)
+    } else {
+        (DEFEAT_ART, Color::Red)
+    };
+    let art_style = Style::default().fg(color).add_modifier(Modifier::BOLD);
+
+    // Pad to a common width so centring keeps the art's columns aligned
+    let width = art.iter().map(|line| line.len()).max().unwrap_or(0);
+    let mut lines: Vec<Line> = art
+        .iter()
+        .map(|line| Line::from(Span::styled(format!("{:<width$}", line), art_style)))
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(format!(
+        "Turns: {}   Shots: {}   Hits: {}   Accuracy: {:.0}%   Avg turn: {:.1}s",
+        state.turn_count,
+        state.total_shots,
+        state.total_hits,
+        state.get_accuracy(),
+        state.get_avg_turn_time()
+    )));
+    let streaks = &state.streaks;
+    lines.push(Line::from(format!(
+        "Best streak: {}   Longest drought: {}   Fastest sink: {}   First hit: {}",
+        streaks.best_hit_streak,
+        streaks.longest_drought,
+        turns_or_dash(streaks.fastest_sink),
+        turn_or_dash(streaks.first_hit_turn)
+    )));
+    lines.push(Line::from(""));
+    if state.analysis.is_some() {
+        lines.push(Line::from(Span::styled(
+            "A: post-game analysis",
+            Style::default().fg(Color::Cyan),
+        )));
+    }
+    lines.push(Line::from(Span::styled(
+        "Press any key to continue",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let area = f.area();
+    let height = (lines.len() as u16).min(area.height);
+    let top = Rect {
+        y: area.y + (area.height - height) / 2,
+        height,
+        ..area
+    };
+    let screen = Paragraph::new(lines).alignment(Alignment::Center);
+    f.render_widget(Block::default().borders(Borders::ALL), area);
+    f.render_widget(screen, top);
+}
+
+/// "T7"-style turn number, or "-" if it has not happened yet.
+fn turn_or_dash(turn: Option<usize>) -> String {
+    turn.map_or("-".to_string(), |turn| format!("T{}", turn))
+}
+
+/// A span of turns, or "-" if it has not happened yet.
+fn turns_or_dash(turns: Option<usize>) -> String {
+    match turns {
+        Some(1) => "1 turn".to_string(),
+        Some(turns) => format!("{} turns", turns),
+        None => "-".to_string(),
+    }
+}
+
+/// Glyph and colour for an empty cell. Unless reduced motion is on, a slow
+/// diagonal swell rolls across the water.
+fn water(state: &GameState, x: usize, y: usize) -> (&'static str, Style) {
+    if state.settings.reduced_motion {
+        return ("~", Style::default().fg(Color::Blue));
+    }
+    let tick = (state.animation_epoch.elapsed().as_millis() / WAVE_STEP.as_millis()) as usize;
+    match (x + y + GRID_SIZE * 2 - tick % (GRID_SIZE * 2)) % 6 {
+        0 => ("≈", Style::default().fg(Color::LightBlue)),
+        1 => ("~", Style::default().fg(Color::LightBlue)),
+        _ => ("~", Style::default().fg(Color::Blue)),
+    }
+}
+
+/// Plain-text rendering of a grid with axis labels, for sharing outside the game.
+pub fn grid_to_text(grid: &[Vec<CellState>], title: &str) -> String {
+    let mut text = format!("{}\n  ", title);
+    for x in 0..GRID_SIZE {
+        text.push_str(&format!("{:>2}", coords::column_label(x)));
+    }
+    text.push('\n');
+    for (y, row) in grid.iter().enumerate() {
+        text.push_str(&format!("{:>2}", coords::row_label(y)));
+        for cell in row {
+            let symbol = match cell {
+                CellState::Empty => '.',
+                CellState::Ship => '#',
+                CellState::Hit => 'X',
+                CellState::Miss => 'o',
+                CellState::Mine => '*',
+            };
+            text.push_str(&format!(" {}", symbol));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+const HELP_CONTROLS: &[(&str, &str)] = &[
+    ("Arrows", "Move cursor"),
+    ("Enter", "Place ship / lay mine / confirm fleet / fire"),
+    ("R", "Rotate ship"),
+    ("N", "Rename ship being placed"),
+    ("H / V", "Mirror fleet (coaches: H sends a hint)"),
+    ("Shift+Arrows", "Shift fleet"),
+    ("F1-F4", "Emotes"),
+    ("T", "Chat with your opponent"),
+    ("O", "Offer a draw (Y / N answers one)"),
+    ("P", "Pause the battle / carry on"),
+    ("F5 / F6 / F7", "Copy your board / enemy board / both"),
+    ("F8 / F9", "Save screen as text / ANSI"),
+    ("F12", "Performance overlay"),
+    ("[ / ]", "Scrub board history (opponent's turn)"),
+    ("S", "Cycle side panel"),
+    ("D", "Read out both boards as text"),
+    ("A", "Post-game analysis (once a game ends)"),
+    ("H", "Hint in practice mode (play-ai --hints)"),
+    ("?", "Toggle this help"),
+    ("Q", "Quit"),
+];
+
+/// Full-screen help listing the controls and the current notification settings.
+fn draw_help(f: &mut Frame, state: &GameState) {
+    let mut lines: Vec<Line> = vec![Line::from(Span::styled(
+        "Controls",
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    lines.extend(
+        HELP_CONTROLS
+            .iter()
+            .map(|(key, action)| Line::from(format!("  {:<14}{}", key, action))),
+    );
+
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Notifications",
+        Style::default().add_modifier(Modifier::BOLD),
+    )));
+    for event in NotifyEvent::ALL {
+        let alerts = event.alerts(&state.settings.notifications);
+        let channels: Vec<&str> = [
+            (alerts.bell, "bell"),
+            (alerts.desktop, "desktop"),
+            (alerts.title, "title"),
+        ]
+        .into_iter()
+        .filter_map(|(on, name)| on.then_some(name))
+        .collect();
+        let channels = if channels.is_empty() {
+            "off".to_string()
+        } else {
+            channels.join(", ")
+        };
+        lines.push(Line::from(format!("  {:<24}{}", event.label(), channels)));
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(
+        "  Change these in the settings file (see --settings).",
+    ));
+
+    let area = f.area();
+    let width = 60.min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let help_area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+    let help = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Help (? or Esc to close)"),
+    );
+    f.render_widget(Clear, help_area);
+    f.render_widget(help, help_area);
+}
+
+/// Draws a small box centred at the top of `area`.
+fn draw_toast(f: &mut Frame, area: Rect, text: &str) {
+    let width = (text.chars().count() as u16 + 4).min(area.width);
+    let toast_area = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y,
+        width,
+        height: 3.min(area.height),
+    };
+    let toast = Paragraph::new(text)
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::ALL));
+    f.render_widget(Clear, toast_area);
+    f.render_widget(toast, toast_area);
 }
 
 fn draw_grid(
@@ -172,21 +740,30 @@ fn draw_grid(
         return;
     }
 
+    // With the side panel open, dim enemy cells the intel rules out
+    let impossible =
+        (!is_own && state.side_panel != SidePanelMode::Hidden && state.viewing_turn.is_none())
+            .then(|| intel::deduce(&state.enemy_grid, &state.enemy_sinks).impossible);
+
     // Draw grid
-    for (y, _row) in grid.iter().enumerate().take(GRID_SIZE) {
-        for x in 0..GRID_SIZE {
+    for (y, row) in grid.iter().enumerate().take(GRID_SIZE) {
+        for (x, cell) in row.iter().enumerate().take(GRID_SIZE) {
             let cell_x = inner.x + 1 + (x as u16 + 1) * cell_width;
             let cell_y = inner.y + 1 + (y as u16) * cell_height;
 
             let cell_rect = Rect::new(cell_x, cell_y, cell_width, cell_height);
 
-            let (symbol, style) = match grid[y][x] {
-                CellState::Empty => ("~", Style::default().fg(Color::Blue)),
+            let (symbol, style) = match cell {
+                CellState::Empty if impossible.as_ref().is_some_and(|cells| cells[y][x]) => {
+                    ("·", Style::default().fg(Color::DarkGray))
+                }
+                CellState::Empty => water(state, x, y),
                 CellState::Ship => {
                     if is_own {
                         ("■", Style::default().fg(Color::Green))
                     } else {
-                        ("~", Style::default().fg(Color::Blue))
+                        // Sighted by a squall
+                        ("■", Style::default().fg(Color::Yellow))
                     }
                 }
                 CellState::Hit => (
@@ -194,21 +771,27 @@ fn draw_grid(
                     Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
                 ),
                 CellState::Miss => ("·", Style::default().fg(Color::DarkGray)),
+                CellState::Mine => ("✹", Style::default().fg(Color::Magenta)),
             };
 
             let mut cell_style = style;
+            if !is_own
+                && let Some(practice) = &state.practice
+                && practice.hint == Some((x, y))
+            {
+                cell_style = cell_style.bg(Color::Magenta);
+            }
             // Show cursor on appropriate grid based on phase
             if state.cursor == (x, y) {
                 match state.phase {
-                    GamePhase::Placing => {
-                        if is_own {
-                            cell_style = cell_style.bg(Color::Yellow);
-                        }
+                    GamePhase::Placing if is_own => {
+                        cell_style = cell_style.bg(Color::Yellow);
+                    }
+                    GamePhase::YourTurn if !is_own && state.pending_shot == Some((x, y)) => {
+                        cell_style = cell_style.bg(Color::Red);
                     }
-                    GamePhase::YourTurn => {
-                        if !is_own {
-                            cell_style = cell_style.bg(Color::Yellow);
-                        }
+                    GamePhase::YourTurn if !is_own => {
+                        cell_style = cell_style.bg(Color::Yellow);
                     }
                     _ => {}
                 }
@@ -222,7 +805,7 @@ fn draw_grid(
                     (state.placing_horizontal && y == cy && x >= cx && x < cx + length)
                         || (!state.placing_horizontal && x == cx && y >= cy && y < cy + length);
                 if in_preview {
-                    let valid = state.can_place_ship(cx, cy, length, state.placing_horizontal);
+                    let valid = state.can_place_ship(cx, cy, length);
                     cell_style = if valid {
                         Style::default().fg(Color::LightGreen).bg(Color::DarkGray)
                     } else {
@@ -240,8 +823,8 @@ fn draw_grid(
 
     // Draw coordinates
     for i in 0..GRID_SIZE {
-        // Horizontal axis: numbers 1-10
-        let x_label = Paragraph::new(format!("{}", i + 1)).alignment(Alignment::Center);
+        // Horizontal axis: column labels
+        let x_label = Paragraph::new(coords::column_label(i)).alignment(Alignment::Center);
         let x_rect = Rect::new(
             inner.x + 1 + (i as u16 + 1) * cell_width,
             inner.y,
@@ -250,9 +833,8 @@ fn draw_grid(
         );
         f.render_widget(x_label, x_rect);
 
-        // Vertical axis: letters A-J
-        let y_label =
-            Paragraph::new(format!("{}", (b'A' + i as u8) as char)).alignment(Alignment::Center);
+        // Vertical axis: row labels
+        let y_label = Paragraph::new(coords::row_label(i)).alignment(Alignment::Center);
         let y_rect = Rect::new(
             inner.x,
             inner.y + 1 + i as u16 * cell_height,
@@ -264,6 +846,11 @@ fn draw_grid(
 }
 
 fn draw_side_panel(f: &mut Frame, area: Rect, state: &GameState) {
+    if state.side_panel == SidePanelMode::EnemyFleet {
+        draw_enemy_fleet_panel(f, area, state);
+        return;
+    }
+
     // Note: Ship status should be updated before drawing
     // This is handled in the client when receiving attack results
 
@@ -272,6 +859,7 @@ fn draw_side_panel(f: &mut Frame, area: Rect, state: &GameState) {
         .constraints([
             Constraint::Length(12), // Ship status
             Constraint::Length(8),  // Stats
+            Constraint::Length(6),  // Enemy intel
             Constraint::Min(0),     // Spacer
         ])
         .split(area);
@@ -311,11 +899,25 @@ fn draw_side_panel(f: &mut Frame, area: Rect, state: &GameState) {
     let avg_time = state.get_avg_turn_time();
     let ships_sunk = state.get_ships_sunk();
 
+    let streaks = &state.streaks;
     let stats_text = format!(
         "Turns: {} | Avg Time: {:.1}s\n\
         Accuracy: {:.0}% | Sunk: {}/5\n\
-        Shots: {} | Hits: {}",
-        state.turn_count, avg_time, accuracy, ships_sunk, state.total_shots, state.total_hits
+        Shots: {} | Hits: {}\n\
+        Streak: {} | Best: {}\n\
+        Drought: {} | 1st hit: {}\n\
+        Fastest sink: {}",
+        state.turn_count,
+        avg_time,
+        accuracy,
+        ships_sunk,
+        state.total_shots,
+        state.total_hits,
+        streaks.hit_streak,
+        streaks.best_hit_streak,
+        streaks.longest_drought,
+        turn_or_dash(streaks.first_hit_turn),
+        turns_or_dash(streaks.fastest_sink)
     );
 
     let stats_block = Block::default()
@@ -332,10 +934,96 @@ fn draw_side_panel(f: &mut Frame, area: Rect, state: &GameState) {
         .block(stats_block);
     f.render_widget(stats_para, panel_chunks[1]);
 
-    // Help text
-    let help_text = "Press 'S' to toggle\nthis side panel";
+    // Enemy Intel Section
+    let intel = intel::deduce(&state.enemy_grid, &state.enemy_sinks);
+    let afloat = match (intel.afloat.last(), intel.afloat.first()) {
+        (Some(min), Some(max)) if min == max => {
+            format!("{} left, size {}", intel.afloat.len(), min)
+        }
+        (Some(min), Some(max)) => format!("{} left, size {}-{}", intel.afloat.len(), min, max),
+        _ => "none".to_string(),
+    };
+    let sunk = if intel.sunk.is_empty() {
+        "none".to_string()
+    } else {
+        join_lengths(&intel.sunk)
+    };
+    let intel_text = format!(
+        "Sunk: {}\nAfloat: {}\nNo-ship cells: {}",
+        sunk,
+        afloat,
+        intel.impossible_count()
+    );
+    let intel_block = Block::default()
+        .borders(Borders::ALL)
+        .title("🔎 Enemy Intel")
+        .title_style(
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        );
+    let intel_para = Paragraph::new(intel_text)
+        .style(Style::default().fg(Color::White))
+        .block(intel_block);
+    f.render_widget(intel_para, panel_chunks[2]);
+
+    draw_side_panel_help(f, panel_chunks[3]);
+}
+
+/// The enemy fleet, each ship crossed off once the server reports it sunk.
+fn draw_enemy_fleet_panel(f: &mut Frame, area: Rect, state: &GameState) {
+    let panel_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(12), // Enemy fleet
+            Constraint::Min(0),     // Spacer
+        ])
+        .split(area);
+
+    let sunk = |name: &str| state.enemy_ships_sunk.iter().any(|ship| ship.name == name);
+    let afloat = SHIPS.iter().filter(|(_, name)| !sunk(name)).count();
+    let mut fleet_lines: Vec<String> = SHIPS
+        .iter()
+        .map(|(length, name)| {
+            let ship_visual = "■".repeat(*length);
+            if sunk(name) {
+                format!("{}  ~~{}~~", ship_visual, name)
+            } else {
+                format!("{}  {}", ship_visual, name)
+            }
+        })
+        .collect();
+    fleet_lines.push(String::new());
+    fleet_lines.push(format!("{} of {} afloat", afloat, SHIPS.len()));
+    // Older servers report sinks without saying which ship went down
+    if state.enemy_ships_sunk.len() < state.enemy_sinks.len() {
+        fleet_lines.push("Some sinks unnamed".to_string());
+    }
+
+    let fleet_block = Block::default()
+        .borders(Borders::ALL)
+        .title("🎯 Enemy Fleet")
+        .title_style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
+    let fleet_para = Paragraph::new(fleet_lines.join("\n"))
+        .style(Style::default().fg(Color::White))
+        .block(fleet_block);
+    f.render_widget(fleet_para, panel_chunks[0]);
+
+    draw_side_panel_help(f, panel_chunks[1]);
+}
+
+fn draw_side_panel_help(f: &mut Frame, area: Rect) {
+    let help_text = "Press 'S' to cycle\nthis side panel\n'?' for help";
     let help_para = Paragraph::new(help_text)
         .style(Style::default().fg(Color::DarkGray))
         .alignment(Alignment::Center);
-    f.render_widget(help_para, panel_chunks[2]);
+    f.render_widget(help_para, area);
+}
+
+fn join_lengths(lengths: &[usize]) -> String {
+    lengths
+        .iter()
+        .map(|length| length.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
 }
@@ -0,0 +1,103 @@
+use rand::{
+    Rng,
+    seq::{IndexedRandom, SliceRandom},
+};
+
+use crate::engine::Board;
+use crate::types::{CellState, GRID_SIZE};
+
+type Cells = Vec<(usize, usize)>;
+
+/// The kinds of event the stormy-seas rule can throw at a player.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Weather {
+    Fog,
+    Current,
+    Squall,
+}
+
+/// Decides when the weather strikes. The server asks once per turn; where an
+/// event lands is worked out by the server from the boards.
+pub trait EventGenerator: Send {
+    fn next_event(&mut self) -> Option<Weather>;
+}
+
+/// Standard rules: the sea stays calm.
+pub struct CalmSeas;
+
+impl EventGenerator for CalmSeas {
+    fn next_event(&mut self) -> Option<Weather> {
+        None
+    }
+}
+
+/// `--stormy-seas`: every turn has the same chance of one random event.
+pub struct StormySeas {
+    chance: f64,
+}
+
+impl StormySeas {
+    pub fn new(chance: f64) -> Self {
+        Self { chance }
+    }
+}
+
+impl Default for StormySeas {
+    fn default() -> Self {
+        Self::new(0.15)
+    }
+}
+
+impl EventGenerator for StormySeas {
+    fn next_event(&mut self) -> Option<Weather> {
+        let mut rng = rand::rng();
+        if !rng.random_bool(self.chance) {
+            return None;
+        }
+        [Weather::Fog, Weather::Current, Weather::Squall]
+            .choose(&mut rng)
+            .copied()
+    }
+}
+
+/// Moves one unhit ship a cell in a random direction, onto water that has not
+/// been fired at. Returns its cells before and after, or `None` if no ship can move.
+pub fn drift(board: &mut Board, rng: &mut impl Rng) -> Option<(Cells, Cells)> {
+    let mut ships: Vec<Cells> = board
+        .ships()
+        .iter()
+        .filter(|ship| ship.hits == 0)
+        .map(|ship| ship.cells.clone())
+        .collect();
+    ships.shuffle(rng);
+    let mut directions = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+    for from in ships {
+        directions.shuffle(rng);
+        for (dx, dy) in directions {
+            let to: Option<Cells> = from
+                .iter()
+                .map(|&(x, y)| {
+                    let nx = x.checked_add_signed(dx).filter(|&nx| nx < GRID_SIZE)?;
+                    let ny = y.checked_add_signed(dy).filter(|&ny| ny < GRID_SIZE)?;
+                    (from.contains(&(nx, ny)) || board[ny][nx] == CellState::Empty)
+                        .then_some((nx, ny))
+                })
+                .collect();
+            if let Some(to) = to {
+                board.move_ship(&from, &to);
+                return Some((from, to));
+            }
+        }
+    }
+    None
+}
+
+/// A random cell that has not been fired at, for a squall to reveal.
+pub fn squall_cell(grid: &[Vec<CellState>], rng: &mut impl Rng) -> Option<(usize, usize)> {
+    let unshot: Vec<(usize, usize)> = (0..GRID_SIZE)
+        .flat_map(|y| (0..GRID_SIZE).map(move |x| (x, y)))
+        .filter(|&(x, y)| matches!(grid[y][x], CellState::Empty | CellState::Ship))
+        .collect();
+    unshot.choose(rng).copied()
+}
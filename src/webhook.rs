@@ -0,0 +1,89 @@
+use serde::Serialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::dashboard;
+use crate::types::GameEvent;
+
+static URL: OnceLock<String> = OnceLock::new();
+
+/// How long a POST may take, connecting included, before it is given up.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sets the URL that receives a POST after every finished game.
+pub fn set_url(url: String) {
+    let _ = URL.set(url);
+}
+
+/// The JSON body posted to the webhook.
+#[derive(Debug, Serialize)]
+struct GameResult<'a> {
+    /// The server's ID for the game, as in its log.
+    game: u64,
+    /// Display names, or "Player N" for a player who gave none.
+    players: &'a [String; 2],
+    /// 1 or 2, matching the server logs; `None` for a draw.
+    winner: Option<usize>,
+    /// "fleet_sunk", "mine", "forfeit", "turn_timeout" or "draw".
+    reason: &'a str,
+    turns: usize,
+    shots: [usize; 2],
+    hits: [usize; 2],
+    sunk: [usize; 2],
+}
+
+/// Posts the result of the game that just ended, built from its event history,
+/// on a background thread. Does nothing unless a webhook URL is configured.
+pub fn report(
+    game: u64,
+    players: &[String; 2],
+    winner: Option<usize>,
+    reason: &str,
+    history: &[GameEvent],
+) {
+    let Some(url) = URL.get() else {
+        return;
+    };
+
+    // Only count the latest game when several were played on one connection
+    let start = history
+        .iter()
+        .rposition(|event| matches!(event, GameEvent::NewGame))
+        .map_or(0, |i| i + 1);
+    let mut result = GameResult {
+        game,
+        players,
+        winner,
+        reason,
+        turns: 0,
+        shots: [0; 2],
+        hits: [0; 2],
+        sunk: [0; 2],
+    };
+    for event in &history[start..] {
+        if let GameEvent::Shot {
+            player, hit, sunk, ..
+        } = event
+        {
+            result.turns += 1;
+            result.shots[player - 1] += 1;
+            result.hits[player - 1] += *hit as usize;
+            result.sunk[player - 1] += *sunk as usize;
+        }
+    }
+
+    let body = match serde_json::to_value(&result) {
+        Ok(body) => body,
+        Err(e) => return dashboard::log(format!("Webhook payload error: {}", e)),
+    };
+    let url = url.clone();
+    std::thread::spawn(move || {
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(TIMEOUT))
+            .build()
+            .into();
+        if let Err(e) = agent.post(&url).send_json(&body) {
+            dashboard::log(format!("Webhook to {} failed: {}", url, e));
+        }
+    });
+}
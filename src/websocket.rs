@@ -0,0 +1,168 @@
+//! WebSocket framing, for browser clients and networks that only let web
+//! traffic through. [`Frames`] turns a WebSocket back into the stream the
+//! rest of the game reads and writes: a text message is one line of JSON and
+//! a binary message is one MessagePack frame, in both directions.
+
+use futures_util::{SinkExt, StreamExt};
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_tungstenite::{
+    WebSocketStream,
+    tungstenite::{self, Message, protocol::WebSocketConfig},
+};
+
+use crate::codec::{self, Framing};
+use crate::net::MAX_FRAME_LEN;
+
+/// A WebSocket read and written as a stream of frames.
+pub struct Frames<S> {
+    ws: WebSocketStream<S>,
+    /// The frame being handed out to the reader, with its framing.
+    incoming: Vec<u8>,
+    /// How much of `incoming` has been read already.
+    read: usize,
+    /// Bytes written since the last complete frame went out.
+    outgoing: Vec<u8>,
+}
+
+/// Completes the server side of the WebSocket handshake on `stream`.
+pub async fn accept<S>(stream: S) -> io::Result<Frames<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let ws = tokio_tungstenite::accept_async_with_config(stream, Some(config()))
+        .await
+        .map_err(io::Error::other)?;
+    Ok(Frames::new(ws))
+}
+
+/// Opens a WebSocket to `url` over the already connected `stream`.
+pub async fn connect<S>(url: &str, stream: S) -> Result<Frames<S>, tungstenite::Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (ws, _) = tokio_tungstenite::client_async_with_config(url, stream, Some(config())).await?;
+    Ok(Frames::new(ws))
+}
+
+/// Caps messages at the same size as frames over TCP.
+fn config() -> WebSocketConfig {
+    WebSocketConfig::default()
+        .max_message_size(Some(MAX_FRAME_LEN))
+        .max_frame_size(Some(MAX_FRAME_LEN))
+}
+
+impl<S> Frames<S> {
+    fn new(ws: WebSocketStream<S>) -> Self {
+        Self {
+            ws,
+            incoming: Vec::new(),
+            read: 0,
+            outgoing: Vec::new(),
+        }
+    }
+
+    /// Where the first complete frame in `outgoing` ends, if there is one.
+    fn frame_end(&self) -> Option<usize> {
+        let &first = self.outgoing.first()?;
+        match codec::for_frame(first).framing() {
+            Framing::Line => Some(self.outgoing.iter().position(|&b| b == b'\n')? + 1),
+            Framing::LengthPrefixed => {
+                let prefix = self.outgoing.first_chunk::<{ codec::LENGTH_PREFIX }>()?;
+                let end = codec::LENGTH_PREFIX + u32::from_be_bytes(*prefix) as usize;
+                (self.outgoing.len() >= end).then_some(end)
+            }
+        }
+    }
+}
+
+impl<S> AsyncRead for Frames<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        while self.read == self.incoming.len() {
+            let message = match ready!(self.ws.poll_next_unpin(cx)) {
+                Some(Ok(message)) => message,
+                Some(Err(tungstenite::Error::ConnectionClosed)) | None => {
+                    return Poll::Ready(Ok(()));
+                }
+                Some(Err(e)) => return Poll::Ready(Err(io::Error::other(e))),
+            };
+            let this = &mut *self;
+            this.incoming.clear();
+            this.read = 0;
+            match message {
+                // A message is one line, even if a browser pretty-printed it
+                Message::Text(text) => {
+                    this.incoming.extend(
+                        text.as_bytes().iter().map(
+                            |&b| {
+                                if b == b'\n' || b == b'\r' { b' ' } else { b }
+                            },
+                        ),
+                    );
+                    this.incoming.push(b'\n');
+                }
+                Message::Binary(bytes) => {
+                    this.incoming
+                        .extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+                    this.incoming.extend_from_slice(&bytes);
+                }
+                Message::Close(_) => return Poll::Ready(Ok(())),
+                _ => {}
+            }
+        }
+        let this = &mut *self;
+        let n = buf.remaining().min(this.incoming.len() - this.read);
+        buf.put_slice(&this.incoming[this.read..this.read + n]);
+        this.read += n;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> AsyncWrite for Frames<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.outgoing.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    /// Sends each complete frame written so far as a message.
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        while let Some(end) = this.frame_end() {
+            ready!(this.ws.poll_ready_unpin(cx)).map_err(io::Error::other)?;
+            let frame: Vec<u8> = this.outgoing.drain(..end).collect();
+            let message = match codec::for_frame(frame[0]).framing() {
+                Framing::Line => {
+                    Message::text(String::from_utf8_lossy(&frame[..end - 1]).into_owned())
+                }
+                Framing::LengthPrefixed => Message::binary(frame[codec::LENGTH_PREFIX..].to_vec()),
+            };
+            this.ws
+                .start_send_unpin(message)
+                .map_err(io::Error::other)?;
+        }
+        this.ws.poll_flush_unpin(cx).map_err(io::Error::other)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        self.ws.poll_close_unpin(cx).map_err(io::Error::other)
+    }
+}